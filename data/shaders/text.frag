@@ -0,0 +1,11 @@
+#version 400
+in vec2 vTexcoord;
+in vec4 vColor;
+
+uniform sampler2D fontTexture;
+
+out vec4 out_color;
+
+void main() {
+    out_color = texture(fontTexture, vTexcoord) * vColor;
+}
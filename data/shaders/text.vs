@@ -0,0 +1,16 @@
+#version 400
+
+in vec3 position;
+in vec2 texcoord;
+in vec4 color;
+
+uniform mat4 ProjMatrix;
+
+out vec2 vTexcoord;
+out vec4 vColor;
+
+void main() {
+    vTexcoord = texcoord;
+    vColor = color;
+    gl_Position = ProjMatrix * vec4(position, 1.0);
+}
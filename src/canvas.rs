@@ -1,10 +1,13 @@
 use renderer::mesh::*;
 use renderer::texture::*;
 use renderer::shader::*;
+use system::asset::Handle;
+use math::vec2::*;
 use math::vec3::*;
-use math::vec4::*;
 use math::mat4::*;
-use math::transform;
+use math::quat::*;
+use math::color::Color;
+use math::transform::Transform;
 
 
 static WIDGET_VERT_POS : [f32; 12] = [
@@ -25,28 +28,36 @@ static WIDGET_INDEX : [u32; 6] = [
     0, 1, 2, 0, 2, 3
 ];
 
-pub struct Widget<'a> {
-    position: (u32, u32),
-    size: (u32, u32),
+/// Uniforms set by the widgets, registered even if the shader doesn't use them
+static WIDGET_UNIFORMS: [&'static str; 4] = [
+    "ModelMatrix", "backColor", "canvasPosition", "canvasSize"
+];
+
+pub struct Widget {
+    position: Vec2,
+    size: Vec2,
     mesh: Mesh,
-    shader: &'a Program,
+    shader: Handle<Program>,
 
-    backColor: Vec4,
+    backColor: Color,
 
     model_matrix: Mat4,
     pos_changed: bool
 }
 
-impl<'a> Widget<'a> {
-    pub fn new(pos: (u32, u32), size: (u32, u32), shader_program: &'a Program) -> Widget {
+impl Widget {
+    pub fn new(pos: Vec2, size: Vec2, shader_program: Handle<Program>) -> Widget {
         let m = Mesh::new(&WIDGET_VERT_POS, &WIDGET_INDEX, Some(&WIDGET_VERT_TEX), None);
+        for name in WIDGET_UNIFORMS.iter() {
+            shader_program.get_mut().register_uniform(name);
+        }
 
         Widget {
             position: pos,
             size: size,
             mesh: m,
             shader: shader_program,
-            backColor: Vec4::new(0.9,0.9,0.9,1.0),
+            backColor: Color::new(0.9,0.9,0.9,1.0),
             model_matrix: Mat4::identity(),
             pos_changed: true
         }
@@ -55,10 +66,13 @@ impl<'a> Widget<'a> {
     fn update_model_matrix(&mut self) {
         if self.pos_changed {
             self.pos_changed = false;
-            self.model_matrix = transform::scale(Vec3::new(self.size.0 as f32, self.size.1 as f32, 1.0));
-            self.model_matrix *= transform::translation(Vec3::new(self.position.0 as f32, self.position.1 as f32, 0.0));
-            self.shader.bind();
-            self.shader.set_uniform_matrix4fv("ModelMatrix", &self.model_matrix);
+            let t = Transform::new(Vec3::new(self.position.x, self.position.y, 0.0),
+                                   Quat::identity(),
+                                   Vec3::new(self.size.x, self.size.y, 1.0));
+            self.model_matrix = t.to_mat4();
+            let shader = self.shader.get();
+            shader.bind();
+            shader.set_uniform_matrix4fv("ModelMatrix", &self.model_matrix);
         }
     }
 
@@ -67,23 +81,24 @@ impl<'a> Widget<'a> {
     }
 
     pub fn render(&self) {
-        self.shader.bind();
-        self.shader.set_uniform_4fv("backColor", &self.backColor);
-        self.shader.set_uniform_2fv("canvasPosition", (self.position.0 as f32, self.position.1 as f32));
-        self.shader.set_uniform_2fv("canvasSize", (self.size.0 as f32, self.size.1 as f32));
+        let shader = self.shader.get();
+        shader.bind();
+        shader.set_uniform_color("backColor", &self.backColor);
+        shader.set_uniform_2fv("canvasPosition", &self.position);
+        shader.set_uniform_2fv("canvasSize", &self.size);
         self.mesh.render();
     }
 }
 
-pub struct Canvas<'a> {
+pub struct Canvas {
     texture: Texture,
-    widget: Widget<'a>
+    widget: Widget
 }
 
-impl<'a> Canvas<'a> {
-    pub fn new(pos: (u32, u32), size: (u32, u32), shader_program: &'a Program) -> Canvas {
+impl Canvas {
+    pub fn new(pos: Vec2, size: Vec2, shader_program: Handle<Program>) -> Canvas {
         let w = Widget::new(pos, size, shader_program);
-        let t = Texture::from_empty(size, TextureFmt::RGB8U);
+        let t = Texture::from_empty((size.x as u32, size.y as u32), TextureFmt::RGB8U);
 
         Canvas {
             texture: t,
@@ -4,10 +4,1887 @@ pub mod renderer;
 
 #[cfg(test)]
 mod tests {
+    extern crate rustc_serialize;
+    extern crate glfw;
+
+    fn feq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    /// xorshift generator for the property tests, deterministic so that
+    /// failures can be reproduced
+    struct Rng(u32);
+
+    impl Rng {
+        fn next(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            self.0
+        }
+
+        fn range(&mut self, min: f32, max: f32) -> f32 {
+            min + (self.next() % 1000001) as f32 / 1000000.0 * (max - min)
+        }
+
+        fn vec3(&mut self, min: f32, max: f32) -> ::math::vec3::Vec3 {
+            ::math::vec3::Vec3::new(self.range(min, max), self.range(min, max), self.range(min, max))
+        }
+
+        /// random rotation, translation and non degenerate scale
+        fn transform(&mut self) -> ::math::mat4::Mat4 {
+            use math::quat::Quat;
+            use math::transform::Transform;
+            let axis = ::math::vec3::Vec3::normalize(self.vec3(-1.0, 1.0) + ::math::vec3::Vec3::new(0.0, 0.01, 0.0));
+            let rot = Quat::from_axis_angle(axis, self.range(-180.0, 180.0));
+            Transform::new(self.vec3(-10.0, 10.0), rot, self.vec3(0.5, 2.0)).to_mat4()
+        }
+    }
+
     #[test]
     fn it_works() {
     }
 
+    #[test]
+    fn mat4_tests() {
+        use math::vec3::Vec3;
+        use math::vec4::Vec4;
+        use math::mat4::Mat4;
+        use math::transform;
+
+        // the translation is in the last column, it moves points but not
+        // directions
+        let t = transform::translation(Vec3::new(1.0, 2.0, 3.0));
+        let p = t * Vec4::new(10.0, 20.0, 30.0, 1.0);
+        assert!(feq(p.x, 11.0) && feq(p.y, 22.0) && feq(p.z, 33.0) && feq(p.w, 1.0));
+        let d = t * Vec4::new(10.0, 20.0, 30.0, 0.0);
+        assert!(feq(d.x, 10.0) && feq(d.y, 20.0) && feq(d.z, 30.0) && feq(d.w, 0.0));
+
+        // (a * b) * v applies a first, then b
+        let s = transform::scale(Vec3::new(2.0, 2.0, 2.0));
+        let p = (s * t) * Vec4::new(0.0, 0.0, 0.0, 1.0);
+        assert!(feq(p.x, 1.0) && feq(p.y, 2.0) && feq(p.z, 3.0));
+        let p = (t * s) * Vec4::new(0.0, 0.0, 0.0, 1.0);
+        assert!(feq(p.x, 2.0) && feq(p.y, 4.0) && feq(p.z, 6.0));
+        assert_eq!(Mat4::identity() * Vec4::new(1.0, 2.0, 3.0, 4.0), Vec4::new(1.0, 2.0, 3.0, 4.0));
+
+        // persp maps the near and far planes to -1 and 1, and the edges of
+        // the horizontal fov to -1 and 1
+        let p = Mat4::persp(90.0, 2.0, 1.0, 10.0);
+        let ndc = |v: Vec4| Vec3::new(v.x / v.w, v.y / v.w, v.z / v.w);
+        assert!(feq(ndc(p * Vec4::new(0.0, 0.0, -1.0, 1.0)).z, -1.0));
+        assert!(feq(ndc(p * Vec4::new(0.0, 0.0, -10.0, 1.0)).z, 1.0));
+        assert!(feq(ndc(p * Vec4::new(5.0, 0.0, -5.0, 1.0)).x, 1.0));
+        assert!(feq(ndc(p * Vec4::new(0.0, 2.5, -5.0, 1.0)).y, 1.0));
+
+        // look_at puts the target in front of the camera, on the -Z axis of
+        // view space, and the up vector along +Y
+        let eye = Vec3::new(3.0, 4.0, 5.0);
+        let target = Vec3::new(-1.0, 0.5, 2.0);
+        let view = Mat4::look_at(eye, target, Vec3::new(0.0, 1.0, 0.0));
+        let t = view * Vec4::new(target.x, target.y, target.z, 1.0);
+        let dist = Vec3::length(target - eye);
+        assert!(feq(t.x, 0.0) && feq(t.y, 0.0) && feq(t.z, -dist));
+        let e = view * Vec4::new(eye.x, eye.y, eye.z, 1.0);
+        assert!(feq(e.x, 0.0) && feq(e.y, 0.0) && feq(e.z, 0.0));
+        let above = view * Vec4::new(eye.x, eye.y + 1.0, eye.z, 1.0);
+        assert!(above.y > 0.0);
+
+        // inverse undoes the matrix, and gives an empty matrix when singular
+        let m = transform::translation(Vec3::new(1.0, 2.0, 3.0)) * transform::scale(Vec3::new(2.0, 4.0, 0.5));
+        let i = Mat4::inverse(m) * m;
+        for c in 0..4 {
+            for r in 0..4 {
+                assert!(feq(i[c][r], Mat4::identity()[c][r]));
+            }
+        }
+        assert_eq!(Mat4::inverse(transform::scale(Vec3::new(1.0, 0.0, 1.0))), Mat4::empty());
+
+        // scalar division, in place or not
+        let mut m = transform::scale(Vec3::new(2.0, 4.0, 8.0));
+        m /= 2.0;
+        assert_eq!(m, transform::scale(Vec3::new(2.0, 4.0, 8.0)) / 2.0);
+        assert_eq!(m[1][1], 2.0);
+        assert_eq!(m[3][3], 0.5);
+    }
+
+    #[test]
+    fn quat_tests() {
+        use math::vec3::Vec3;
+        use math::mat4::Mat4;
+        use math::quat::Quat;
+        use math::transform;
+
+        // axis angle rotates like the rotation matrix
+        let q = Quat::from_axis_angle(Vec3::z(), 90.0);
+        let v = q * Vec3::x();
+        assert!(feq(v.x, 0.0) && feq(v.y, 1.0) && feq(v.z, 0.0));
+
+        let mq = Quat::to_mat4(q);
+        let mr = transform::rotate(90.0, Vec3::z());
+        for c in 0..4 {
+            for r in 0..4 {
+                assert!(feq(mq[c][r], mr[c][r]));
+            }
+        }
+
+        // composition matches matrix multiplication order
+        let a = Quat::from_axis_angle(Vec3::new(1.0, 2.0, 3.0), 40.0);
+        let b = Quat::from_euler(10.0, 20.0, 30.0);
+        let mab = Quat::to_mat4(a * b);
+        let mm: Mat4 = Quat::to_mat4(a) * Quat::to_mat4(b);
+        for c in 0..4 {
+            for r in 0..4 {
+                assert!(feq(mab[c][r], mm[c][r]));
+            }
+        }
+
+        // mat4 round trip, q and -q being the same rotation
+        let back = Quat::from_mat4(mab);
+        assert!(feq(Quat::dot(back, a * b).abs(), 1.0));
+
+        // euler round trip
+        let e = Quat::to_euler(b);
+        assert!(feq(e.x, 10.0) && feq(e.y, 20.0) && feq(e.z, 30.0));
+
+        // slerp halfway between identity and 90deg is 45deg
+        let s = Quat::slerp(Quat::identity(), q, 0.5);
+        let h = Quat::from_axis_angle(Vec3::z(), 45.0);
+        assert!(feq(Quat::dot(s, h), 1.0));
+        let n = Quat::nlerp(Quat::identity(), q, 0.5);
+        assert!(feq(Quat::dot(n, h), 1.0));
+        assert!(feq(Quat::length(n), 1.0));
+
+        // inverse cancels out
+        let i = a * Quat::inverse(a);
+        assert!(feq(i.w.abs(), 1.0));
+    }
+
+    #[test]
+    fn mat2_mat3_tests() {
+        use math::vec2::Vec2;
+        use math::vec3::Vec3;
+        use math::mat2::Mat2;
+        use math::mat3::Mat3;
+        use math::transform;
+
+        // 2D affine : scale, then rotate, then translate
+        let m = transform::scale_2d(Vec2::new(2.0, 2.0)) *
+                transform::rotate_2d(90.0) *
+                transform::translation_2d(Vec2::new(10.0, 0.0));
+        let p = m * Vec3::new(1.0, 0.0, 1.0);
+        assert!(feq(p.x, 10.0) && feq(p.y, 2.0) && feq(p.z, 1.0));
+
+        let i = m * Mat3::inverse(m);
+        for c in 0..3 {
+            for r in 0..3 {
+                assert!(feq(i[c][r], if c == r { 1.0 } else { 0.0 }));
+            }
+        }
+
+        let r = Mat2::rotation(90.0) * Vec2::x();
+        assert!(feq(r.x, 0.0) && feq(r.y, 1.0));
+        let i2 = Mat2::rotation(30.0) / Mat2::rotation(30.0);
+        assert!(feq(i2[0][0], 1.0) && feq(i2[1][0], 0.0));
+
+        // normals stay orthogonal to surfaces under non uniform scaling
+        let s = transform::scale(Vec3::new(4.0, 1.0, 1.0));
+        let nm = Mat3::normal_matrix(&s);
+        let n = Vec3::normalize(nm * Vec3::normalize(Vec3::new(1.0, 1.0, 0.0)));
+        let tangent = Mat3::from_mat4(&s) * Vec3::new(1.0, -1.0, 0.0);
+        assert!(feq(Vec3::dot(n, tangent), 0.0));
+    }
+
+    #[test]
+    fn geometry_tests() {
+        use math::vec3::Vec3;
+        use math::mat4::Mat4;
+        use math::geometry::*;
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -2.0));
+
+        let plane = Plane::from_point_normal(Vec3::empty(), Vec3::z());
+        assert!(feq(ray.intersect_plane(&plane).unwrap(), 10.0));
+
+        let aabb = Aabb::from_points(&[Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0)]);
+        assert!(feq(ray.intersect_aabb(&aabb).unwrap(), 9.0));
+        let miss = Ray::new(Vec3::new(5.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(miss.intersect_aabb(&aabb).is_none());
+
+        let sphere = Sphere::new(Vec3::new(0.0, 0.0, 2.0), 1.0);
+        assert!(feq(ray.intersect_sphere(&sphere).unwrap(), 7.0));
+        assert!(miss.intersect_sphere(&sphere).is_none());
+        assert!(sphere.intersects_aabb(&aabb));
+
+        let (t, u, v) = ray.intersect_triangle(Vec3::new(-1.0, -1.0, 0.0),
+                                               Vec3::new(1.0, -1.0, 0.0),
+                                               Vec3::new(0.0, 1.0, 0.0)).unwrap();
+        assert!(feq(t, 10.0) && feq(u, 0.25) && feq(v, 0.5));
+        assert!(miss.intersect_triangle(Vec3::new(-1.0, -1.0, 0.0),
+                                        Vec3::new(1.0, -1.0, 0.0),
+                                        Vec3::new(0.0, 1.0, 0.0)).is_none());
+
+        // camera at the origin looking down -z
+        let persp = Frustum::from_matrix(&Mat4::persp(90.0, 1.0, 1.0, 100.0), DepthRange::MinusOneToOne);
+        assert!(persp.contains_point(Vec3::new(0.0, 0.0, -50.0)));
+        assert!(!persp.contains_point(Vec3::new(0.0, 0.0, -0.5)));
+        assert!(!persp.contains_point(Vec3::new(0.0, 0.0, -101.0)));
+        assert!(!persp.contains_point(Vec3::new(20.0, 0.0, -10.0)));
+        assert!(feq(persp.planes[4].distance(Vec3::new(0.0, 0.0, -1.0)), 0.0));
+
+        let inside = Aabb::new(Vec3::new(-1.0, -1.0, -11.0), Vec3::new(1.0, 1.0, -9.0));
+        let straddle = Aabb::new(Vec3::new(-1.0, -1.0, -2.0), Vec3::new(1.0, 1.0, 0.0));
+        let behind = Aabb::new(Vec3::new(-1.0, -1.0, 1.0), Vec3::new(1.0, 1.0, 3.0));
+        assert_eq!(persp.classify_aabb(&inside), Containment::Inside);
+        assert_eq!(persp.classify_aabb(&straddle), Containment::Intersecting);
+        assert_eq!(persp.classify_aabb(&behind), Containment::Outside);
+
+        let ortho = Frustum::from_matrix(&Mat4::ortho(0.0, 100.0, 100.0, 0.0, 0.1, 10.0), DepthRange::ZeroToOne);
+        assert!(ortho.contains_point(Vec3::new(50.0, 50.0, -5.0)));
+        assert!(!ortho.contains_point(Vec3::new(150.0, 50.0, -5.0)));
+        assert!(feq(ortho.planes[4].distance(Vec3::new(0.0, 0.0, -0.1)), 0.0));
+        assert!(feq(ortho.planes[5].distance(Vec3::new(0.0, 0.0, -10.0)), 0.0));
+    }
+
+    #[test]
+    fn transform_tests() {
+        use math::vec3::Vec3;
+        use math::vec4::Vec4;
+        use math::mat4::Mat4;
+        use math::quat::Quat;
+        use math::transform::{self, Transform};
+
+        let t = Transform::new(Vec3::new(1.0, 2.0, 3.0),
+                               Quat::from_axis_angle(Vec3::new(0.0, 1.0, 1.0), 60.0),
+                               Vec3::new(2.0, 3.0, 0.5));
+        let m = t.to_mat4();
+
+        // same as composing the matrices by hand
+        let by_hand = transform::scale(t.scale) * Quat::to_mat4(t.rotation) * transform::translation(t.translation);
+        for c in 0..4 {
+            for r in 0..4 {
+                assert!(feq(m[c][r], by_hand[c][r]));
+            }
+        }
+
+        let p = Vec3::new(-1.0, 0.5, 4.0);
+        let mp = m * Vec4::new(p.x, p.y, p.z, 1.0);
+        let tp = t.transform_point(p);
+        assert!(feq(mp.x, tp.x) && feq(mp.y, tp.y) && feq(mp.z, tp.z));
+
+        // decomposition round trip, including a reflection
+        let d = Transform::from_mat4(&m);
+        assert!(feq(d.scale.x, 2.0) && feq(d.scale.y, 3.0) && feq(d.scale.z, 0.5));
+        assert!(feq(Quat::dot(d.rotation, t.rotation).abs(), 1.0));
+        assert!(feq(d.translation.z, 3.0));
+        let mirrored = Transform::new(t.translation, t.rotation, Vec3::new(-2.0, 3.0, 0.5));
+        let dm = Transform::from_mat4(&mirrored.to_mat4()).to_mat4();
+        let mm = mirrored.to_mat4();
+        for c in 0..4 {
+            for r in 0..4 {
+                assert!(feq(dm[c][r], mm[c][r]));
+            }
+        }
+
+        // inverses
+        let i = m * t.inverse_mat4();
+        let id = Mat4::identity();
+        for c in 0..4 {
+            for r in 0..4 {
+                assert!(feq(i[c][r], id[c][r]));
+            }
+        }
+        let u = Transform::new(t.translation, t.rotation, Vec3::new(2.0, 2.0, 2.0));
+        let back = u.inverse().transform_point(u.transform_point(p));
+        assert!(feq(back.x, p.x) && feq(back.y, p.y) && feq(back.z, p.z));
+
+        // normals stay orthogonal to transformed tangents
+        let n = t.transform_normal(Vec3::normalize(Vec3::new(1.0, 1.0, 0.0)));
+        let tangent = t.transform_direction(Vec3::new(1.0, -1.0, 0.0));
+        assert!(feq(Vec3::dot(n, tangent), 0.0));
+        let nm = t.normal_matrix() * Vec3::new(1.0, 1.0, 0.0);
+        assert!(feq(Vec3::dot(Vec3::normalize(nm), n), 1.0));
+    }
+
+    #[test]
+    fn camera_tests() {
+        use math::vec2::Vec2;
+        use math::vec3::Vec3;
+        use math::mat4::Mat4;
+        use math::geometry::Sphere;
+        use renderer::camera::Camera;
+
+        let mut cam = Camera::perspective(90.0, Vec2::new(800.0, 600.0), 0.1, 100.0);
+        cam.set_position(Vec3::new(0.0, 0.0, 10.0));
+        cam.look_at(Vec3::empty(), Vec3::y());
+
+        // same view as Mat4::look_at
+        let v = Mat4::look_at(Vec3::new(0.0, 0.0, 10.0), Vec3::empty(), Vec3::y());
+        for c in 0..4 {
+            for r in 0..4 {
+                assert!(feq(cam.view_matrix()[c][r], v[c][r]));
+            }
+        }
+
+        // the target is at the center of the screen
+        let s = cam.world_to_screen(Vec3::empty()).unwrap();
+        assert!(feq(s.x, 400.0) && feq(s.y, 300.0));
+        assert!(cam.world_to_screen(Vec3::new(0.0, 0.0, 20.0)).is_none());
+
+        // unproject goes back to the world position
+        let p = Vec3::new(1.0, 2.0, -3.0);
+        let sp = cam.world_to_screen(p).unwrap();
+        let wp = cam.unproject(sp);
+        assert!((wp.x - p.x).abs() < 1e-2 && (wp.y - p.y).abs() < 1e-2 && (wp.z - p.z).abs() < 1e-2);
+
+        // a ray through the center pixel hits the target
+        let ray = cam.screen_to_world_ray(Vec2::new(400.0, 300.0));
+        let t = ray.intersect_sphere(&Sphere::new(Vec3::empty(), 1.0)).unwrap();
+        assert!((t - 8.9).abs() < 1e-3);
+
+        // upper-left pixel goes up and left
+        let ray = cam.screen_to_world_ray(Vec2::new(0.0, 0.0));
+        assert!(ray.dir.x < 0.0 && ray.dir.y > 0.0 && ray.dir.z < 0.0);
+
+        // 2D camera keeps pixel coordinates after a resize
+        let mut cam2d = Camera::orthographic(Vec2::new(800.0, 600.0), 0.01, 100.0);
+        cam2d.resize(1024, 768);
+        let s = cam2d.world_to_screen(Vec3::new(1000.0, 700.0, -1.0)).unwrap();
+        assert!((s.x - 1000.0).abs() < 1e-2 && (s.y - 700.0).abs() < 1e-2);
+        let w = cam2d.unproject(Vec3::new(10.0, 20.0, 0.5));
+        assert!((w.x - 10.0).abs() < 1e-3 && (w.y - 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn controller_tests() {
+        use tests::glfw::Key;
+        use math::vec2::Vec2;
+        use math::vec3::Vec3;
+        use renderer::controller::*;
+        use system::config::Config;
+
+        fn veq(a: Vec3, b: Vec3) -> bool {
+            feq(a.x, b.x) && feq(a.y, b.y) && feq(a.z, b.z)
+        }
+
+        // key names as written in config files
+        assert_eq!(key_from_name("W"), Some(Key::W));
+        assert_eq!(key_from_name("7"), Some(Key::Num7));
+        assert_eq!(key_from_name("LeftShift"), Some(Key::LeftShift));
+        assert_eq!(key_from_name("w"), None);
+        assert_eq!(key_from_name(""), None);
+
+        let config = |json: &str| json.parse::<Config>().unwrap();
+
+        // typos and missing keys fall back to the default bindings
+        let conf = config("{ \"input\": { \"bindings\": { \"forward\": \"Up\", \"left\": \"Lfet\", \"back\": 3 } } }");
+        let mut bindings = CameraBindings::from_config(&conf);
+        assert_eq!(bindings.forward, Key::Up);
+        assert_eq!(bindings.left, Key::A);
+        assert_eq!(bindings.back, Key::S);
+        assert_eq!(bindings.down, Key::Q);
+        assert_eq!(bindings.direction(|key| key == Key::Up || key == Key::D), Vec3::new(1.0, 0.0, -1.0));
+        assert_eq!(bindings.direction(|key| key == Key::E || key == Key::Q), Vec3::empty());
+
+        // the bindings follow the config
+        bindings.apply_config(&config("{ \"input\": { \"bindings\": { \"forward\": \"I\", \"left\": \"J\" } } }"));
+        assert_eq!(bindings.forward, Key::I);
+        assert_eq!(bindings.left, Key::J);
+        assert_eq!(bindings.back, Key::S);
+
+        // an empty config runs with the built-in values, and the speeds
+        // follow the config as well
+        let empty = config("{}");
+        let mut fps = FpsController::new(&empty);
+        assert_eq!(fps.move_speed, DEFAULT_MOVE_SPEED as f32);
+        fps.apply_config(&config("{ \"camera\": { \"move_speed\": 20.0 }, \"input\": { \"bindings\": { \"up\": \"Space\" } } }"));
+        assert_eq!(fps.move_speed, 20.0);
+        assert_eq!(fps.bindings.up, Key::Space);
+        fps.apply_config(&empty);
+        assert_eq!(fps.move_speed, DEFAULT_MOVE_SPEED as f32);
+        assert_eq!(fps.bindings.up, Key::E);
+
+        fps.look(Vec2::new(100.0, 1000.0));
+        assert!(feq(fps.yaw, -15.0) && feq(fps.pitch, -89.0));
+
+        // forward follows the yaw, up stays world aligned
+        fps.pitch = 0.0;
+        fps.yaw = 90.0;
+        let pos = fps.step(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), 2.0);
+        assert!(veq(pos, Vec3::new(-9.0, 0.0, 0.0)));
+        fps.pitch = 45.0;
+        assert!(veq(fps.step(Vec3::empty(), Vec3::new(0.0, 1.0, 0.0), 1.0), Vec3::new(0.0, 5.0, 0.0)));
+        assert_eq!(fps.step(pos, Vec3::empty(), 1.0), pos);
+
+        let mut orbit = OrbitController::new(&empty, Vec3::new(1.0, 2.0, 3.0), 10.0);
+        assert!(veq(orbit.eye_position(), Vec3::new(1.0, 2.0, 13.0)));
+        orbit.zoom(1.0);
+        assert!(feq(orbit.distance, 9.0));
+        orbit.zoom(100.0);
+        assert!(feq(orbit.distance, 0.01));
+        orbit.distance = 10.0;
+        orbit.pan(Vec2::new(100.0, 0.0));
+        assert!(veq(orbit.target, Vec3::new(-1.0, 2.0, 3.0)));
+        orbit.orbit(Vec2::new(0.0, -1000.0));
+        assert!(feq(orbit.pitch, 89.0));
+        assert!(orbit.eye_position().y < orbit.target.y - 9.9);
+
+        // zooming keeps the world point under the cursor
+        let pz = PanZoomController::new(&empty);
+        let cursor = Vec2::new(100.0, 50.0);
+        let (pos, zoom) = pz.zoom_at(Vec2::new(10.0, 20.0), 2.0, cursor, 1.0);
+        assert!(feq(zoom, 2.2));
+        let world = pos + cursor / zoom;
+        assert!(feq(world.x, 60.0) && feq(world.y, 45.0));
+        assert_eq!(pz.zoom_at(pos, zoom, cursor, 1000.0).1, pz.max_zoom);
+        assert_eq!(pz.zoom_at(pos, zoom, cursor, -1000.0).1, pz.min_zoom);
+    }
+
+    #[test]
+    fn interp_tests() {
+        use math::vec3::Vec3;
+        use math::interp::*;
+
+        assert!(feq(lerp(2.0, 4.0, 0.25), 2.5));
+        assert!(feq(inverse_lerp(2.0, 4.0, 2.5), 0.25));
+        assert!(feq(remap(5.0, 0.0, 10.0, 100.0, 200.0), 150.0));
+        assert!(feq(smoothstep(0.0, 1.0, 0.5), 0.5));
+        assert!(feq(smoothstep(0.0, 1.0, 2.0), 1.0));
+        let v = lerp(Vec3::empty(), Vec3::new(2.0, 4.0, 6.0), 0.5);
+        assert!(feq(v.x, 1.0) && feq(v.y, 2.0) && feq(v.z, 3.0));
+
+        let all = [Easing::Linear, Easing::InQuad, Easing::OutQuad, Easing::InOutQuad,
+                   Easing::InCubic, Easing::OutCubic, Easing::InOutCubic,
+                   Easing::InQuart, Easing::OutQuart, Easing::InOutQuart,
+                   Easing::InQuint, Easing::OutQuint, Easing::InOutQuint,
+                   Easing::InSine, Easing::OutSine, Easing::InOutSine,
+                   Easing::InExpo, Easing::OutExpo, Easing::InOutExpo,
+                   Easing::InCirc, Easing::OutCirc, Easing::InOutCirc,
+                   Easing::InBack, Easing::OutBack, Easing::InOutBack,
+                   Easing::InElastic, Easing::OutElastic, Easing::InOutElastic,
+                   Easing::InBounce, Easing::OutBounce, Easing::InOutBounce];
+        for e in all.iter() {
+            assert!((e.apply(0.0)).abs() < 1e-3, "{:?}", e);
+            assert!((e.apply(1.0) - 1.0).abs() < 1e-3, "{:?}", e);
+        }
+        assert!(feq(Easing::InOutCubic.apply(0.5), 0.5));
+
+        let a = Vec3::empty();
+        let b = Vec3::new(1.0, 2.0, 0.0);
+        let c = Vec3::new(3.0, 2.0, 0.0);
+        let d = Vec3::new(4.0, 0.0, 0.0);
+        let mid = cubic_bezier(a, b, c, d, 0.5);
+        assert!(feq(mid.x, 2.0) && feq(mid.y, 1.5));
+        let q = quadratic_bezier(a, b, d, 1.0);
+        assert!(feq(q.x, 4.0) && feq(q.y, 0.0));
+
+        // catmull-rom passes through its points
+        let spline = CatmullRom::new(vec![a, b, c, d]);
+        let p = spline.eval(1.0 / 3.0);
+        assert!(feq(p.x, b.x) && feq(p.y, b.y));
+        let p = spline.eval(1.0);
+        assert!(feq(p.x, d.x) && feq(p.y, d.y));
+
+        // arc-length on a straight line is exact
+        let line = CubicBezier { points: [a, Vec3::new(0.1, 0.0, 0.0), Vec3::new(0.2, 0.0, 0.0), Vec3::new(10.0, 0.0, 0.0)] };
+        let table = ArcLength::new(&line, 256);
+        assert!((table.total_length() - 10.0).abs() < 1e-3);
+        let samples = sample_uniform(&line, 11);
+        assert_eq!(samples.len(), 11);
+        for (i, s) in samples.iter().enumerate() {
+            assert!((s.x - i as f32).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn noise_tests() {
+        use math::vec2::Vec2;
+        use math::vec3::Vec3;
+        use math::vec4::Vec4;
+        use math::noise::*;
+
+        let n = Noise::new(42);
+        let m = Noise::new(42);
+        let o = Noise::new(7);
+
+        // deterministic for a given seed
+        let p = Vec3::new(1.3, 4.7, -2.1);
+        assert_eq!(n.perlin3(p), m.perlin3(p));
+        assert_eq!(n.simplex3(p), m.simplex3(p));
+        assert!(n.simplex3(p) != o.simplex3(p));
+
+        // perlin is zero on the lattice
+        assert!(feq(n.perlin2(Vec2::new(3.0, -5.0)), 0.0));
+        assert!(feq(n.perlin3(Vec3::new(3.0, -5.0, 2.0)), 0.0));
+        assert!(feq(n.perlin4(Vec4::new(3.0, -5.0, 2.0, 1.0)), 0.0));
+
+        // bounded, not constant and continuous
+        let mut min = 0.0f32;
+        let mut max = 0.0f32;
+        for i in 0..500 {
+            let x = i as f32 * 0.137;
+            let y = i as f32 * 0.071 - 3.0;
+            let vals = [n.perlin2(Vec2::new(x, y)),
+                        n.perlin3(Vec3::new(x, y, 0.5 * x)),
+                        n.perlin4(Vec4::new(x, y, 0.5 * x, 0.3)),
+                        n.simplex2(Vec2::new(x, y)),
+                        n.simplex3(Vec3::new(x, y, 0.5 * x)),
+                        n.simplex4(Vec4::new(x, y, 0.5 * x, 0.3))];
+            for v in vals.iter() {
+                assert!(*v >= -1.1 && *v <= 1.1);
+                min = min.min(*v);
+                max = max.max(*v);
+            }
+            let d = n.simplex3(Vec3::new(x, y, 0.0)) - n.simplex3(Vec3::new(x + 1e-3, y, 0.0));
+            assert!(d.abs() < 0.05);
+        }
+        assert!(min < -0.2 && max > 0.2);
+
+        // worley F1 <= F2, and F1 is small next to a feature point
+        for i in 0..100 {
+            let w = n.worley3(Vec3::new(i as f32 * 0.31, 0.2, 1.7));
+            assert!(w.x <= w.y && w.x >= 0.0 && w.x < 1.8);
+            let w = n.worley2(Vec2::new(i as f32 * 0.31, 0.2));
+            assert!(w.x <= w.y && w.x >= 0.0 && w.x < 1.5);
+        }
+
+        // fractal combinators keep the range of their source
+        let q = Vec2::new(0.3, 0.8);
+        let f = fbm(q, 5, 2.0, 0.5, |p| n.simplex2(p));
+        assert!(f >= -1.0 && f <= 1.0);
+        assert!(feq(fbm(q, 1, 2.0, 0.5, |p| n.simplex2(p)), n.simplex2(q)));
+        let r = ridged(q, 5, 2.0, 0.5, 1.0, |p| n.simplex2(p));
+        assert!(r >= 0.0 && r <= 1.0);
+    }
+
+    #[test]
+    fn color_tests() {
+        use math::color::*;
+        use system::config;
+
+        // hex parsing
+        let c = Color::from_hex("#33aaffcc").unwrap();
+        assert_eq!(c.to_rgba8(), [0x33, 0xaa, 0xff, 0xcc]);
+        assert_eq!(c.to_hex(), "#33aaffcc");
+        assert_eq!(Color::from_hex("fff").unwrap(), WHITE);
+        assert_eq!(Color::from_hex("#00f8").unwrap().to_rgba8(), [0, 0, 255, 0x88]);
+        assert_eq!(Color::from_hex("#ff0000").unwrap(), RED);
+        assert!(Color::from_hex("#12345").is_none());
+        assert!(Color::from_hex("#zzzzzz").is_none());
+
+        // sRGB <-> linear
+        let grey = Color::from_hex("#808080").unwrap();
+        assert!(feq(grey.to_linear().r, 0.21586));
+        assert!(feq(grey.to_linear().a, 1.0));
+        let back = grey.to_linear().to_srgb();
+        assert!(feq(back.r, grey.r) && feq(back.g, grey.g) && feq(back.b, grey.b));
+        assert!(feq(Color::rgb(0.002, 0.0, 1.0).to_linear().to_srgb().r, 0.002));
+
+        // HSV / HSL round trips
+        assert_eq!(Color::from_hsv(120.0, 1.0, 1.0, 1.0), GREEN);
+        assert_eq!(Color::from_hsl(240.0, 1.0, 0.5, 1.0), BLUE);
+        let hsv = c.to_hsv();
+        let c2 = Color::from_hsv(hsv.x, hsv.y, hsv.z, c.a);
+        assert!(feq(c.r, c2.r) && feq(c.g, c2.g) && feq(c.b, c2.b));
+        let hsl = c.to_hsl();
+        let c3 = Color::from_hsl(hsl.x, hsl.y, hsl.z, c.a);
+        assert!(feq(c.r, c3.r) && feq(c.g, c3.g) && feq(c.b, c3.b));
+        assert!(feq(ORANGE.to_hsv().x, 30.0));
+        assert!(feq(GREY.to_hsl().y, 0.0));
+
+        // premultiplied alpha
+        let p = Color::new(1.0, 0.5, 0.25, 0.5).premultiply();
+        assert_eq!(p, Color::new(0.5, 0.25, 0.125, 0.5));
+        assert_eq!(p.unpremultiply(), Color::new(1.0, 0.5, 0.25, 0.5));
+        assert_eq!(TRANSPARENT.unpremultiply(), TRANSPARENT);
+
+        // config colors
+        let conf = config::Config::new("data/config.json");
+        assert_eq!(conf.get_color("window.clear_color"), DARK_GREY);
+    }
+
+    #[test]
+    fn simd_tests() {
+        use math::vec3::Vec3;
+        use math::vec4::Vec4;
+        use math::mat4::Mat4;
+        use math::transform;
+
+        // deterministic pseudo random values in [-2, 2]
+        let mut seed = 0x2545f491u32;
+        let mut rnd = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            (seed % 10000) as f32 / 2500.0 - 2.0
+        };
+
+        for _ in 0..200 {
+            let mut a = Mat4::empty();
+            let mut b = Mat4::empty();
+            for c in 0..4 {
+                for r in 0..4 {
+                    a[c][r] = rnd();
+                    b[c][r] = rnd();
+                }
+                // keep the matrices well conditioned for the inverse comparison
+                a[c][c] += 5.0;
+            }
+            let v = Vec4::new(rnd(), rnd(), rnd(), rnd());
+
+            // products are evaluated in the same order, results are exact
+            assert_eq!(a * b, Mat4::mul_scalar(a, b));
+            assert_eq!(a * v, Mat4::mul_vec4_scalar(a, v));
+
+            let inv = Mat4::inverse(a);
+            let inv_ref = Mat4::inverse_scalar(a);
+            for c in 0..4 {
+                for r in 0..4 {
+                    let tol = 1e-4 * inv_ref[c][r].abs().max(1.0);
+                    assert!((inv[c][r] - inv_ref[c][r]).abs() < tol);
+                }
+            }
+        }
+
+        // singular matrices
+        let mut s = Mat4::identity();
+        s[2] = s[1];
+        assert_eq!(Mat4::inverse(s), Mat4::empty());
+        assert_eq!(Mat4::inverse_scalar(s), Mat4::empty());
+
+        // batch helpers, with an odd count to hit the remainder path
+        let m = transform::scale(Vec3::new(2.0, 3.0, 4.0)) * transform::translation(Vec3::new(1.0, -1.0, 0.5));
+        let src: Vec<Vec4> = (0..7).map(|i| Vec4::new(i as f32, 1.0 - i as f32, 0.5 * i as f32, 1.0)).collect();
+        let mut dst = src.clone();
+        Mat4::transform_vec4s(&m, &mut dst);
+        for i in 0..src.len() {
+            assert_eq!(dst[i], m * src[i]);
+        }
+
+        let mut points: Vec<Vec3> = src.iter().map(|p| Vec3::new(p.x, p.y, p.z)).collect();
+        let mut dirs = points.clone();
+        Mat4::transform_points(&m, &mut points);
+        Mat4::transform_directions(&m, &mut dirs);
+        for i in 0..src.len() {
+            let p = m * src[i];
+            assert_eq!(points[i], Vec3::new(p.x, p.y, p.z));
+            let d = m * Vec4::new(src[i].x, src[i].y, src[i].z, 0.0);
+            assert_eq!(dirs[i], Vec3::new(d.x, d.y, d.z));
+        }
+    }
+
+    #[test]
+    fn approx_tests() {
+        use math::approx::*;
+        use math::vec3::Vec3;
+        use math::vec4::Vec4;
+        use math::mat4::Mat4;
+
+        assert!(1.0f32.approx_eq(&(1.0 + 1e-6)));
+        assert!(!1.0f32.approx_eq(&1.001));
+        assert!(0.0f32.approx_eq_abs(&1e-7, 1e-6));
+        assert!(!0.0f32.approx_eq_rel(&1e-7, 0.1));
+        assert!(1000.0f32.approx_eq_rel(&1000.1, 1e-3));
+        assert!(!1000.0f32.approx_eq_abs(&1000.1, 1e-3));
+
+        // ULPs, across zero and at the limits
+        let next = f32::from_bits(1.0f32.to_bits() + 1);
+        assert!(1.0f32.approx_eq_ulps(&next, 1));
+        assert!(!1.0f32.approx_eq_ulps(&next, 0));
+        assert!(0.0f32.approx_eq_ulps(&-0.0, 0));
+        assert!(f32::from_bits(1).approx_eq_ulps(&-f32::from_bits(1), 2));
+        assert!(!::std::f32::NAN.approx_eq_ulps(&::std::f32::NAN, 100));
+        assert!(::std::f32::INFINITY.approx_eq_rel(&::std::f32::INFINITY, 0.0));
+
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        assert!(v.approx_eq(&(v + Vec3::new(0.0, 1e-6, 0.0))));
+        assert!(!v.approx_eq(&(v + Vec3::new(0.0, 0.0, 1e-2))));
+        let w = Vec4::new(1.0, 2.0, 3.0, 4.0);
+        assert!(w.approx_eq_abs(&(w * 1.0001), 1e-3));
+        assert!(!w.approx_eq_abs(&(w * 1.01), 1e-3));
+
+        let m = Mat4::identity();
+        let mut n = m;
+        n[3][1] = 1e-6;
+        assert!(m.approx_eq(&n));
+        n[3][1] = 1e-3;
+        assert!(!m.approx_eq(&n));
+
+        // scalar division of matrices
+        let mut d = m * 4.0;
+        d /= 2.0;
+        assert_eq!(d, m * 2.0);
+        assert_eq!(d, m * 4.0 / 2.0);
+    }
+
+    #[test]
+    fn property_tests() {
+        use math::approx::*;
+        use math::vec3::Vec3;
+        use math::vec4::Vec4;
+        use math::mat4::Mat4;
+
+        let mut rng = Rng(0x9e3779b9);
+        let identity = Mat4::identity();
+
+        for _ in 0..500 {
+            // M * inverse(M) == I, for both the general and affine inverse
+            let m = rng.transform();
+            assert!((m * Mat4::inverse(m)).approx_eq_abs(&identity, 1e-4));
+            assert!((Mat4::inverse(m) * m).approx_eq_abs(&identity, 1e-4));
+            assert!((m * Mat4::affine_inverse(&m)).approx_eq_abs(&identity, 1e-4));
+            assert!(Mat4::inverse(Mat4::inverse(m)).approx_eq_abs(&m, 1e-3));
+
+            // transposition
+            assert_eq!(Mat4::transpose(Mat4::transpose(m)), m);
+            let n = rng.transform();
+            assert!(Mat4::transpose(m * n).approx_eq(&(Mat4::transpose(n) * Mat4::transpose(m))));
+
+            // the multiplication order applies the left operand first
+            let p = Vec4::new(rng.range(-5.0, 5.0), rng.range(-5.0, 5.0), rng.range(-5.0, 5.0), 1.0);
+            assert!(((m * n) * p).approx_eq_abs(&(n * (m * p)), 1e-3));
+
+            // cross product
+            let a = rng.vec3(-10.0, 10.0);
+            let b = rng.vec3(-10.0, 10.0);
+            let c = Vec3::cross(a, b);
+            assert!(c.approx_eq(&-Vec3::cross(b, a)));
+            assert!(Vec3::dot(c, a).approx_eq_abs(&0.0, 1e-2));
+            assert!(Vec3::dot(c, b).approx_eq_abs(&0.0, 1e-2));
+
+            // look_at builds an orthonormal view matrix : eye to the origin,
+            // target on the -z axis
+            let eye = rng.vec3(-10.0, 10.0);
+            let target = eye + rng.vec3(-5.0, 5.0) + Vec3::new(0.0, 0.0, 0.5);
+            let view = Mat4::look_at(eye, target, Vec3::new(0.0, 1.0, 0.0));
+            for i in 0..3 {
+                let ci = Vec3::new(view[0][i], view[1][i], view[2][i]);
+                assert!(Vec3::length(ci).approx_eq_abs(&1.0, 1e-4));
+                for j in (i + 1)..3 {
+                    let cj = Vec3::new(view[0][j], view[1][j], view[2][j]);
+                    assert!(Vec3::dot(ci, cj).approx_eq_abs(&0.0, 1e-4));
+                }
+            }
+            let e = view * Vec4::new(eye.x, eye.y, eye.z, 1.0);
+            assert!(e.approx_eq_abs(&Vec4::new(0.0, 0.0, 0.0, 1.0), 1e-4));
+            let t = view * Vec4::new(target.x, target.y, target.z, 1.0);
+            let dist = Vec3::length(target - eye);
+            assert!(t.approx_eq_abs(&Vec4::new(0.0, 0.0, -dist, 1.0), 1e-3));
+
+            // projections map the near and far planes to NDC
+            let near = rng.range(0.01, 10.0);
+            let far = near + rng.range(1.0, 1000.0);
+            let ndc = |proj: &Mat4, z: f32| {
+                let v = *proj * Vec4::new(0.0, 0.0, z, 1.0);
+                v.z / v.w
+            };
+
+            let ortho = Mat4::ortho(-1.0, 1.0, -1.0, 1.0, near, far);
+            assert!(ndc(&ortho, -near).approx_eq_abs(&0.0, 1e-5));
+            assert!(ndc(&ortho, -far).approx_eq_abs(&1.0, 1e-5));
+
+            let persp = Mat4::persp(rng.range(30.0, 120.0), rng.range(0.5, 2.0), near, far);
+            assert!(ndc(&persp, -near).approx_eq_abs(&-1.0, 1e-4));
+            assert!(ndc(&persp, -far).approx_eq_abs(&1.0, 1e-3));
+        }
+    }
+
+    #[test]
+    fn projection_tests() {
+        use math::approx::*;
+        use math::vec3::Vec3;
+        use math::vec4::Vec4;
+        use math::mat4::Mat4;
+
+        let ndc = |proj: &Mat4, p: Vec3| {
+            let v = *proj * Vec4::new(p.x, p.y, p.z, 1.0);
+            Vec3::new(v.x, v.y, v.z) / v.w
+        };
+
+        // off-axis frustum : the near window corners map to the NDC corners
+        let (l, r, b, t, n, f) = (-0.2, 0.6, -0.1, 0.3, 0.5, 50.0);
+        let fr = Mat4::frustrum(l, r, b, t, n, f);
+        assert!(ndc(&fr, Vec3::new(l, b, -n)).approx_eq_abs(&Vec3::new(-1.0, -1.0, -1.0), 1e-5));
+        assert!(ndc(&fr, Vec3::new(r, t, -n)).approx_eq_abs(&Vec3::new(1.0, 1.0, -1.0), 1e-5));
+        // and the far window, scaled by f / n
+        let k = f / n;
+        assert!(ndc(&fr, Vec3::new(r * k, b * k, -f)).approx_eq_abs(&Vec3::new(1.0, -1.0, 1.0), 1e-4));
+        assert!(fr != Mat4::ortho(l, r, b, t, n, f));
+
+        // a symmetric frustum is the usual perspective
+        let (fov_x, aspect) = (90.0f32, 2.0);
+        let w = n * (fov_x.to_radians() * 0.5).tan();
+        let h = w / aspect;
+        assert!(Mat4::frustrum(-w, w, -h, h, n, f).approx_eq(&Mat4::persp(fov_x, aspect, n, f)));
+
+        // infinite far plane
+        let inf = Mat4::persp_infinite(fov_x, aspect, n);
+        assert!(ndc(&inf, Vec3::new(0.0, 0.0, -n)).z.approx_eq_abs(&-1.0, 1e-5));
+        assert!(ndc(&inf, Vec3::new(0.0, 0.0, -1e6)).z.approx_eq_abs(&1.0, 1e-5));
+
+        // reverse-Z, finite and infinite
+        let rz = Mat4::persp_reverse_z(fov_x, aspect, n, f);
+        assert!(ndc(&rz, Vec3::new(0.0, 0.0, -n)).z.approx_eq_abs(&1.0, 1e-5));
+        assert!(ndc(&rz, Vec3::new(0.0, 0.0, -f)).z.approx_eq_abs(&0.0, 1e-5));
+        let rzi = Mat4::persp_reverse_z_infinite(fov_x, aspect, n);
+        assert!(ndc(&rzi, Vec3::new(0.0, 0.0, -n)).z.approx_eq_abs(&1.0, 1e-5));
+        assert!(ndc(&rzi, Vec3::new(0.0, 0.0, -1e6)).z.approx_eq_abs(&0.0, 1e-5));
+
+        // ortho targeting [-1, 1]
+        let o = Mat4::ortho_gl(-2.0, 2.0, -1.0, 1.0, n, f);
+        assert!(ndc(&o, Vec3::new(-2.0, 1.0, -n)).approx_eq_abs(&Vec3::new(-1.0, 1.0, -1.0), 1e-5));
+        assert!(ndc(&o, Vec3::new(2.0, -1.0, -f)).approx_eq_abs(&Vec3::new(1.0, -1.0, 1.0), 1e-5));
+
+        // unproject and view depth recover the original point for every variant
+        let p = Vec3::new(0.3, -0.2, -7.5);
+        let projs = [fr, Mat4::persp(fov_x, aspect, n, f), inf, rz, rzi, o,
+                     Mat4::ortho(-2.0, 2.0, -1.0, 1.0, n, f)];
+        for proj in projs.iter() {
+            let d = ndc(proj, p);
+            assert!(Mat4::unproject(&Mat4::inverse(*proj), d).approx_eq_abs(&p, 1e-3));
+            assert!(Mat4::view_depth(proj, d.z).approx_eq_abs(&p.z, 1e-3));
+        }
+    }
+
+    #[test]
+    fn polygon_tests() {
+        use math::vec2::Vec2;
+        use math::vec3::Vec3;
+        use math::polygon::*;
+
+        // sum of the triangle areas, checking they all share the winding w
+        let tri_area = |pts: &[Vec2], idx: &[u32], w: Winding| {
+            let mut total = 0.0;
+            for t in idx.chunks(3) {
+                let tri = [pts[t[0] as usize], pts[t[1] as usize], pts[t[2] as usize]];
+                assert_eq!(winding(&tri), w);
+                total += area(&tri);
+            }
+            total
+        };
+
+        let square = [Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0), Vec2::new(4.0, 4.0), Vec2::new(0.0, 4.0)];
+        assert!(feq(signed_area(&square), 16.0));
+        assert_eq!(winding(&square), Winding::CounterClockwise);
+        let mut cw = square.to_vec();
+        cw.reverse();
+        assert!(feq(signed_area(&cw), -16.0));
+        assert_eq!(winding(&cw), Winding::Clockwise);
+
+        // concave L shape
+        let l = [Vec2::new(0.0, 0.0), Vec2::new(3.0, 0.0), Vec2::new(3.0, 1.0),
+                 Vec2::new(1.0, 1.0), Vec2::new(1.0, 3.0), Vec2::new(0.0, 3.0)];
+        assert!(contains_point(&l, Vec2::new(0.5, 2.5)));
+        assert!(contains_point(&l, Vec2::new(2.5, 0.5)));
+        assert!(!contains_point(&l, Vec2::new(2.0, 2.0)));
+        assert!(!contains_point(&l, Vec2::new(-1.0, 0.5)));
+
+        let idx = triangulate(&l);
+        assert_eq!(idx.len(), 3 * 4);
+        assert!(feq(tri_area(&l, &idx, Winding::CounterClockwise), area(&l)));
+        let mut l_cw = l.to_vec();
+        l_cw.reverse();
+        let idx = triangulate(&l_cw);
+        assert!(feq(tri_area(&l_cw, &idx, Winding::Clockwise), area(&l)));
+
+        // square with two holes, given in both windings
+        let hole_a = [Vec2::new(1.0, 1.0), Vec2::new(1.0, 2.0), Vec2::new(2.0, 2.0), Vec2::new(2.0, 1.0)];
+        let hole_b = [Vec2::new(2.5, 2.5), Vec2::new(3.5, 2.5), Vec2::new(3.0, 3.5)];
+        let (verts, idx) = triangulate_with_holes(&square, &[&hole_a, &hole_b]);
+        assert_eq!(verts.len(), 4 + 4 + 3);
+        assert_eq!(verts[4], hole_a[0]);
+        // n + 2h - 2 triangles
+        assert_eq!(idx.len(), 3 * (11 + 4 - 2));
+        assert!(feq(tri_area(&verts, &idx, Winding::CounterClockwise),
+                    16.0 - area(&hole_a) - area(&hole_b)));
+        for t in idx.chunks(3) {
+            let c = (verts[t[0] as usize] + verts[t[1] as usize] + verts[t[2] as usize]) / 3.0;
+            assert!(!contains_point(&hole_a, c) && !contains_point(&hole_b, c));
+        }
+
+        // convex hull
+        let pts = [Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(2.0, 0.0),
+                   Vec2::new(2.0, 2.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 2.0),
+                   Vec2::new(0.5, 1.5)];
+        let hull = convex_hull(&pts);
+        assert_eq!(hull, vec![0, 2, 3, 5]);
+        let hull_pts: Vec<Vec2> = hull.iter().map(|&i| pts[i as usize]).collect();
+        assert_eq!(winding(&hull_pts), Winding::CounterClockwise);
+        assert_eq!(convex_hull(&pts[..2]).len(), 2);
+
+        // planar polygon in 3D, facing up
+        let poly = [Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 2.0), Vec3::new(2.0, 1.0, 2.0),
+                    Vec3::new(1.0, 1.0, 1.0), Vec3::new(2.0, 1.0, 0.0)];
+        assert!(Vec3::normalize(newell_normal(&poly)) == Vec3::new(0.0, 1.0, 0.0));
+        let idx = triangulate_3d(&poly);
+        assert_eq!(idx.len(), 3 * 3);
+        for t in idx.chunks(3) {
+            let (a, b, c) = (poly[t[0] as usize], poly[t[1] as usize], poly[t[2] as usize]);
+            assert!(Vec3::cross(b - a, c - a).y > 0.0);
+        }
+
+        assert_eq!(to_positions(&l, 0.5).len(), 18);
+    }
+
+    #[test]
+    fn serialize_tests() {
+        use self::rustc_serialize::json;
+        use math::approx::*;
+        use math::vec2::Vec2;
+        use math::vec3::Vec3;
+        use math::vec4::Vec4;
+        use math::mat4::Mat4;
+        use math::quat::Quat;
+        use math::color::Color;
+        use math::transform::Transform;
+        use system::config;
+
+        let v: Vec4 = json::decode("[0.2, 0.2, 0.2, 1.0]").unwrap();
+        assert_eq!(v, Vec4::new(0.2, 0.2, 0.2, 1.0));
+        let v: Vec3 = json::decode("[1, -2, 3.5]").unwrap();
+        assert_eq!(v, Vec3::new(1.0, -2.0, 3.5));
+        assert!(json::decode::<Vec3>("[1, 2]").is_err());
+        assert!(json::decode::<Vec2>("{\"x\": 1}").is_err());
+
+        // round trips
+        let v = Vec2::new(0.5, -4.0);
+        assert_eq!(json::decode::<Vec2>(&json::encode(&v).unwrap()).unwrap(), v);
+        let m = Mat4::persp(90.0, 1.5, 0.1, 100.0);
+        assert_eq!(json::decode::<Mat4>(&json::encode(&m).unwrap()).unwrap(), m);
+        let q = Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 30.0);
+        assert_eq!(json::decode::<Quat>(&json::encode(&q).unwrap()).unwrap(), q);
+        let c = Color::new(0.1, 0.2, 0.3, 0.4);
+        assert_eq!(json::decode::<Color>(&json::encode(&c).unwrap()).unwrap(), c);
+        let t = Transform::new(Vec3::new(1.0, 2.0, 3.0), q, Vec3::new(2.0, 2.0, 2.0));
+        assert_eq!(json::decode::<Transform>(&json::encode(&t).unwrap()).unwrap(), t);
+
+        // matrices as columns or 16 numbers
+        let flat: Mat4 = json::decode("[1,0,0,0, 0,1,0,0, 0,0,1,0, 5,6,7,1]").unwrap();
+        let nested: Mat4 = json::decode("[[1,0,0,0], [0,1,0,0], [0,0,1,0], [5,6,7,1]]").unwrap();
+        assert_eq!(flat, nested);
+        assert_eq!(flat * Vec4::new(0.0, 0.0, 0.0, 1.0), Vec4::new(5.0, 6.0, 7.0, 1.0));
+
+        // euler rotations, opaque colors and partial transforms
+        let q: Quat = json::decode("[0, 90, 0]").unwrap();
+        assert!((q * Vec3::new(1.0, 0.0, 0.0)).approx_eq_abs(&Vec3::new(0.0, 0.0, -1.0), 1e-5));
+        let c: Color = json::decode("[1, 0.5, 0]").unwrap();
+        assert_eq!(c, Color::new(1.0, 0.5, 0.0, 1.0));
+        let t: Transform = json::decode("{\"translation\": [1, 2, 3]}").unwrap();
+        assert_eq!(t, Transform::new(Vec3::new(1.0, 2.0, 3.0), Quat::identity(), Vec3::new(1.0, 1.0, 1.0)));
+
+        let conf = config::Config::new("data/config.json");
+        assert_eq!(conf.get_vec4("window.clear_color"), Vec4::new(0.2, 0.2, 0.2, 1.0));
+        assert_eq!(conf.get_vec3("camera.2d.position"), Vec3::empty());
+        assert_eq!(conf.get::<Vec4>("canvas.rect").z, 200.0);
+    }
+
+    #[test]
+    fn config_tests() {
+        use system::config::*;
+        use math::vec3::Vec3;
+
+        let conf = "{ \"fSpeed\": 2.5, \"iCount\": -3, \"sName\": \"radar\", \"vPos\": [1, 2, 3],
+                      \"sColor\": \"#nothex\" }".parse::<Config>().unwrap();
+
+        assert_eq!(conf.try_get_f64("fSpeed").unwrap(), 2.5);
+        assert_eq!(conf.try_get_i64("iCount").unwrap(), -3);
+        assert_eq!(conf.try_get_str("sName").unwrap(), "radar");
+        assert_eq!(conf.try_get_vec3("vPos").unwrap(), Vec3::new(1.0, 2.0, 3.0));
+
+        match conf.try_get_f64("fMissing") {
+            Err(ConfigError::MissingKey(ref k)) => assert_eq!(k, "fMissing"),
+            _ => panic!("expected a missing key"),
+        }
+        match conf.try_get_u64("iCount") {
+            Err(ConfigError::WrongType { ref key, .. }) => assert_eq!(key, "iCount"),
+            _ => panic!("expected a type error"),
+        }
+        match conf.try_get_vec3("sName") {
+            Err(ConfigError::WrongType { ref found, .. }) => assert_eq!(found, "\"radar\""),
+            _ => panic!("expected a type error"),
+        }
+        match conf.try_get_color("sColor") {
+            Err(ConfigError::InvalidValue { .. }) => {}
+            _ => panic!("expected an invalid value"),
+        }
+
+        // defaults
+        assert_eq!(conf.get_or("fMissing", 1.5), 1.5);
+        assert_eq!(conf.get_or("fSpeed", 1.5), 2.5);
+        assert_eq!(conf.get_or("sName", 7u64), 7);
+        assert_eq!(conf.get_or("vMissing", Vec3::empty()), Vec3::empty());
+        assert_eq!(conf.get_str_or("sMissing", "default"), "default");
+
+        // parse errors carry the position
+        match "{\n  \"a\": 1,\n  \"b\" 2\n}".parse::<Config>() {
+            Err(ConfigError::Parse { line, column, .. }) => {
+                assert_eq!(line, 3);
+                assert_eq!(column, 8);
+            }
+            _ => panic!("expected a parse error"),
+        }
+        match "[1, 2]".parse::<Config>() {
+            Err(ConfigError::NotAnObject) => {}
+            _ => panic!("expected a non object error"),
+        }
+        match Config::load("data/missing.json") {
+            Err(ConfigError::Io { ref path, .. }) => assert_eq!(path, "data/missing.json"),
+            _ => panic!("expected an io error"),
+        }
+        assert!(Config::load("data/config.json").is_ok());
+    }
+
+    #[test]
+    fn config_section_tests() {
+        use self::rustc_serialize::{Decodable, Decoder};
+        use system::config::*;
+
+        struct Bindings {
+            jump: String,
+            crouch: String,
+        }
+
+        impl Decodable for Bindings {
+            fn decode<D: Decoder>(d: &mut D) -> Result<Bindings, D::Error> {
+                d.read_struct("Bindings", 2, |d| {
+                    Ok(Bindings {
+                        jump: d.read_struct_field("jump", 0, |d| d.read_str())?,
+                        crouch: d.read_struct_field("crouch", 1, |d| d.read_str())?,
+                    })
+                })
+            }
+        }
+
+        let conf = "{ \"window\": { \"width\": 800, \"fullscreen\": true },
+                      \"input\": { \"bindings\": { \"jump\": \"Space\", \"crouch\": \"C\" } },
+                      \"levels\": [\"intro\", \"end\"] }".parse::<Config>().unwrap();
+
+        // dotted paths
+        assert_eq!(conf.get_u64("window.width"), 800);
+        assert!(conf.get_bool("window.fullscreen"));
+        assert_eq!(conf.get_str("input.bindings.jump"), "Space");
+        match conf.try_get_str("input.bindings.fire") {
+            Err(ConfigError::MissingKey(ref k)) => assert_eq!(k, "input.bindings.fire"),
+            _ => panic!("expected a missing key"),
+        }
+        match conf.try_get_u64("window.width.value") {
+            Err(ConfigError::WrongType { ref key, .. }) => assert_eq!(key, "window.width"),
+            _ => panic!("expected a type error"),
+        }
+        match conf.try_get_bool("window.width") {
+            Err(ConfigError::WrongType { ref found, .. }) => assert_eq!(found, "an integer"),
+            _ => panic!("expected a type error"),
+        }
+
+        // arrays, objects and whole sections
+        assert_eq!(conf.get_array("levels").len(), 2);
+        assert_eq!(conf.get_array("levels")[1].as_string(), Some("end"));
+        assert_eq!(conf.get_object("window").len(), 2);
+        assert!(conf.try_get_object("levels").is_err());
+
+        let bindings = conf.get::<Bindings>("input.bindings");
+        assert_eq!(bindings.jump, "Space");
+        assert_eq!(bindings.crouch, "C");
+        match conf.try_get::<Bindings>("window") {
+            Err(ConfigError::InvalidValue { ref key, .. }) => assert_eq!(key, "window"),
+            _ => panic!("expected a decoding error"),
+        }
+
+        // the shipped config is organized in sections
+        let conf = Config::new("data/config.json");
+        assert_eq!(conf.get_u64("window.width"), 1200);
+        assert_eq!(conf.get_str("input.bindings.forward"), "W");
+    }
+
+    #[test]
+    fn config_layer_tests() {
+        use self::rustc_serialize::json::Json;
+        use system::config::*;
+
+        let mut conf = Config::empty();
+        conf.add_layer(Layer::Defaults, Json::from_str(DEFAULTS).unwrap()).unwrap();
+        conf.add_file(Layer::File, "data/config.json").unwrap();
+        // layers added out of order still apply by precedence
+        conf.add_args(vec!["--set".to_string(), "window.width=640".to_string(),
+                           "--verbose".to_string(), "--set=window.title=cli".to_string()]).unwrap();
+        conf.add_env(vec![("RADAR_WINDOW__WIDTH".to_string(), "800".to_string()),
+                          ("RADAR_WINDOW__HEIGHT".to_string(), "480".to_string()),
+                          ("RADAR_CAMERA__2D__FAR".to_string(), "50.5".to_string()),
+                          ("RADAR_BASE_DIR".to_string(), "/opt/radar".to_string()),
+                          ("HOME".to_string(), "/home/radar".to_string())]);
+        conf.add_layer(Layer::User, Json::from_str("{ \"window\": { \"height\": 768 },
+                                                      \"camera\": { \"zoom_speed\": 0.5 } }").unwrap()).unwrap();
+
+        assert_eq!(conf.get_u64("window.width"), 640);
+        assert_eq!(conf.source("window.width"), Some(Layer::CommandLine));
+        assert_eq!(conf.get_str("window.title"), "cli");
+        assert_eq!(conf.get_u64("window.height"), 480);
+        assert_eq!(conf.source("window.height"), Some(Layer::Environment));
+        assert_eq!(conf.get_f64("camera.2d.far"), 50.5);
+        assert_eq!(conf.get_f64("camera.zoom_speed"), 0.5);
+        assert_eq!(conf.source("camera.zoom_speed"), Some(Layer::User));
+        assert_eq!(conf.source("canvas.rect"), Some(Layer::File));
+        assert_eq!(conf.source("home"), None);
+        assert_eq!(conf.source("base_dir"), None);
+        assert_eq!(conf.source("window.fullscreen"), None);
+
+        // sections are merged key by key, not replaced
+        assert_eq!(conf.get_f64("camera.move_speed"), 5.0);
+        assert_eq!(conf.get_f64("camera.2d.near"), 0.01);
+
+        // the defaults alone are enough to run
+        let mut conf = Config::empty();
+        conf.add_layer(Layer::Defaults, Json::from_str(DEFAULTS).unwrap()).unwrap();
+        assert_eq!(conf.get_u64("window.width"), 1280);
+        assert_eq!(conf.get_str("input.bindings.left"), "A");
+        assert_eq!(conf.source("window.width"), Some(Layer::Defaults));
+
+        // bad arguments
+        match conf.add_args(vec!["--set".to_string()]) {
+            Err(ConfigError::InvalidArgument(ref arg)) => assert_eq!(arg, "--set"),
+            _ => panic!("expected an argument error"),
+        }
+        match conf.add_args(vec!["--set=window.width".to_string()]) {
+            Err(ConfigError::InvalidArgument(ref arg)) => assert_eq!(arg, "window.width"),
+            _ => panic!("expected an argument error"),
+        }
+        match conf.add_layer(Layer::User, Json::from_str("[1, 2]").unwrap()) {
+            Err(ConfigError::NotAnObject) => {}
+            _ => panic!("expected a root object error"),
+        }
+    }
+
+    #[test]
+    fn config_write_tests() {
+        use self::rustc_serialize::json::Json;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use math::color::Color;
+        use system::config::*;
+
+        let mut conf = Config::empty();
+        conf.add_str(Layer::File, "{ \"window\": { \"width\": 800, \"height\": 600 },
+                                     \"renderer\": { \"texture_filter\": \"linear\" } }").unwrap();
+        conf.add_str(Layer::User, "{ \"window\": { \"width\": 1024 }, \"zoom\": 2.0 }").unwrap();
+        conf.add_args(vec!["--set=window.title=cli".to_string()]).unwrap();
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_cb = seen.clone();
+        let id = conf.on_change("window", move |change| seen_cb.borrow_mut().push(change.key.clone()));
+        let all = conf.subscribe("");
+
+        // setters, with notifications for the effective changes only
+        conf.set_u64("window.height", 720);
+        conf.set_u64("window.height", 720);
+        conf.set_str("window.title", "options");
+        conf.set_str("renderer.texture_filter", "nearest");
+        conf.set_color("window.clear_color", Color::rgb(1.0, 0.0, 0.5));
+        assert_eq!(conf.get_u64("window.height"), 720);
+        assert_eq!(conf.get_str("window.title"), "cli");
+        assert_eq!(conf.get_str("renderer.texture_filter"), "nearest");
+        assert_eq!(conf.get_color("window.clear_color"), Color::rgb(1.0, 0.0, 0.5));
+        assert_eq!(*seen.borrow(), vec!["window.height".to_string(), "window.clear_color".to_string()]);
+
+        let changes: Vec<ConfigChange> = all.try_iter().collect();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[1].key, "renderer.texture_filter");
+        assert_eq!(changes[1].value, Json::String("nearest".to_string()));
+
+        conf.unsubscribe(id);
+        conf.set_bool("window.vsync", false);
+        assert_eq!(seen.borrow().len(), 2);
+        assert_eq!(all.try_iter().count(), 1);
+
+        // a new layer reports its changes too
+        conf.add_str(Layer::Environment, "{ \"window\": { \"vsync\": true }, \"zoom\": 2.0 }").unwrap();
+        let changes: Vec<ConfigChange> = all.try_iter().collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].key, "window.vsync");
+
+        // the user layer, keys in the order of the files they were read
+        // from, then in the order they were set
+        let saved = conf.layer_to_pretty_string(Layer::User);
+        assert_eq!(saved, "{
+    \"window\"   : {
+        \"width\"       : 1024,
+        \"height\"      : 720,
+        \"title\"       : \"options\",
+        \"clear_color\" : [1.0, 0.0, 0.5, 1.0],
+        \"vsync\"       : false
+    },
+    \"renderer\" : {
+        \"texture_filter\" : \"nearest\"
+    },
+    \"zoom\"     : 2.0
+}
+");
+
+        let path = ::std::env::temp_dir().join("radar_config_write_test.json");
+        let path = path.to_str().unwrap();
+        conf.save_layer(Layer::User, path).unwrap();
+        let mut reloaded = Config::empty();
+        reloaded.add_file(Layer::File, "data/config.json").unwrap();
+        reloaded.add_file(Layer::User, path).unwrap();
+        assert_eq!(reloaded.get_u64("window.width"), 1024);
+        assert_eq!(reloaded.get_vec4("canvas.rect").x, 400.0);
+        assert_eq!(reloaded.get_str("window.title"), "options");
+        assert!(!reloaded.get_bool("window.vsync"));
+        assert_eq!(reloaded.layer_to_pretty_string(Layer::User), saved);
+
+        // save writes every value : a file loaded, changed and saved back
+        // keeps its other sections
+        ::std::fs::copy("data/config.json", path).unwrap();
+        let shipped = Config::load("data/config.json").unwrap();
+        let mut conf = Config::load(path).unwrap();
+        conf.set_u64("window.width", 1600);
+        conf.save(path).unwrap();
+        let reloaded = Config::load(path).unwrap();
+        assert_eq!(reloaded.get_u64("window.width"), 1600);
+        assert_eq!(reloaded.get_u64("window.height"), shipped.get_u64("window.height"));
+        for section in ["renderer", "input", "canvas", "camera.2d"].iter() {
+            assert_eq!(reloaded.try_get_object(section).unwrap(), shipped.try_get_object(section).unwrap());
+        }
+        // the json parser does not round trip every decimal exactly
+        for field in ["camera.move_speed", "camera.look_sensitivity", "camera.zoom_speed"].iter() {
+            assert!((reloaded.get_f64(field) - shipped.get_f64(field)).abs() < 1e-9);
+        }
+        assert_eq!(reloaded.get_str("input.bindings.forward"), "W");
+        ::std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn config_reload_tests() {
+        use self::rustc_serialize::json::Json;
+        use std::cell::RefCell;
+        use std::fs::File;
+        use std::io::Write;
+        use std::rc::Rc;
+        use system::config::*;
+
+        let path = ::std::env::temp_dir().join("radar_config_reload_test.json");
+        let path = path.to_str().unwrap();
+        let write = |s: &str| File::create(path).unwrap().write_all(s.as_bytes()).unwrap();
+
+        write("{ \"window\": { \"width\": 800, \"title\": \"radar\" }, \"speed\": 1.0 }");
+        let mut conf = Config::empty();
+        conf.add_file(Layer::File, path).unwrap();
+        conf.add_args(vec!["--set=speed=3.0".to_string()]).unwrap();
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_cb = changes.clone();
+        conf.on_change("", move |change| changes_cb.borrow_mut().push(change.clone()));
+
+        // nothing changed on disk
+        assert!(conf.reload_changed().is_empty());
+        assert!(changes.borrow().is_empty());
+
+        // a value changed, one added, one removed, one shadowed by the command line
+        write("{ \"window\": { \"width\": 1024, \"vsync\": true }, \"speed\": 2.0 }");
+        assert!(conf.reload_changed().is_empty());
+        assert_eq!(conf.get_u64("window.width"), 1024);
+        assert_eq!(conf.get_f64("speed"), 3.0);
+        {
+            let changes = changes.borrow();
+            let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+            assert_eq!(keys, vec!["window.title", "window.width", "window.vsync"]);
+            assert_eq!(changes[0].value, Json::Null);
+            assert_eq!(changes[2].value, Json::Boolean(true));
+        }
+
+        // a broken file keeps the previous values, and is reported once
+        changes.borrow_mut().clear();
+        write("{ \"window\": { \"width\": 640, } }");
+        let errors = conf.reload_changed();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, path);
+        match errors[0].1 {
+            ConfigError::Parse { line, .. } => assert_eq!(line, 1),
+            ref err => panic!("expected a parse error, got {}", err),
+        }
+        assert!(conf.reload_changed().is_empty());
+        assert_eq!(conf.get_u64("window.width"), 1024);
+        assert!(changes.borrow().is_empty());
+
+        // fixed
+        write("{ \"window\": { \"width\": 640 } }");
+        assert!(conf.reload_changed().is_empty());
+        assert_eq!(conf.get_u64("window.width"), 640);
+        assert_eq!(changes.borrow().len(), 2);
+
+        // a whole section added then removed reports each of its keys
+        let filter = Rc::new(RefCell::new(Vec::new()));
+        let filter_cb = filter.clone();
+        conf.on_change("renderer.texture_filter", move |change| filter_cb.borrow_mut().push(change.value.clone()));
+        changes.borrow_mut().clear();
+        write("{ \"window\": { \"width\": 640 }, \"renderer\": { \"texture_filter\": \"nearest\", \"msaa\": 4 } }");
+        assert!(conf.reload_changed().is_empty());
+        assert_eq!(*filter.borrow(), vec![Json::String("nearest".to_string())]);
+        {
+            let changes = changes.borrow();
+            let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+            assert_eq!(keys, vec!["renderer.msaa", "renderer.texture_filter"]);
+        }
+        write("{ \"window\": { \"width\": 640 }, \"renderer\": 1 }");
+        assert!(conf.reload_changed().is_empty());
+        assert_eq!(*filter.borrow(), vec![Json::String("nearest".to_string()), Json::Null]);
+        assert_eq!(changes.borrow().len(), 5);
+        assert_eq!(changes.borrow()[4].key, "renderer");
+
+        ::std::fs::remove_file(path).unwrap();
+        assert!(conf.reload_changed().is_empty());
+        assert_eq!(conf.get_u64("window.width"), 640);
+    }
+
+    #[test]
+    fn console_tests() {
+        use self::rustc_serialize::json::Json;
+        use math::color::Color;
+        use system::config::*;
+        use system::console::*;
+        use system::cvar::*;
+
+        // line parsing
+        let cmds = parse_line("set window.title \"radar \\\"rs\\\"\";echo  a\tb ; ;").unwrap();
+        assert_eq!(cmds, vec![vec!["set".to_string(), "window.title".to_string(), "radar \"rs\"".to_string()],
+                              vec!["echo".to_string(), "a".to_string(), "b".to_string()]]);
+        assert_eq!(parse_line("echo \"\"").unwrap(), vec![vec!["echo".to_string(), "".to_string()]]);
+        assert!(parse_line("").unwrap().is_empty());
+        match parse_line("echo \"open") {
+            Err(ConsoleError::Syntax(_)) => {}
+            _ => panic!("expected a syntax error"),
+        }
+
+        let mut conf = "{ \"window\": { \"width\": 800, \"vsync\": true, \"clear_color\": [0.2, 0.2, 0.2, 1.0] },
+                          \"camera\": { \"zoom_speed\": 0.1 } }".parse::<Config>().unwrap();
+        let mut console = Console::new();
+        console.cvars.register(&mut conf, CVar::new("window.width", CVarType::Int, "width")
+                                              .with_range(1.0, 4096.0).with_flags(CVAR_ARCHIVE));
+        console.cvars.register(&mut conf, CVar::new("window.vsync", CVarType::Bool, "vsync"));
+        console.cvars.register(&mut conf, CVar::new("window.clear_color", CVarType::Color, "background"));
+        console.cvars.register(&mut conf, CVar::new("camera.zoom_speed", CVarType::Float, "zoom").with_range(0.0, 1.0));
+        console.cvars.register(&mut conf, CVar::new("debug.wireframe", CVarType::Bool, "wireframe")
+                                              .with_default(Json::Boolean(false)));
+        console.cvars.register(&mut conf, CVar::new("renderer.gl_version", CVarType::Str, "version")
+                                              .with_flags(CVAR_READ_ONLY));
+        console.cvars.force(&mut conf, "renderer.gl_version", &"4.1".to_string()).unwrap();
+
+        // defaults go to the defaults layer, console values to the runtime one
+        assert_eq!(conf.source("debug.wireframe"), Some(Layer::Defaults));
+        assert_eq!(console.execute(&mut conf, "window.width 1024").unwrap(), "window.width = 1024");
+        assert_eq!(conf.get_u64("window.width"), 1024);
+        assert_eq!(conf.source("window.width"), Some(Layer::Runtime));
+        assert!(conf.layer_to_pretty_string(Layer::User).contains("1024"));
+        assert_eq!(console.execute(&mut conf, "set camera.zoom_speed 0.5; toggle window.vsync").unwrap(),
+                   "camera.zoom_speed = 0.5\nwindow.vsync = false");
+        assert!(!conf.layer_to_pretty_string(Layer::User).contains("zoom_speed"));
+        console.execute(&mut conf, "window.clear_color #ff0000").unwrap();
+        assert_eq!(conf.get_color("window.clear_color"), Color::rgb(1.0, 0.0, 0.0));
+        console.execute(&mut conf, "window.clear_color 0 0.5 1").unwrap();
+        assert_eq!(conf.get_color("window.clear_color"), Color::rgb(0.0, 0.5, 1.0));
+        assert_eq!(console.execute(&mut conf, "renderer.gl_version").unwrap(), "renderer.gl_version = 4.1");
+
+        // errors
+        match console.execute(&mut conf, "window.width 0") {
+            Err(ConsoleError::CVar(CVarError::OutOfRange { .. })) => {}
+            _ => panic!("expected a range error"),
+        }
+        match console.execute(&mut conf, "set window.vsync maybe") {
+            Err(ConsoleError::CVar(CVarError::InvalidValue { .. })) => {}
+            _ => panic!("expected an invalid value"),
+        }
+        match console.execute(&mut conf, "set renderer.gl_version 9") {
+            Err(ConsoleError::CVar(CVarError::ReadOnly(_))) => {}
+            _ => panic!("expected a read-only error"),
+        }
+        match console.execute(&mut conf, "echo ok; jump") {
+            Err(ConsoleError::UnknownCommand(ref name)) => assert_eq!(name, "jump"),
+            _ => panic!("expected an unknown command"),
+        }
+        match console.execute(&mut conf, "reset") {
+            Err(ConsoleError::Usage(_)) => {}
+            _ => panic!("expected a usage error"),
+        }
+        assert_eq!(conf.get_u64("window.width"), 1024);
+        assert_eq!(console.log().last().unwrap(), "error: usage: reset <name>");
+
+        // reset drops the console values
+        console.execute(&mut conf, "reset window.width").unwrap();
+        assert_eq!(conf.get_u64("window.width"), 800);
+        assert!(!conf.layer_to_pretty_string(Layer::User).contains("width"));
+
+        // custom commands
+        console.register_command("double", "double <name>", "Double a number", |console, conf, args| {
+            let value = conf.try_get_f64(&args[0]).map_err(|err| ConsoleError::Failed(err.to_string()))?;
+            console.cvars.set(conf, &args[0], &[(value * 2.0).to_string()])?;
+            Ok(String::new())
+        });
+        console.execute(&mut conf, "double camera.zoom_speed").unwrap();
+        assert_eq!(conf.get_f64("camera.zoom_speed"), 1.0);
+        assert!(console.execute(&mut conf, "double camera.zoom_speed").is_err());
+        assert!(console.execute(&mut conf, "help double").unwrap().starts_with("double <name>"));
+
+        // completion
+        assert_eq!(console.complete("he").0, "help ");
+        assert_eq!(console.complete("window.w").0, "window.width ");
+        let (line, candidates) = console.complete("set window.");
+        assert_eq!(line, "set window.");
+        assert_eq!(candidates, vec!["window.clear_color", "window.vsync", "window.width"]);
+        assert_eq!(console.complete("echo a; toggle d").0, "echo a; toggle debug.wireframe ");
+        assert_eq!(console.complete("window.vsync t").0, "window.vsync true ");
+        assert_eq!(console.complete("r").1, vec!["renderer.gl_version", "reset"]);
+        assert!(console.complete("echo x").1.is_empty());
+
+        // history file
+        let path = ::std::env::temp_dir().join("radar_console_history_test");
+        let _ = ::std::fs::remove_file(&path);
+        let mut console = Console::new();
+        console.load_history(&path).unwrap();
+        console.execute(&mut conf, "echo one").unwrap();
+        console.execute(&mut conf, "echo one").unwrap();
+        let _ = console.execute(&mut conf, "nope");
+        assert_eq!(console.history(), &["echo one".to_string(), "nope".to_string()]);
+
+        let mut console = Console::new();
+        console.load_history(&path).unwrap();
+        assert_eq!(console.history().len(), 2);
+        assert_eq!(console.execute(&mut conf, "history").unwrap(), "   1 echo one\n   2 nope\n   3 history");
+        ::std::fs::remove_file(&path).unwrap();
+
+        assert!(!console.quit_requested());
+        console.execute(&mut conf, "quit").unwrap();
+        assert!(console.quit_requested());
+    }
+
+    #[test]
+    fn vfs_tests() {
+        use std::fs;
+        use std::io::{ErrorKind, Read};
+        use system::config::{Config, Layer};
+        use system::vfs::*;
+
+        assert_eq!(normalize("data\\shaders/./../fonts//console.png"), Some("data/fonts/console.png".to_string()));
+        assert_eq!(normalize("/data/"), Some("data".to_string()));
+        assert_eq!(normalize("data/../../etc/passwd"), None);
+
+        // loose files
+        let dir = ::std::env::temp_dir().join("radar_vfs_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("shaders")).unwrap();
+        fs::write(dir.join("shaders/test.frag"), "// loose fragment shader\n").unwrap();
+        fs::write(dir.join("shaders/test.vs"), "// loose vertex shader\n").unwrap();
+        fs::write(dir.join("config.json"), "{}").unwrap();
+
+        let mut vfs = Vfs::new();
+        vfs.mount_dir("data", &dir, 0).unwrap();
+        assert!(vfs.exists("data/shaders/test.vs"));
+        assert!(vfs.is_dir("data/shaders"));
+        assert!(vfs.is_dir("") && !vfs.exists("shaders/test.vs"));
+        assert_eq!(vfs.read_to_string("data/shaders/test.frag").unwrap(), "// loose fragment shader\n");
+        assert_eq!(vfs.real_path("data/config.json"), Some(dir.join("config.json")));
+        assert_eq!(vfs.read_bytes("data/missing.png").unwrap_err().kind(), ErrorKind::NotFound);
+
+        // the archive shadows the loose files, deflated and stored entries
+        vfs.mount_archive("data", "data/tests/patch.pak", 1).unwrap();
+        let patched = vfs.read_to_string("data/shaders/test.frag").unwrap();
+        assert_eq!(patched, "// patched fragment shader\n".repeat(8));
+        assert_eq!(vfs.real_path("data/shaders/test.frag"), None);
+        assert_eq!(vfs.read_to_string("data/shaders/test.vs").unwrap(), "// loose vertex shader\n");
+        let mut readme = String::new();
+        vfs.open("data/levels/readme.txt").unwrap().read_to_string(&mut readme).unwrap();
+        assert_eq!(readme, "stored, not compressed\n");
+
+        // merged listings, mount points included
+        vfs.mount_dir("mods/extra", &dir, 0).unwrap();
+        let names = |vfs: &Vfs, path: &str| -> Vec<String> {
+            vfs.list_dir(path).unwrap().into_iter().map(|e| e.name).collect()
+        };
+        assert_eq!(names(&vfs, "data"), vec!["config.json", "levels", "shaders"]);
+        assert_eq!(names(&vfs, "data/shaders"), vec!["test.frag", "test.vs"]);
+        assert_eq!(names(&vfs, ""), vec!["data", "mods"]);
+        assert!(vfs.list_dir("data/levels").unwrap()[0] == DirEntry { name: "readme.txt".to_string(), is_dir: false });
+        assert!(vfs.is_dir("mods") && vfs.exists("mods/extra/config.json"));
+        assert!(vfs.list_dir("data/nothing").is_err());
+
+        // same priority : the last mount wins
+        let other = dir.join("other");
+        fs::create_dir_all(other.join("shaders")).unwrap();
+        fs::write(other.join("shaders/test.frag"), "// other\n").unwrap();
+        vfs.mount_dir("data", &other, 1).unwrap();
+        assert_eq!(vfs.read_to_string("data/shaders/test.frag").unwrap(), "// other\n");
+        vfs.unmount("data");
+        assert!(!vfs.exists("data/shaders/test.vs"));
+
+        // corrupted archive entries are reported when read
+        let mut bytes = fs::read("data/tests/patch.pak").unwrap();
+        let pos = bytes.windows(6).position(|w| w == b"stored").unwrap();
+        bytes[pos] = b'S';
+        let corrupted = dir.join("corrupted.pak");
+        fs::write(&corrupted, &bytes).unwrap();
+        let mut vfs = Vfs::new();
+        vfs.mount_archive("", &corrupted, 0).unwrap();
+        assert_eq!(vfs.read_bytes("levels/readme.txt").unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(vfs.mount_archive("", dir.join("config.json"), 0).is_err());
+
+        // config files are read through the global vfs, loose ones watched
+        fs::write(dir.join("config.json"), "{ \"window\": { \"width\": 800 } }").unwrap();
+        mount_dir("radar_vfs_test", &dir, 0).unwrap();
+        let mut conf = Config::empty();
+        conf.add_vfs_file(Layer::File, "radar_vfs_test/config.json").unwrap();
+        assert_eq!(conf.get_u64("window.width"), 800);
+        fs::write(dir.join("config.json"), "{ \"window\": { \"width\": 1024 } }").unwrap();
+        assert!(conf.reload_changed().is_empty());
+        assert_eq!(conf.get_u64("window.width"), 1024);
+        assert!(conf.add_vfs_file(Layer::User, "radar_vfs_test/missing.json").is_err());
+    }
+
+    #[test]
+    fn asset_tests() {
+        use std::fs;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use system::asset::*;
+        use system::config::Config;
+        use system::filesystem;
+        use system::vfs;
+
+        static LOADS: AtomicUsize = AtomicUsize::new(0);
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Text(String);
+        impl Asset for Text {
+            fn load(path: &str) -> Result<Text, AssetError> {
+                LOADS.fetch_add(1, Ordering::SeqCst);
+                Ok(Text(filesystem::read_to_string(path)?))
+            }
+        }
+        impl Drop for Text {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let dir = ::std::env::temp_dir().join("radar_asset_test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("hello.txt"), "hello").unwrap();
+        fs::write(dir.join("level.json"), "{ \"name\": \"intro\", \"enemies\": 3 }").unwrap();
+        fs::write(dir.join("broken.json"), "{ \"name\": ").unwrap();
+        vfs::mount_dir("asset_test", &dir, 0).unwrap();
+
+        // repeated loads share the asset, whatever the path spelling
+        let mut assets = AssetManager::new();
+        let a: Handle<Text> = assets.load("asset_test/hello.txt").unwrap();
+        let b: Handle<Text> = assets.load("asset_test/./hello.txt").unwrap();
+        assert!(a.same_asset(&b));
+        assert_eq!(LOADS.load(Ordering::SeqCst), 1);
+        assert_eq!(a.ref_count(), 2);
+        assert_eq!(b.path(), "asset_test/hello.txt");
+        assert_eq!(b.get().0, "hello");
+        assert_eq!(assets.loaded::<Text>(), vec!["asset_test/hello.txt"]);
+
+        // replacing the content is seen through every handle
+        a.replace(Text("bonjour".to_string()));
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+        assert_eq!(b.get().0, "bonjour");
+
+        // freed with the last handle, and loaded again after that
+        drop(a);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+        drop(b);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+        assert!(assets.get::<Text>("asset_test/hello.txt").is_none());
+        assert_eq!(assets.len(), 0);
+        let c: Handle<Text> = assets.load("asset_test/hello.txt").unwrap();
+        assert_eq!(LOADS.load(Ordering::SeqCst), 2);
+        assert_eq!(c.ref_count(), 1);
+
+        // the same path as another type is another asset
+        let text: Handle<Text> = assets.load("asset_test/level.json").unwrap();
+        let conf: Handle<Config> = assets.load("asset_test/level.json").unwrap();
+        assert!(text.get().0.starts_with("{"));
+        assert_eq!(conf.get().get_str("name"), "intro");
+        assert_eq!(conf.get().get_u64("enemies"), 3);
+        assert_eq!(assets.len(), 3);
+        drop(text);
+        drop(conf);
+        assets.collect_garbage();
+        assert_eq!(assets.len(), 1);
+
+        // errors
+        match assets.load::<Text>("asset_test/missing.txt") {
+            Err(AssetError::Fs(filesystem::FsError::NotFound(_))) => {}
+            Err(err) => panic!("expected not found, got {}", err),
+            Ok(_) => panic!("expected not found"),
+        }
+        assert!(assets.load::<Config>("asset_test/broken.json").is_err());
+        assert!(assets.load::<Text>("../outside.txt").is_err());
+    }
+
+    #[test]
+    fn asset_loading_tests() {
+        use std::fs;
+        use std::thread;
+        use std::time::{Duration, Instant};
+        use system::asset::*;
+        use system::config::Config;
+        use system::filesystem;
+        use system::vfs;
+        use system::worker::WorkerPool;
+
+        // the pool runs every job, and waits for them when dropped
+        {
+            use std::sync::Arc;
+            use std::sync::atomic::{AtomicUsize, Ordering};
+            let done = Arc::new(AtomicUsize::new(0));
+            let pool = WorkerPool::new("test", 3);
+            assert_eq!(pool.size(), 3);
+            for _ in 0..20 {
+                let done = done.clone();
+                pool.execute(move || { done.fetch_add(1, Ordering::SeqCst); });
+            }
+            drop(pool);
+            assert_eq!(done.load(Ordering::SeqCst), 20);
+        }
+
+        struct Text(String);
+        impl Asset for Text {
+            fn load(path: &str) -> Result<Text, AssetError> {
+                Text::upload(Text::decode(path)?)
+            }
+        }
+        impl AsyncAsset for Text {
+            type Data = String;
+            fn decode(path: &str) -> Result<String, AssetError> {
+                Ok(filesystem::read_to_string(path)?)
+            }
+            fn upload(data: String) -> Result<Text, AssetError> {
+                if data.is_empty() {
+                    return Err(AssetError::invalid("text", "empty"));
+                }
+                Ok(Text(data))
+            }
+            fn placeholder() -> Option<Text> {
+                Some(Text("...".to_string()))
+            }
+        }
+
+        let dir = ::std::env::temp_dir().join("radar_asset_loading_test");
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..8 {
+            fs::write(dir.join(format!("{}.txt", i)), format!("text {}", i)).unwrap();
+        }
+        fs::write(dir.join("empty.txt"), "").unwrap();
+        fs::write(dir.join("level.json"), "{ \"name\": \"intro\" }").unwrap();
+        vfs::mount_dir("loading_test", &dir, 0).unwrap();
+
+        let mut assets = AssetManager::new();
+        assets.set_worker_count(2);
+        let handles: Vec<Handle<Text>> = (0..8).map(|i| assets.load_async(&format!("loading_test/{}.txt", i))).collect();
+        let missing: Handle<Text> = assets.load_async("loading_test/missing.txt");
+        let empty: Handle<Text> = assets.load_async("loading_test/empty.txt");
+        let conf: Handle<Config> = assets.load_async("loading_test/level.json");
+        let dropped: Handle<Text> = assets.load_async("loading_test/7.txt");
+        assert!(dropped.same_asset(&handles[7]));
+
+        // nothing is uploaded before an update, the placeholder is used
+        assert_eq!(handles[0].status(), AssetStatus::Loading);
+        assert_eq!(handles[0].get().0, "...");
+        assert!(conf.try_get().is_none());
+        assert!(assets.is_loading());
+
+        // with no time budget, one upload per update
+        let start = Instant::now();
+        let mut uploads = Vec::new();
+        while assets.is_loading() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            let n = assets.update(Duration::from_secs(0));
+            assert!(n <= 1);
+            if n > 0 {
+                uploads.push(n);
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(uploads.len(), 11);
+
+        for (i, handle) in handles.iter().enumerate() {
+            assert!(handle.is_ready());
+            assert_eq!(handle.get().0, format!("text {}", i));
+        }
+        assert_eq!(conf.get().get_str("name"), "intro");
+        match missing.status() {
+            AssetStatus::Failed(msg) => assert!(msg.contains("missing.txt")),
+            status => panic!("expected a failure, got {:?}", status),
+        }
+        assert_eq!(missing.get().0, "...");
+        assert_eq!(empty.status(), AssetStatus::Failed("invalid asset text: empty".to_string()));
+
+        // loads whose handles are dropped are not uploaded
+        drop(handles);
+        drop(dropped);
+        let a: Handle<Text> = assets.load_async("loading_test/0.txt");
+        drop(assets.load_async::<Text>("loading_test/1.txt"));
+        assets.finish_loading();
+        assert!(a.is_ready() && !assets.is_loading());
+        assert_eq!(assets.loaded::<Text>(), vec!["loading_test/0.txt", "loading_test/empty.txt",
+                                                 "loading_test/missing.txt"]);
+
+        // a sync load of a path that is loading gets the same handle
+        let b: Handle<Text> = assets.load_async("loading_test/2.txt");
+        let c: Handle<Text> = assets.load("loading_test/2.txt").unwrap();
+        assert!(b.same_asset(&c));
+        assets.finish_loading();
+        assert_eq!(c.get().0, "text 2");
+
+        // a panicking decoder fails its load, and the worker goes on
+        struct Broken;
+        impl Asset for Broken {
+            fn load(path: &str) -> Result<Broken, AssetError> {
+                Broken::upload(Broken::decode(path)?)
+            }
+        }
+        impl AsyncAsset for Broken {
+            type Data = ();
+            fn decode(_path: &str) -> Result<(), AssetError> {
+                panic!("malformed file");
+            }
+            fn upload(_data: ()) -> Result<Broken, AssetError> {
+                Ok(Broken)
+            }
+        }
+
+        let mut assets = AssetManager::new();
+        assets.set_worker_count(1);
+        let broken: Handle<Broken> = assets.load_async("loading_test/0.txt");
+        let text: Handle<Text> = assets.load_async("loading_test/3.txt");
+        assets.finish_loading();
+        assert_eq!(broken.status(),
+                   AssetStatus::Failed("invalid asset loading_test/0.txt: decoder panicked".to_string()));
+        assert_eq!(text.get().0, "text 3");
+        assert!(!assets.is_loading());
+
+        // texture data too short for its size is refused before reaching GL
+        use renderer::texture::{Texture, TextureData, TextureFmt};
+        let short = TextureData { size: (2, 2), fmt: TextureFmt::RGBA8U, pixels: vec![0; 12], source_file: None };
+        assert!(::std::panic::catch_unwind(|| Texture::from_data(&short)).is_err());
+    }
+
+    #[test]
+    fn asset_reload_tests() {
+        use std::fs;
+        use system::asset::*;
+        use system::filesystem;
+        use system::vfs;
+
+        // a program-like asset : two source files, and state set by its users
+        struct Shaders { sources: Vec<String>, files: Vec<String>, uniform: i32 }
+        impl Asset for Shaders {
+            fn load(path: &str) -> Result<Shaders, AssetError> {
+                let files = vec![format!("{}.vs", path), format!("{}.frag", path)];
+                let mut sources = Vec::new();
+                for file in files.iter() {
+                    let source = filesystem::read_to_string(file)?;
+                    if source.contains("error") {
+                        return Err(AssetError::invalid(file, "syntax error"));
+                    }
+                    sources.push(source);
+                }
+                Ok(Shaders { sources: sources, files: files, uniform: 0 })
+            }
+            fn source_files(&self) -> Vec<String> {
+                self.files.clone()
+            }
+            fn reloaded(&mut self, previous: &Shaders) {
+                self.uniform = previous.uniform;
+            }
+        }
+
+        let dir = ::std::env::temp_dir().join("radar_asset_reload_test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("test.vs"), "vertex 1").unwrap();
+        fs::write(dir.join("test.frag"), "fragment 1").unwrap();
+        fs::write(dir.join("other.vs"), "vertex").unwrap();
+        fs::write(dir.join("other.frag"), "fragment").unwrap();
+        vfs::mount_dir("reload_test", &dir, 0).unwrap();
+
+        let mut assets = AssetManager::new();
+        let shaders: Handle<Shaders> = assets.load("reload_test/test").unwrap();
+        let other: Handle<Shaders> = assets.load("reload_test/other").unwrap();
+        shaders.get_mut().uniform = 42;
+        assert!(assets.reload_changed().is_empty());
+
+        // a change to any source file reloads the asset in place
+        fs::write(dir.join("test.frag"), "fragment 2 !").unwrap();
+        let results = assets.reload_changed();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "reload_test/test");
+        assert!(results[0].1.is_ok());
+        assert_eq!(shaders.get().sources, vec!["vertex 1", "fragment 2 !"]);
+        assert_eq!(shaders.get().uniform, 42);
+        assert_eq!(other.get().sources, vec!["vertex", "fragment"]);
+        assert!(assets.reload_changed().is_empty());
+
+        // a broken file keeps the previous version, and is reported once
+        fs::write(dir.join("test.vs"), "vertex error").unwrap();
+        let results = assets.reload_changed();
+        match results[0].1 {
+            Err(ref err) => assert_eq!(err.to_string(), "invalid asset reload_test/test.vs: syntax error"),
+            Ok(()) => panic!("expected a reload error"),
+        }
+        assert_eq!(shaders.get().sources[0], "vertex 1");
+        assert!(shaders.is_ready());
+        assert!(assets.reload_changed().is_empty());
+
+        // fixed
+        fs::write(dir.join("test.vs"), "vertex 3").unwrap();
+        assert!(assets.reload_changed()[0].1.is_ok());
+        assert_eq!(shaders.get().sources, vec!["vertex 3", "fragment 2 !"]);
+        assert_eq!(shaders.get().uniform, 42);
+
+        // dropped assets are not reloaded
+        drop(other);
+        fs::write(dir.join("other.vs"), "vertex 2").unwrap();
+        assert!(assets.reload_changed().is_empty());
+
+        // an async load that failed is reloaded once its file is fixed,
+        // although its placeholder has no source files
+        struct Image { pixels: String, file: Option<String> }
+        impl Asset for Image {
+            fn load(path: &str) -> Result<Image, AssetError> {
+                Image::upload(Image::decode(path)?)
+            }
+            fn source_files(&self) -> Vec<String> {
+                self.file.iter().cloned().collect()
+            }
+        }
+        impl AsyncAsset for Image {
+            type Data = (String, String);
+            fn decode(path: &str) -> Result<(String, String), AssetError> {
+                let pixels = filesystem::read_to_string(path)?;
+                if pixels.contains("error") {
+                    return Err(AssetError::invalid(path, "bad header"));
+                }
+                Ok((path.to_string(), pixels))
+            }
+            fn upload(data: (String, String)) -> Result<Image, AssetError> {
+                Ok(Image { pixels: data.1, file: Some(data.0) })
+            }
+            fn placeholder() -> Option<Image> {
+                Some(Image { pixels: "grey".to_string(), file: None })
+            }
+        }
+
+        fs::write(dir.join("rust.png"), "error").unwrap();
+        let image: Handle<Image> = assets.load_async("reload_test/rust.png");
+        assets.finish_loading();
+        assert_eq!(image.status(), AssetStatus::Failed("invalid asset reload_test/rust.png: bad header".to_string()));
+        assert!(assets.reload_changed().is_empty());
+
+        fs::write(dir.join("rust.png"), "pixels").unwrap();
+        let results = assets.reload_changed();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_ok());
+        assert!(image.is_ready());
+        assert_eq!(image.get().pixels, "pixels");
+    }
+
     #[test]
     fn fs_tests() {
         use system::filesystem;
@@ -15,11 +1892,34 @@ mod tests {
 
         // read random file
         let s = filesystem::read_file("data/config.json");
+        assert_eq!(filesystem::read_bytes("data/config.json").unwrap(), s.as_bytes());
+
+        // errors instead of panics
+        use std::path::Path;
+        use system::filesystem::FsError;
+        use system::vfs;
+        assert!(filesystem::check_extension(Path::new("data/rust.png"), &["jpg", "png"]));
+        assert!(!filesystem::check_extension(Path::new("data/rust.png"), &["jpg"]));
+        assert!(!filesystem::check_extension(Path::new("data/README"), &["png"]));
+        match filesystem::read_bytes("data/missing.png") {
+            Err(FsError::NotFound(ref path)) => assert_eq!(path, "data/missing.png"),
+            other => panic!("expected NotFound, got {:?}", other),
+        }
+
+        let dir = ::std::env::temp_dir().join("radar_fs_test");
+        ::std::fs::create_dir_all(&dir).unwrap();
+        ::std::fs::write(dir.join("latin1.txt"), b"caf\xe9 au lait").unwrap();
+        vfs::mount_dir("fs_test", &dir, 0).unwrap();
+        assert_eq!(filesystem::read_bytes("fs_test/latin1.txt").unwrap().len(), 12);
+        match filesystem::read_to_string("fs_test/latin1.txt") {
+            Err(FsError::InvalidUtf8 { offset, .. }) => assert_eq!(offset, 3),
+            other => panic!("expected InvalidUtf8, got {:?}", other),
+        }
         
         // read and decode JSON config file
         let conf = config::Config::new("data/config.json");
-        let fwinwidth = conf.get_f64("iWindowWidth");
-        let fwinheight = conf.get_u64("iWindowHeight");
+        let fwinwidth = conf.get_f64("window.width");
+        let fwinheight = conf.get_u64("window.height");
         println!("read window size : {} {}", fwinwidth, fwinheight);
     }
 }
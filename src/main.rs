@@ -5,12 +5,17 @@ mod canvas;
 
 use renderer::context::*;
 use renderer::mesh;
-use renderer::shader::{Program, Shader, ShaderType};
+use renderer::shader::Program;
 use renderer::texture;
 use math::mat4::*;
 use math::transform;
+use math::interp;
+use math::vec2::*;
 use math::vec3::*;
 use canvas::Canvas;
+use system::asset::{Asset, Handle};
+use system::config::Config;
+use system::console::{self, Console};
 
 extern crate rand;
 extern crate time;
@@ -20,6 +25,7 @@ extern crate specs;
 use specs::Join;
 
 use gl::types::*;
+use std::env;
 use std::mem;
 use std::ptr;
 use std::ffi::CString;
@@ -58,7 +64,37 @@ impl specs::Component for CompMesh{
     type Storage = specs::VecStorage<CompMesh>;     
 }
 
+/// No window : the config and the console only, with commands read from
+/// stdin
+fn run_headless(config_file: &str) {
+    let mut conf = match Config::load_layered(config_file) {
+        Ok(conf) => conf,
+        Err(err) => panic!("Error loading config file {}: {}", config_file, err)
+    };
+
+    let mut console = Console::new();
+    if let Some(path) = console::history_path() {
+        if let Err(err) = console.load_history(&path) {
+            println!("Console warning: can't read history file {}: {}", path.display(), err);
+        }
+    }
+    register_cvars(&mut console, &mut conf);
+    console.run_stdin(&mut conf);
+}
+
+fn load<T: Asset>(ctx: &mut Context, path: &str) -> Handle<T> {
+    match ctx.assets.load(path) {
+        Ok(handle) => handle,
+        Err(err) => panic!("Error loading asset : {}.", err)
+    }
+}
+
 fn main() {
+    if env::args().any(|arg| arg == "--headless") {
+        run_headless("data/config.json");
+        return;
+    }
+
     let mut VERTEX_COL_DATA: [GLfloat; 12] = [
         1.0, 1.0, 1.0, 1.0,
         1.0, 0.0, 1.0, 1.0,
@@ -67,51 +103,51 @@ fn main() {
 
     let mut ctx = Context::new("data/config.json");
 
-    let t = texture::Texture::from_image("data/rust.png");
+    // grey until it is loaded
+    let t: Handle<texture::Texture> = ctx.assets.load_async("data/rust.png");
+    let texture_filter = |conf: &system::config::Config| {
+        let name = conf.get_str_or("renderer.texture_filter", "trilinear");
+        texture::TextureFilter::from_name(name).unwrap_or_else(|| {
+            println!("Unknown texture filter {}, using trilinear.", name);
+            texture::TextureFilter::Trilinear
+        })
+    };
+    let filter_changes = ctx.config.subscribe("renderer.texture_filter");
+    let mut filter_applied = false;
 
 
-    let mut program = Program::new();
-    let vs = Shader::new(ShaderType::VERTEX,"data/shaders/test.vs".to_string());
-    let fs = Shader::new(ShaderType::FRAGMENT,"data/shaders/test.frag".to_string());
-    program.attach(&vs);
-    program.attach(&fs);
-    program.link();
-    program.register_uniform("ProjMatrix");
-    program.register_uniform("ModelMatrix");
-    program.register_uniform("diffuseTexture");
+    let program: Handle<Program> = load(&mut ctx, "data/shaders/test");
 
     let mut m0 = mesh::Mesh::new(&VERTEX_DATA, &INDEX_DATA, Some(&VERTEX_TEX_DATA), Some(&VERTEX_COL_DATA));
 
-    program.set_uniform_matrix4fv("ProjMatrix", &ctx.proj_matrix_2d);
-    program.set_uniform_matrix4fv("ModelMatrix", &Mat4::identity());
-    program.set_uniform_1i("diffuseTexture", 0);
-
-    let mut canvas_program = Program::new();
-    let canvas_vs = Shader::new(ShaderType::VERTEX, "data/shaders/canvas.vs".to_string());
-    let canvas_fs = Shader::new(ShaderType::FRAGMENT, "data/shaders/canvas.frag".to_string());
-    canvas_program.attach(&canvas_vs);
-    canvas_program.attach(&canvas_fs);
-    canvas_program.link();
-    canvas_program.register_uniform("ProjMatrix");
-    canvas_program.register_uniform("ModelMatrix");
-    canvas_program.register_uniform("backColor");
-    canvas_program.register_uniform("diffuseTexture");
-    canvas_program.register_uniform("canvasPosition");
-    canvas_program.register_uniform("canvasSize");
+    program.get().set_uniform_matrix4fv("ModelMatrix", &Mat4::identity());
+    program.get().set_uniform_1i("diffuseTexture", 0);
 
-    canvas_program.set_uniform_matrix4fv("ProjMatrix", &ctx.proj_matrix_2d);
-    canvas_program.set_uniform_1i("diffuseTexture", 0);
+    let canvas_program: Handle<Program> = load(&mut ctx, "data/shaders/canvas");
 
-    let mut canvas1 = Canvas::new((400, 200), (200, 100), &canvas_program);
+    let canvas_rect = ctx.config.get_vec4("canvas.rect");
+    let mut canvas1 = Canvas::new(Vec2::new(canvas_rect.x, canvas_rect.y),
+                                  Vec2::new(canvas_rect.z, canvas_rect.w),
+                                  canvas_program.clone());
 
     let mut rng = rand::thread_rng();
 
     let mut start_time = time::now();
     let mut accum = 0.0;
+    let mut col_from = VERTEX_COL_DATA;
+    let mut col_to = VERTEX_COL_DATA;
 
     while ctx.is_running() {
         ctx.start_frame();
 
+        if filter_changes.try_iter().count() > 0 {
+            filter_applied = false;
+        }
+        if !filter_applied && t.is_ready() {
+            t.get().set_filter(texture_filter(&ctx.config));
+            filter_applied = true;
+        }
+
         let frame_time = time::now();
         let elapsed_duration = frame_time - start_time;
         start_time = frame_time;
@@ -124,21 +160,29 @@ fn main() {
         if accum >= 1.0 {
             accum -= 1.0;
 
-            // try modifying tri color
+            // pick a new tri color to fade to
+            col_from = col_to;
             for i in 0..12 {
                 if i % 4 != 0 {
-                    VERTEX_COL_DATA[i] = rng.gen::<f32>();
+                    col_to[i] = rng.gen::<f32>();
                 }
             }
-            m0.update_buffer(mesh::MeshAttrib::Color, &VERTEX_COL_DATA);
         }
 
+        let k = interp::ease_in_out_cubic(accum as f32);
+        for i in 0..12 {
+            VERTEX_COL_DATA[i] = interp::lerp(col_from[i], col_to[i], k);
+        }
+        m0.update_buffer(mesh::MeshAttrib::Color, &VERTEX_COL_DATA);
+
         //now we update the systems
-        program.bind();
-        t.bind();
+        program.get().bind();
+        program.get().set_uniform_matrix4fv("ProjMatrix", ctx.camera_2d.view_proj_matrix());
+        t.get().bind();
         m0.render();
 
-        canvas_program.bind();
+        canvas_program.get().bind();
+        canvas_program.get().set_uniform_matrix4fv("ProjMatrix", ctx.camera_2d.view_proj_matrix());
         canvas1.update(elapsed);
         canvas1.render();
 
@@ -0,0 +1,79 @@
+use math::vec3::*;
+use math::vec4::*;
+use math::mat4::*;
+
+/// Tolerance used by approx_eq, both absolute and relative
+pub const EPSILON: f32 = 1e-5;
+
+/// Absolute comparison, for values close to zero
+pub fn abs_eq(a: f32, b: f32, epsilon: f32) -> bool {
+    a == b || (a - b).abs() <= epsilon
+}
+
+/// Relative comparison, the tolerance scales with the larger magnitude
+pub fn rel_eq(a: f32, b: f32, max_relative: f32) -> bool {
+    a == b || (a - b).abs() <= max_relative * a.abs().max(b.abs())
+}
+
+/// Map the float bits to integers ordered like the floats, -0 and +0
+/// being the same value
+fn ordered_bits(f: f32) -> i64 {
+    let i = f.to_bits() as i32 as i64;
+    if i < 0 { ::std::i32::MIN as i64 - i } else { i }
+}
+
+/// Number of representable floats between a and b. NaN is never equal
+pub fn ulps_eq(a: f32, b: f32, max_ulps: u32) -> bool {
+    if a.is_nan() || b.is_nan() {
+        return false;
+    }
+    (ordered_bits(a) - ordered_bits(b)).abs() <= max_ulps as i64
+}
+
+/// Approximate equality of floating point values. Composite types compare
+/// every component with the same tolerance.
+pub trait ApproxEq {
+    /// Apply a comparison to every pair of components
+    fn approx_eq_with<F: Fn(f32, f32) -> bool>(&self, other: &Self, cmp: F) -> bool;
+
+    fn approx_eq_abs(&self, other: &Self, epsilon: f32) -> bool {
+        self.approx_eq_with(other, |a, b| abs_eq(a, b, epsilon))
+    }
+
+    fn approx_eq_rel(&self, other: &Self, max_relative: f32) -> bool {
+        self.approx_eq_with(other, |a, b| rel_eq(a, b, max_relative))
+    }
+
+    fn approx_eq_ulps(&self, other: &Self, max_ulps: u32) -> bool {
+        self.approx_eq_with(other, |a, b| ulps_eq(a, b, max_ulps))
+    }
+
+    /// Absolute comparison near zero, relative elsewhere, with EPSILON
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.approx_eq_with(other, |a, b| abs_eq(a, b, EPSILON) || rel_eq(a, b, EPSILON))
+    }
+}
+
+impl ApproxEq for f32 {
+    fn approx_eq_with<F: Fn(f32, f32) -> bool>(&self, other: &f32, cmp: F) -> bool {
+        cmp(*self, *other)
+    }
+}
+
+impl ApproxEq for Vec3 {
+    fn approx_eq_with<F: Fn(f32, f32) -> bool>(&self, other: &Vec3, cmp: F) -> bool {
+        cmp(self.x, other.x) && cmp(self.y, other.y) && cmp(self.z, other.z)
+    }
+}
+
+impl ApproxEq for Vec4 {
+    fn approx_eq_with<F: Fn(f32, f32) -> bool>(&self, other: &Vec4, cmp: F) -> bool {
+        cmp(self.x, other.x) && cmp(self.y, other.y) && cmp(self.z, other.z) && cmp(self.w, other.w)
+    }
+}
+
+impl ApproxEq for Mat4 {
+    fn approx_eq_with<F: Fn(f32, f32) -> bool>(&self, other: &Mat4, cmp: F) -> bool {
+        (0..4).all(|i| self.m[i].approx_eq_with(&other.m[i], &cmp))
+    }
+}
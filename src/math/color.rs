@@ -0,0 +1,249 @@
+use std::ops::{Add, Mul, Sub};
+use std::fmt;
+
+use math::vec3::*;
+use math::vec4::*;
+
+/// RGBA color with float components, usually in [0, 1]. Colors are stored
+/// as given : values coming from hex strings or color pickers are sRGB
+/// encoded, convert them with to_linear() before doing lighting math.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+pub const TRANSPARENT: Color = Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
+pub const BLACK: Color = Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+pub const WHITE: Color = Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
+pub const GREY: Color = Color { r: 0.5, g: 0.5, b: 0.5, a: 1.0 };
+pub const DARK_GREY: Color = Color { r: 0.2, g: 0.2, b: 0.2, a: 1.0 };
+pub const LIGHT_GREY: Color = Color { r: 0.9, g: 0.9, b: 0.9, a: 1.0 };
+pub const RED: Color = Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 };
+pub const GREEN: Color = Color { r: 0.0, g: 1.0, b: 0.0, a: 1.0 };
+pub const BLUE: Color = Color { r: 0.0, g: 0.0, b: 1.0, a: 1.0 };
+pub const YELLOW: Color = Color { r: 1.0, g: 1.0, b: 0.0, a: 1.0 };
+pub const CYAN: Color = Color { r: 0.0, g: 1.0, b: 1.0, a: 1.0 };
+pub const MAGENTA: Color = Color { r: 1.0, g: 0.0, b: 1.0, a: 1.0 };
+pub const ORANGE: Color = Color { r: 1.0, g: 0.5, b: 0.0, a: 1.0 };
+pub const PURPLE: Color = Color { r: 0.5, g: 0.0, b: 0.5, a: 1.0 };
+
+/// Display function, as a hex string
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+/// Component-wise addition, alpha included
+impl Add for Color {
+    type Output = Color;
+    #[inline(always)]
+    fn add(self, other: Color) -> Color {
+        Color::new(self.r + other.r, self.g + other.g, self.b + other.b, self.a + other.a)
+    }
+}
+
+/// Component-wise substraction, alpha included
+impl Sub for Color {
+    type Output = Color;
+    #[inline(always)]
+    fn sub(self, other: Color) -> Color {
+        Color::new(self.r - other.r, self.g - other.g, self.b - other.b, self.a - other.a)
+    }
+}
+
+/// Component-wise modulation
+impl Mul<Color> for Color {
+    type Output = Color;
+    #[inline(always)]
+    fn mul(self, other: Color) -> Color {
+        Color::new(self.r * other.r, self.g * other.g, self.b * other.b, self.a * other.a)
+    }
+}
+
+/// Scale all components, alpha included
+impl Mul<f32> for Color {
+    type Output = Color;
+    #[inline(always)]
+    fn mul(self, other: f32) -> Color {
+        Color::new(self.r * other, self.g * other, self.b * other, self.a * other)
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_u8(c: f32) -> u8 {
+    (c.max(0.0).min(1.0) * 255.0 + 0.5) as u8
+}
+
+impl Color {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color { r: r, g: g, b: b, a: a }
+    }
+
+    /// Opaque color
+    pub fn rgb(r: f32, g: f32, b: f32) -> Color {
+        Color::new(r, g, b, 1.0)
+    }
+
+    pub fn from_rgba8(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0)
+    }
+
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        [to_u8(self.r), to_u8(self.g), to_u8(self.b), to_u8(self.a)]
+    }
+
+    /// Parse "#rgb", "#rgba", "#rrggbb" or "#rrggbbaa", the '#' being
+    /// optional. Returns None for malformed strings
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        let digits = hex.trim().trim_start_matches('#');
+        if !digits.chars().all(|c| c.is_digit(16)) {
+            return None;
+        }
+
+        let nibble = |i: usize| u8::from_str_radix(&digits[i..i + 1], 16).unwrap();
+        let byte = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap();
+
+        match digits.len() {
+            3 => Some(Color::from_rgba8(nibble(0) * 17, nibble(1) * 17, nibble(2) * 17, 255)),
+            4 => Some(Color::from_rgba8(nibble(0) * 17, nibble(1) * 17, nibble(2) * 17, nibble(3) * 17)),
+            6 => Some(Color::from_rgba8(byte(0), byte(2), byte(4), 255)),
+            8 => Some(Color::from_rgba8(byte(0), byte(2), byte(4), byte(6))),
+            _ => None,
+        }
+    }
+
+    /// Format as "#rrggbbaa"
+    pub fn to_hex(&self) -> String {
+        let c = self.to_rgba8();
+        format!("#{:02x}{:02x}{:02x}{:02x}", c[0], c[1], c[2], c[3])
+    }
+
+    /// Decode sRGB components to linear light. Alpha is left untouched
+    pub fn to_linear(&self) -> Color {
+        Color::new(srgb_to_linear(self.r), srgb_to_linear(self.g), srgb_to_linear(self.b), self.a)
+    }
+
+    /// Encode linear light components to sRGB. Alpha is left untouched
+    pub fn to_srgb(&self) -> Color {
+        Color::new(linear_to_srgb(self.r), linear_to_srgb(self.g), linear_to_srgb(self.b), self.a)
+    }
+
+    /// Color from hue in degrees, saturation and value in [0, 1]
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: f32) -> Color {
+        let h = ((h % 360.0) + 360.0) % 360.0 / 60.0;
+        let c = v * s;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = hue_sector(h, c, x);
+        let m = v - c;
+        Color::new(r + m, g + m, b + m, a)
+    }
+
+    /// Return (hue in degrees, saturation, value)
+    pub fn to_hsv(&self) -> Vec3 {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+        let s = if max > 0.0 { delta / max } else { 0.0 };
+        Vec3::new(self.hue(max, delta), s, max)
+    }
+
+    /// Color from hue in degrees, saturation and lightness in [0, 1]
+    pub fn from_hsl(h: f32, s: f32, l: f32, a: f32) -> Color {
+        let h = ((h % 360.0) + 360.0) % 360.0 / 60.0;
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = hue_sector(h, c, x);
+        let m = l - c * 0.5;
+        Color::new(r + m, g + m, b + m, a)
+    }
+
+    /// Return (hue in degrees, saturation, lightness)
+    pub fn to_hsl(&self) -> Vec3 {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+        let l = (max + min) * 0.5;
+        let s = if delta > 0.0 { delta / (1.0 - (2.0 * l - 1.0).abs()) } else { 0.0 };
+        Vec3::new(self.hue(max, delta), s, l)
+    }
+
+    fn hue(&self, max: f32, delta: f32) -> f32 {
+        if delta <= 0.0 {
+            return 0.0;
+        }
+        let h = if max == self.r {
+            ((self.g - self.b) / delta) % 6.0
+        } else if max == self.g {
+            (self.b - self.r) / delta + 2.0
+        } else {
+            (self.r - self.g) / delta + 4.0
+        };
+        (h * 60.0 + 360.0) % 360.0
+    }
+
+    /// Multiply the color components by alpha
+    pub fn premultiply(&self) -> Color {
+        Color::new(self.r * self.a, self.g * self.a, self.b * self.a, self.a)
+    }
+
+    /// Inverse of premultiply. Fully transparent colors become black
+    pub fn unpremultiply(&self) -> Color {
+        if self.a > 0.0 {
+            Color::new(self.r / self.a, self.g / self.a, self.b / self.a, self.a)
+        } else {
+            TRANSPARENT
+        }
+    }
+
+    pub fn with_alpha(&self, a: f32) -> Color {
+        Color::new(self.r, self.g, self.b, a)
+    }
+
+    pub fn from_vec4(v: Vec4) -> Color {
+        Color::new(v.x, v.y, v.z, v.w)
+    }
+
+    pub fn to_vec4(&self) -> Vec4 {
+        Vec4::new(self.r, self.g, self.b, self.a)
+    }
+
+    pub fn as_ptr(&self) -> *const f32 {
+        &self.r as *const f32
+    }
+}
+
+/// rgb before lightness offset, for h in [0, 6)
+fn hue_sector(h: f32, c: f32, x: f32) -> (f32, f32, f32) {
+    if h < 1.0 {
+        (c, x, 0.0)
+    } else if h < 2.0 {
+        (x, c, 0.0)
+    } else if h < 3.0 {
+        (0.0, c, x)
+    } else if h < 4.0 {
+        (0.0, x, c)
+    } else if h < 5.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    }
+}
@@ -0,0 +1,333 @@
+use std::f32;
+
+use math::vec3::*;
+use math::vec4::*;
+use math::mat4::*;
+
+/// Plane defined by dot(normal, p) + d = 0. The normal points towards the
+/// positive half-space.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub d: f32,
+}
+
+impl Plane {
+    pub fn new(normal: Vec3, d: f32) -> Plane {
+        Plane { normal: normal, d: d }
+    }
+
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Plane {
+        let n = Vec3::normalize(normal);
+        Plane { normal: n, d: -Vec3::dot(n, point) }
+    }
+
+    /// Plane passing through 3 points, facing the side from which they
+    /// appear counter-clockwise
+    pub fn from_points(a: Vec3, b: Vec3, c: Vec3) -> Plane {
+        Plane::from_point_normal(a, Vec3::cross(b - a, c - a))
+    }
+
+    /// Plane from its (a, b, c, d) equation coefficients, normalized
+    pub fn from_vec4(v: Vec4) -> Plane {
+        Plane::normalize(Plane { normal: Vec3::new(v.x, v.y, v.z), d: v.w })
+    }
+
+    pub fn normalize(p: Plane) -> Plane {
+        let len = Vec3::length(p.normal);
+        if len > 0.0 {
+            Plane { normal: p.normal / len, d: p.d / len }
+        } else {
+            p
+        }
+    }
+
+    /// Signed distance from the plane, positive in front of it
+    pub fn distance(&self, p: Vec3) -> f32 {
+        Vec3::dot(self.normal, p) + self.d
+    }
+}
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub dir: Vec3,
+}
+
+impl Ray {
+    /// Create a ray, dir is normalized so that t values are distances
+    pub fn new(origin: Vec3, dir: Vec3) -> Ray {
+        Ray { origin: origin, dir: Vec3::normalize(dir) }
+    }
+
+    /// Point along the ray at parameter t
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.dir * t
+    }
+
+    /// Return the ray parameter of the intersection with the plane, if any
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denom = Vec3::dot(plane.normal, self.dir);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+        let t = -plane.distance(self.origin) / denom;
+        if t >= 0.0 { Some(t) } else { None }
+    }
+
+    /// Return the ray parameter where the ray enters the box, or 0 if the
+    /// origin is inside of it
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut tmin = 0.0f32;
+        let mut tmax = f32::INFINITY;
+
+        for i in 0..3 {
+            if self.dir[i].abs() < 1e-8 {
+                // parallel to the slab, must already be in it
+                if self.origin[i] < aabb.min[i] || self.origin[i] > aabb.max[i] {
+                    return None;
+                }
+            } else {
+                let inv = 1.0 / self.dir[i];
+                let mut t0 = (aabb.min[i] - self.origin[i]) * inv;
+                let mut t1 = (aabb.max[i] - self.origin[i]) * inv;
+                if t0 > t1 {
+                    let tmp = t0;
+                    t0 = t1;
+                    t1 = tmp;
+                }
+                tmin = tmin.max(t0);
+                tmax = tmax.min(t1);
+                if tmin > tmax {
+                    return None;
+                }
+            }
+        }
+        Some(tmin)
+    }
+
+    /// Return the ray parameter of the first hit with the sphere, or 0 if
+    /// the origin is inside of it
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let oc = self.origin - sphere.center;
+        let b = Vec3::dot(oc, self.dir);
+        let c = Vec3::dot(oc, oc) - sphere.radius * sphere.radius;
+
+        // origin outside and pointing away
+        if c > 0.0 && b > 0.0 {
+            return None;
+        }
+
+        let disc = b * b - c;
+        if disc < 0.0 {
+            return None;
+        }
+        let t = -b - disc.sqrt();
+        if t < 0.0 { Some(0.0) } else { Some(t) }
+    }
+
+    /// Moller-Trumbore intersection with the triangle (a, b, c), double
+    /// sided. Return the ray parameter and the barycentric (u, v) of the hit
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<(f32, f32, f32)> {
+        let e1 = b - a;
+        let e2 = c - a;
+        let p = Vec3::cross(self.dir, e2);
+        let det = Vec3::dot(e1, p);
+        if det.abs() < 1e-8 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let s = self.origin - a;
+        let u = Vec3::dot(s, p) * inv_det;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+
+        let q = Vec3::cross(s, e1);
+        let v = Vec3::dot(self.dir, q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = Vec3::dot(e2, q) * inv_det;
+        if t >= 0.0 { Some((t, u, v)) } else { None }
+    }
+}
+
+/// Axis aligned bounding box
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Aabb {
+        Aabb { min: min, max: max }
+    }
+
+    /// Smallest box containing all the given points
+    pub fn from_points(points: &[Vec3]) -> Aabb {
+        let mut aabb = Aabb::empty();
+        for p in points {
+            aabb.expand(*p);
+        }
+        aabb
+    }
+
+    /// Inverted box that any expand() will fix
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn expand(&mut self, p: Vec3) {
+        for i in 0..3 {
+            self.min[i] = self.min[i].min(p[i]);
+            self.max[i] = self.max[i].max(p[i]);
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Half size of the box
+    pub fn extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x &&
+        p.y >= self.min.y && p.y <= self.max.y &&
+        p.z >= self.min.z && p.z <= self.max.z
+    }
+
+    pub fn intersects_aabb(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x &&
+        self.min.y <= other.max.y && self.max.y >= other.min.y &&
+        self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    /// Point of the box closest to p
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        Vec3::new(p.x.max(self.min.x).min(self.max.x),
+                  p.y.max(self.min.y).min(self.max.y),
+                  p.z.max(self.min.z).min(self.max.z))
+    }
+}
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32) -> Sphere {
+        Sphere { center: center, radius: radius }
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        let d = p - self.center;
+        Vec3::dot(d, d) <= self.radius * self.radius
+    }
+
+    pub fn intersects_sphere(&self, other: &Sphere) -> bool {
+        let d = other.center - self.center;
+        let r = self.radius + other.radius;
+        Vec3::dot(d, d) <= r * r
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.contains_point(aabb.closest_point(self.center))
+    }
+}
+
+/// Result of a volume test against a frustum
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Containment {
+    Outside,
+    Intersecting,
+    Inside,
+}
+
+/// Depth range of the clip space a projection matrix maps to
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum DepthRange {
+    /// OpenGL convention, as used by Mat4::persp
+    MinusOneToOne,
+    /// as used by Mat4::ortho
+    ZeroToOne,
+}
+
+/// View frustum as 6 inward facing planes :
+/// left, right, bottom, top, near, far
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extract the frustum planes of a (view-)projection matrix, in the
+    /// space the matrix transforms from (world space for a view-projection)
+    pub fn from_matrix(m: &Mat4, depth: DepthRange) -> Frustum {
+        // rows of the matrix, as clip = m * v
+        let row = |k: usize| Vec4::new(m[0][k], m[1][k], m[2][k], m[3][k]);
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+
+        let near = match depth {
+            DepthRange::MinusOneToOne => r3 + r2,
+            DepthRange::ZeroToOne => r2,
+        };
+
+        Frustum {
+            planes: [Plane::from_vec4(r3 + r0),
+                     Plane::from_vec4(r3 - r0),
+                     Plane::from_vec4(r3 + r1),
+                     Plane::from_vec4(r3 - r1),
+                     Plane::from_vec4(near),
+                     Plane::from_vec4(r3 - r2)],
+        }
+    }
+
+    pub fn contains_point(&self, p: Vec3) -> bool {
+        self.planes.iter().all(|pl| pl.distance(p) >= 0.0)
+    }
+
+    pub fn classify_sphere(&self, s: &Sphere) -> Containment {
+        let mut result = Containment::Inside;
+        for pl in self.planes.iter() {
+            let dist = pl.distance(s.center);
+            if dist < -s.radius {
+                return Containment::Outside;
+            } else if dist < s.radius {
+                result = Containment::Intersecting;
+            }
+        }
+        result
+    }
+
+    /// Conservative classification : boxes close to a frustum corner may
+    /// be reported as intersecting while being outside
+    pub fn classify_aabb(&self, aabb: &Aabb) -> Containment {
+        let center = aabb.center();
+        let ext = aabb.extents();
+
+        let mut result = Containment::Inside;
+        for pl in self.planes.iter() {
+            // projected radius of the box on the plane normal
+            let r = ext.x * pl.normal.x.abs() + ext.y * pl.normal.y.abs() + ext.z * pl.normal.z.abs();
+            let dist = pl.distance(center);
+            if dist < -r {
+                return Containment::Outside;
+            } else if dist < r {
+                result = Containment::Intersecting;
+            }
+        }
+        result
+    }
+}
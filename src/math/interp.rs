@@ -0,0 +1,382 @@
+use std::ops::{Add, Mul, Sub};
+use std::f32::consts::PI;
+
+use math::vec2::*;
+use math::vec3::*;
+use math::vec4::*;
+
+/// Types that can be interpolated and measured : f32 and the vectors
+pub trait VectorSpace: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f32, Output = Self> {
+    fn length(a: Self) -> f32;
+}
+
+impl VectorSpace for f32 {
+    fn length(a: f32) -> f32 {
+        a.abs()
+    }
+}
+
+impl VectorSpace for Vec2 {
+    fn length(a: Vec2) -> f32 {
+        Vec2::length(a)
+    }
+}
+
+impl VectorSpace for Vec3 {
+    fn length(a: Vec3) -> f32 {
+        Vec3::length(a)
+    }
+}
+
+impl VectorSpace for Vec4 {
+    fn length(a: Vec4) -> f32 {
+        Vec4::length(a)
+    }
+}
+
+/// Linear interpolation, a at t = 0 and b at t = 1
+pub fn lerp<T>(a: T, b: T, t: f32) -> T
+    where T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>
+{
+    a + (b - a) * t
+}
+
+/// Inverse of lerp : return t such as lerp(a, b, t) == v
+pub fn inverse_lerp(a: f32, b: f32, v: f32) -> f32 {
+    if a == b { 0.0 } else { (v - a) / (b - a) }
+}
+
+/// Map v from the [in_min, in_max] range to the [out_min, out_max] range
+pub fn remap(v: f32, in_min: f32, in_max: f32, out_min: f32, out_max: f32) -> f32 {
+    lerp(out_min, out_max, inverse_lerp(in_min, in_max, v))
+}
+
+pub fn clamp01(t: f32) -> f32 {
+    t.max(0.0).min(1.0)
+}
+
+/// Hermite interpolation between 0 and 1 when x goes from edge0 to edge1
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = clamp01(inverse_lerp(edge0, edge1, x));
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Ken Perlin's smootherstep, with zero 1st and 2nd order derivatives at the edges
+pub fn smootherstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = clamp01(inverse_lerp(edge0, edge1, x));
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+// Robert Penner's easing equations. All take t in [0, 1] and return 0 at
+// t = 0 and 1 at t = 1.
+
+pub fn ease_in_quad(t: f32) -> f32 { t * t }
+pub fn ease_out_quad(t: f32) -> f32 { t * (2.0 - t) }
+pub fn ease_in_out_quad(t: f32) -> f32 {
+    if t < 0.5 { 2.0 * t * t } else { -1.0 + (4.0 - 2.0 * t) * t }
+}
+
+pub fn ease_in_cubic(t: f32) -> f32 { t * t * t }
+pub fn ease_out_cubic(t: f32) -> f32 { let u = t - 1.0; u * u * u + 1.0 }
+pub fn ease_in_out_cubic(t: f32) -> f32 {
+    if t < 0.5 { 4.0 * t * t * t } else { let u = 2.0 * t - 2.0; 0.5 * u * u * u + 1.0 }
+}
+
+pub fn ease_in_quart(t: f32) -> f32 { t * t * t * t }
+pub fn ease_out_quart(t: f32) -> f32 { let u = t - 1.0; 1.0 - u * u * u * u }
+pub fn ease_in_out_quart(t: f32) -> f32 {
+    if t < 0.5 { 8.0 * t * t * t * t } else { let u = t - 1.0; 1.0 - 8.0 * u * u * u * u }
+}
+
+pub fn ease_in_quint(t: f32) -> f32 { t * t * t * t * t }
+pub fn ease_out_quint(t: f32) -> f32 { let u = t - 1.0; 1.0 + u * u * u * u * u }
+pub fn ease_in_out_quint(t: f32) -> f32 {
+    if t < 0.5 { 16.0 * t * t * t * t * t } else { let u = 2.0 * t - 2.0; 0.5 * u * u * u * u * u + 1.0 }
+}
+
+pub fn ease_in_sine(t: f32) -> f32 { 1.0 - (t * PI * 0.5).cos() }
+pub fn ease_out_sine(t: f32) -> f32 { (t * PI * 0.5).sin() }
+pub fn ease_in_out_sine(t: f32) -> f32 { 0.5 * (1.0 - (PI * t).cos()) }
+
+pub fn ease_in_expo(t: f32) -> f32 {
+    if t <= 0.0 { 0.0 } else { (2.0f32).powf(10.0 * (t - 1.0)) }
+}
+pub fn ease_out_expo(t: f32) -> f32 {
+    if t >= 1.0 { 1.0 } else { 1.0 - (2.0f32).powf(-10.0 * t) }
+}
+pub fn ease_in_out_expo(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else if t < 0.5 {
+        0.5 * (2.0f32).powf(20.0 * t - 10.0)
+    } else {
+        1.0 - 0.5 * (2.0f32).powf(-20.0 * t + 10.0)
+    }
+}
+
+pub fn ease_in_circ(t: f32) -> f32 { 1.0 - (1.0 - t * t).max(0.0).sqrt() }
+pub fn ease_out_circ(t: f32) -> f32 { let u = t - 1.0; (1.0 - u * u).max(0.0).sqrt() }
+pub fn ease_in_out_circ(t: f32) -> f32 {
+    if t < 0.5 {
+        0.5 * ease_in_circ(2.0 * t)
+    } else {
+        0.5 + 0.5 * ease_out_circ(2.0 * t - 1.0)
+    }
+}
+
+const BACK_S: f32 = 1.70158;
+
+pub fn ease_in_back(t: f32) -> f32 { t * t * ((BACK_S + 1.0) * t - BACK_S) }
+pub fn ease_out_back(t: f32) -> f32 { let u = t - 1.0; u * u * ((BACK_S + 1.0) * u + BACK_S) + 1.0 }
+pub fn ease_in_out_back(t: f32) -> f32 {
+    if t < 0.5 {
+        0.5 * ease_in_back(2.0 * t)
+    } else {
+        0.5 + 0.5 * ease_out_back(2.0 * t - 1.0)
+    }
+}
+
+pub fn ease_in_elastic(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return clamp01(t);
+    }
+    -(2.0f32).powf(10.0 * (t - 1.0)) * ((t - 1.075) * 2.0 * PI / 0.3).sin()
+}
+pub fn ease_out_elastic(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return clamp01(t);
+    }
+    (2.0f32).powf(-10.0 * t) * ((t - 0.075) * 2.0 * PI / 0.3).sin() + 1.0
+}
+pub fn ease_in_out_elastic(t: f32) -> f32 {
+    if t < 0.5 {
+        0.5 * ease_in_elastic(2.0 * t)
+    } else {
+        0.5 + 0.5 * ease_out_elastic(2.0 * t - 1.0)
+    }
+}
+
+pub fn ease_out_bounce(t: f32) -> f32 {
+    if t < 1.0 / 2.75 {
+        7.5625 * t * t
+    } else if t < 2.0 / 2.75 {
+        let u = t - 1.5 / 2.75;
+        7.5625 * u * u + 0.75
+    } else if t < 2.5 / 2.75 {
+        let u = t - 2.25 / 2.75;
+        7.5625 * u * u + 0.9375
+    } else {
+        let u = t - 2.625 / 2.75;
+        7.5625 * u * u + 0.984375
+    }
+}
+pub fn ease_in_bounce(t: f32) -> f32 { 1.0 - ease_out_bounce(1.0 - t) }
+pub fn ease_in_out_bounce(t: f32) -> f32 {
+    if t < 0.5 {
+        0.5 * ease_in_bounce(2.0 * t)
+    } else {
+        0.5 + 0.5 * ease_out_bounce(2.0 * t - 1.0)
+    }
+}
+
+/// Easing curve selection, for animations described as data
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Easing {
+    Linear,
+    InQuad, OutQuad, InOutQuad,
+    InCubic, OutCubic, InOutCubic,
+    InQuart, OutQuart, InOutQuart,
+    InQuint, OutQuint, InOutQuint,
+    InSine, OutSine, InOutSine,
+    InExpo, OutExpo, InOutExpo,
+    InCirc, OutCirc, InOutCirc,
+    InBack, OutBack, InOutBack,
+    InElastic, OutElastic, InOutElastic,
+    InBounce, OutBounce, InOutBounce,
+}
+
+impl Easing {
+    /// Evaluate the curve, t is clamped to [0, 1]
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = clamp01(t);
+        match *self {
+            Easing::Linear => t,
+            Easing::InQuad => ease_in_quad(t),
+            Easing::OutQuad => ease_out_quad(t),
+            Easing::InOutQuad => ease_in_out_quad(t),
+            Easing::InCubic => ease_in_cubic(t),
+            Easing::OutCubic => ease_out_cubic(t),
+            Easing::InOutCubic => ease_in_out_cubic(t),
+            Easing::InQuart => ease_in_quart(t),
+            Easing::OutQuart => ease_out_quart(t),
+            Easing::InOutQuart => ease_in_out_quart(t),
+            Easing::InQuint => ease_in_quint(t),
+            Easing::OutQuint => ease_out_quint(t),
+            Easing::InOutQuint => ease_in_out_quint(t),
+            Easing::InSine => ease_in_sine(t),
+            Easing::OutSine => ease_out_sine(t),
+            Easing::InOutSine => ease_in_out_sine(t),
+            Easing::InExpo => ease_in_expo(t),
+            Easing::OutExpo => ease_out_expo(t),
+            Easing::InOutExpo => ease_in_out_expo(t),
+            Easing::InCirc => ease_in_circ(t),
+            Easing::OutCirc => ease_out_circ(t),
+            Easing::InOutCirc => ease_in_out_circ(t),
+            Easing::InBack => ease_in_back(t),
+            Easing::OutBack => ease_out_back(t),
+            Easing::InOutBack => ease_in_out_back(t),
+            Easing::InElastic => ease_in_elastic(t),
+            Easing::OutElastic => ease_out_elastic(t),
+            Easing::InOutElastic => ease_in_out_elastic(t),
+            Easing::InBounce => ease_in_bounce(t),
+            Easing::OutBounce => ease_out_bounce(t),
+            Easing::InOutBounce => ease_in_out_bounce(t),
+        }
+    }
+
+    /// Interpolate between a and b following the curve
+    pub fn ease<T>(&self, a: T, b: T, t: f32) -> T
+        where T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>
+    {
+        lerp(a, b, self.apply(t))
+    }
+}
+
+/// Parametric curve, evaluated for t in [0, 1]
+pub trait Curve<T> {
+    fn eval(&self, t: f32) -> T;
+}
+
+pub fn quadratic_bezier<T: VectorSpace>(p0: T, p1: T, p2: T, t: f32) -> T {
+    let u = 1.0 - t;
+    p0 * (u * u) + p1 * (2.0 * u * t) + p2 * (t * t)
+}
+
+pub fn cubic_bezier<T: VectorSpace>(p0: T, p1: T, p2: T, p3: T, t: f32) -> T {
+    let u = 1.0 - t;
+    p0 * (u * u * u) + p1 * (3.0 * u * u * t) + p2 * (3.0 * u * t * t) + p3 * (t * t * t)
+}
+
+/// Uniform Catmull-Rom segment going from p1 (t = 0) to p2 (t = 1)
+pub fn catmull_rom<T: VectorSpace>(p0: T, p1: T, p2: T, p3: T, t: f32) -> T {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0 +
+     (p2 - p0) * t +
+     (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 +
+     (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5
+}
+
+pub struct QuadraticBezier<T> {
+    pub points: [T; 3],
+}
+
+impl<T: VectorSpace> Curve<T> for QuadraticBezier<T> {
+    fn eval(&self, t: f32) -> T {
+        quadratic_bezier(self.points[0], self.points[1], self.points[2], t)
+    }
+}
+
+pub struct CubicBezier<T> {
+    pub points: [T; 4],
+}
+
+impl<T: VectorSpace> Curve<T> for CubicBezier<T> {
+    fn eval(&self, t: f32) -> T {
+        cubic_bezier(self.points[0], self.points[1], self.points[2], self.points[3], t)
+    }
+}
+
+/// Catmull-Rom spline passing through all its points. The first and last
+/// points are duplicated as end tangent controls.
+pub struct CatmullRom<T> {
+    pub points: Vec<T>,
+}
+
+impl<T: VectorSpace> CatmullRom<T> {
+    pub fn new(points: Vec<T>) -> CatmullRom<T> {
+        CatmullRom { points: points }
+    }
+}
+
+impl<T: VectorSpace> Curve<T> for CatmullRom<T> {
+    fn eval(&self, t: f32) -> T {
+        let n = self.points.len();
+        assert!(n >= 2, "A Catmull-Rom spline needs at least 2 points");
+
+        let seg_count = n - 1;
+        let f = clamp01(t) * seg_count as f32;
+        let seg = (f as usize).min(seg_count - 1);
+        let local = f - seg as f32;
+
+        let p1 = self.points[seg];
+        let p2 = self.points[seg + 1];
+        let p0 = if seg > 0 { self.points[seg - 1] } else { p1 };
+        let p3 = if seg + 2 < n { self.points[seg + 2] } else { p2 };
+        catmull_rom(p0, p1, p2, p3, local)
+    }
+}
+
+/// Arc-length table of a curve, mapping distances along the curve to
+/// curve parameters for constant speed motion
+pub struct ArcLength {
+    /// cumulative length at parameter i / (len - 1)
+    lengths: Vec<f32>,
+}
+
+impl ArcLength {
+    /// Approximate the curve by `subdivisions` linear segments
+    pub fn new<T: VectorSpace, C: Curve<T>>(curve: &C, subdivisions: usize) -> ArcLength {
+        let n = subdivisions.max(1);
+        let mut lengths = Vec::with_capacity(n + 1);
+        let mut total = 0.0;
+        let mut prev = curve.eval(0.0);
+        lengths.push(0.0);
+        for i in 1..(n + 1) {
+            let p = curve.eval(i as f32 / n as f32);
+            total += T::length(p - prev);
+            lengths.push(total);
+            prev = p;
+        }
+        ArcLength { lengths: lengths }
+    }
+
+    pub fn total_length(&self) -> f32 {
+        *self.lengths.last().unwrap()
+    }
+
+    /// Curve parameter at distance s along the curve
+    pub fn param_at_distance(&self, s: f32) -> f32 {
+        let n = self.lengths.len() - 1;
+        let s = s.max(0.0).min(self.total_length());
+
+        // first sample not before s
+        let i = match self.lengths.binary_search_by(|l| l.partial_cmp(&s).unwrap()) {
+            Ok(i) => return i as f32 / n as f32,
+            Err(i) => i,
+        };
+        if i == 0 {
+            return 0.0;
+        }
+        let seg = inverse_lerp(self.lengths[i - 1], self.lengths[i], s);
+        (i as f32 - 1.0 + seg) / n as f32
+    }
+
+    /// Curve parameter at a fraction in [0, 1] of the total length
+    pub fn param_at_fraction(&self, f: f32) -> f32 {
+        self.param_at_distance(clamp01(f) * self.total_length())
+    }
+}
+
+/// Return count points evenly spaced along the curve, ends included
+pub fn sample_uniform<T: VectorSpace, C: Curve<T>>(curve: &C, count: usize) -> Vec<T> {
+    let table = ArcLength::new(curve, count.max(2) * 8);
+    let mut out = Vec::with_capacity(count);
+    for i in 0..count {
+        let f = if count > 1 { i as f32 / (count - 1) as f32 } else { 0.0 };
+        out.push(curve.eval(table.param_at_fraction(f)));
+    }
+    out
+}
@@ -0,0 +1,206 @@
+use std::ops::{Add, Mul, Div, Sub, DivAssign, MulAssign, SubAssign, AddAssign, Index, IndexMut};
+use std::fmt;
+
+use math::vec2::*;
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Mat2 {
+    pub m: [Vec2; 2],
+}
+
+impl fmt::Display for Mat2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Mat2({},{})", self.m[0], self.m[1])
+    }
+}
+
+impl Add<Mat2> for Mat2 {
+    type Output = Mat2;
+    #[inline(always)]
+    fn add(self, other: Mat2) -> Mat2 {
+        Mat2 { m: [self.m[0] + other.m[0], self.m[1] + other.m[1]] }
+    }
+}
+
+impl AddAssign<Mat2> for Mat2 {
+    #[inline(always)]
+    fn add_assign(&mut self, other: Mat2) {
+        self.m[0] += other.m[0];
+        self.m[1] += other.m[1];
+    }
+}
+
+impl Sub<Mat2> for Mat2 {
+    type Output = Mat2;
+    #[inline(always)]
+    fn sub(self, other: Mat2) -> Mat2 {
+        Mat2 { m: [self.m[0] - other.m[0], self.m[1] - other.m[1]] }
+    }
+}
+
+impl SubAssign<Mat2> for Mat2 {
+    fn sub_assign(&mut self, other: Mat2) {
+        self.m[0] -= other.m[0];
+        self.m[1] -= other.m[1];
+    }
+}
+
+/// Same convention as Mat4 : a * b applies a first, then b
+impl Mul<Mat2> for Mat2 {
+    type Output = Mat2;
+    #[inline(always)]
+    fn mul(self, other: Mat2) -> Mat2 {
+        let mut res = Mat2::empty();
+        for c in 0..2 {
+            for r in 0..2 {
+                res[c][r] = 0.0;
+                for k in 0..2 {
+                    res[c][r] += self.m[c][k] * other.m[k][r];
+                }
+            }
+        }
+        res
+    }
+}
+
+/// scalar multiplication of a matrix
+impl Mul<f32> for Mat2 {
+    type Output = Mat2;
+    #[inline(always)]
+    fn mul(self, other: f32) -> Mat2 {
+        Mat2 { m: [self.m[0] * other, self.m[1] * other] }
+    }
+}
+
+/// Transform a Vector by the Matrix
+impl Mul<Vec2> for Mat2 {
+    type Output = Vec2;
+    #[inline(always)]
+    fn mul(self, other : Vec2) -> Vec2 {
+        self.m[0] * other.x + self.m[1] * other.y
+    }
+}
+
+impl MulAssign<f32> for Mat2 {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: f32) {
+        self.m[0] *= other;
+        self.m[1] *= other;
+    }
+}
+
+impl MulAssign<Mat2> for Mat2 {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: Mat2) {
+        *self = *self * other;
+    }
+}
+
+/// Read only access to specified column of the matrix
+impl Index<usize> for Mat2 {
+    type Output = Vec2;
+    fn index<'a>(&'a self, column: usize) -> &'a Vec2 {
+        match column {
+            i @ 0...1 => &self.m[i],
+            _ => panic!("Invalid index access in Mat2 {:?}"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Mat2 {
+    fn index_mut<'a>(&'a mut self, _index: usize) -> &'a mut Vec2 {
+        match _index {
+            i @ 0...1 => &mut self.m[i],
+            _ => panic!("Invalid index access in Mat2 {:?}"),
+        }
+    }
+}
+
+impl Div<Mat2> for Mat2 {
+    type Output = Mat2;
+    fn div(self, other: Mat2) -> Mat2 {
+        self * Mat2::inverse(other)
+    }
+}
+
+impl Div<f32> for Mat2 {
+    type Output = Mat2;
+    fn div(self, other: f32) -> Mat2 {
+        Mat2 { m: [self[0] / other, self[1] / other] }
+    }
+}
+
+impl DivAssign<Mat2> for Mat2 {
+    fn div_assign(&mut self, other: Mat2) {
+        *self *= Mat2::inverse(other);
+    }
+}
+
+impl DivAssign<f32> for Mat2 {
+    fn div_assign(&mut self, other: f32) {
+        *self = *self / other;
+    }
+}
+
+impl Mat2 {
+    pub fn new(a11: f32, a21: f32,
+               a12: f32, a22: f32)
+               -> Mat2 {
+        Mat2 {
+            m: [Vec2::new(a11, a12),
+                Vec2::new(a21, a22)],
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const f32 {
+        &(self[0][0]) as *const f32
+    }
+
+    pub fn from_vec2(col1: Vec2, col2: Vec2) -> Mat2 {
+        Mat2 { m: [col1, col2] }
+    }
+
+    pub fn empty() -> Mat2 {
+        Mat2 { m: [Vec2::empty(), Vec2::empty()] }
+    }
+
+    pub fn identity() -> Mat2 {
+        Mat2 {
+            m: [Vec2::new(1.0, 0.0),
+                Vec2::new(0.0, 1.0)],
+        }
+    }
+
+    /// Rotation of angle_deg degrees, counter-clockwise
+    pub fn rotation(angle_deg: f32) -> Mat2 {
+        let (sin, cos) = angle_deg.to_radians().sin_cos();
+        Mat2 {
+            m: [Vec2::new(cos, sin),
+                Vec2::new(-sin, cos)],
+        }
+    }
+
+    pub fn determinant(m: Mat2) -> f32 {
+        m[0][0] * m[1][1] - m[1][0] * m[0][1]
+    }
+
+    /// Inverse of the matrix. Returns an empty matrix if m is singular
+    pub fn inverse(m: Mat2) -> Mat2 {
+        let det = Mat2::determinant(m);
+        if det == 0.0 {
+            return Mat2::empty();
+        }
+
+        Mat2 {
+            m: [Vec2::new(m[1][1], -m[0][1]) / det,
+                Vec2::new(-m[1][0], m[0][0]) / det],
+        }
+    }
+
+    pub fn transpose(m : Mat2) -> Mat2 {
+        Mat2 {
+            m: [Vec2::new(m[0][0], m[1][0]),
+                Vec2::new(m[0][1], m[1][1])],
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::ops::{Add, Mul, Div, Sub, DivAssign, MulAssign, SubAssign, AddAssign, Index, IndexMut};
+use std::fmt;
+
+use math::vec3::*;
+use math::mat4::*;
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Mat3 {
+    pub m: [Vec3; 3],
+}
+
+impl fmt::Display for Mat3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "Mat3({},{},{})",
+               self.m[0],
+               self.m[1],
+               self.m[2])
+    }
+}
+
+impl Add<Mat3> for Mat3 {
+    type Output = Mat3;
+    #[inline(always)]
+    fn add(self, other: Mat3) -> Mat3 {
+        Mat3 {
+            m: [self.m[0] + other.m[0],
+                self.m[1] + other.m[1],
+                self.m[2] + other.m[2]],
+        }
+    }
+}
+
+impl AddAssign<Mat3> for Mat3 {
+    #[inline(always)]
+    fn add_assign(&mut self, other: Mat3) {
+        self.m[0] += other.m[0];
+        self.m[1] += other.m[1];
+        self.m[2] += other.m[2];
+    }
+}
+
+impl Sub<Mat3> for Mat3 {
+    type Output = Mat3;
+    #[inline(always)]
+    fn sub(self, other: Mat3) -> Mat3 {
+        Mat3 {
+            m: [self.m[0] - other.m[0],
+                self.m[1] - other.m[1],
+                self.m[2] - other.m[2]],
+        }
+    }
+}
+
+impl SubAssign<Mat3> for Mat3 {
+    fn sub_assign(&mut self, other: Mat3) {
+        self.m[0] -= other.m[0];
+        self.m[1] -= other.m[1];
+        self.m[2] -= other.m[2];
+    }
+}
+
+/// Same convention as Mat4 : a * b applies a first, then b
+impl Mul<Mat3> for Mat3 {
+    type Output = Mat3;
+    #[inline(always)]
+    fn mul(self, other: Mat3) -> Mat3 {
+        let mut res = Mat3::empty();
+        for c in 0..3 {
+            for r in 0..3 {
+                res[c][r] = 0.0;
+                for k in 0..3 {
+                    res[c][r] += self.m[c][k] * other.m[k][r];
+                }
+            }
+        }
+        res
+    }
+}
+
+/// scalar multiplication of a matrix
+impl Mul<f32> for Mat3 {
+    type Output = Mat3;
+    #[inline(always)]
+    fn mul(self, other: f32) -> Mat3 {
+        Mat3 { m: [self.m[0] * other, self.m[1] * other, self.m[2] * other] }
+    }
+}
+
+/// Transform a Vector by the Matrix
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+    #[inline(always)]
+    fn mul(self, other : Vec3) -> Vec3 {
+        self.m[0] * other.x + self.m[1] * other.y + self.m[2] * other.z
+    }
+}
+
+impl MulAssign<f32> for Mat3 {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: f32) {
+        self.m[0] *= other;
+        self.m[1] *= other;
+        self.m[2] *= other;
+    }
+}
+
+impl MulAssign<Mat3> for Mat3 {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: Mat3) {
+        *self = *self * other;
+    }
+}
+
+/// Read only access to specified column of the matrix
+impl Index<usize> for Mat3 {
+    type Output = Vec3;
+    fn index<'a>(&'a self, column: usize) -> &'a Vec3 {
+        match column {
+            i @ 0...2 => &self.m[i],
+            _ => panic!("Invalid index access in Mat3 {:?}"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Mat3 {
+    fn index_mut<'a>(&'a mut self, _index: usize) -> &'a mut Vec3 {
+        match _index {
+            i @ 0...2 => &mut self.m[i],
+            _ => panic!("Invalid index access in Mat3 {:?}"),
+        }
+    }
+}
+
+impl Div<Mat3> for Mat3 {
+    type Output = Mat3;
+    fn div(self, other: Mat3) -> Mat3 {
+        self * Mat3::inverse(other)
+    }
+}
+
+impl Div<f32> for Mat3 {
+    type Output = Mat3;
+    fn div(self, other: f32) -> Mat3 {
+        Mat3 { m: [self[0] / other, self[1] / other, self[2] / other] }
+    }
+}
+
+impl DivAssign<Mat3> for Mat3 {
+    fn div_assign(&mut self, other: Mat3) {
+        *self *= Mat3::inverse(other);
+    }
+}
+
+impl DivAssign<f32> for Mat3 {
+    fn div_assign(&mut self, other: f32) {
+        *self = *self / other;
+    }
+}
+
+impl Mat3 {
+    pub fn new(a11: f32, a21: f32, a31: f32,
+               a12: f32, a22: f32, a32: f32,
+               a13: f32, a23: f32, a33: f32)
+               -> Mat3 {
+        Mat3 {
+            m: [Vec3::new(a11, a12, a13),
+                Vec3::new(a21, a22, a23),
+                Vec3::new(a31, a32, a33)],
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const f32 {
+        &(self[0][0]) as *const f32
+    }
+
+    pub fn from_vec3(col1: Vec3, col2: Vec3, col3: Vec3) -> Mat3 {
+        Mat3 { m: [col1, col2, col3] }
+    }
+
+    /// return the upper-left 3x3 part of a Mat4
+    pub fn from_mat4(m: &Mat4) -> Mat3 {
+        Mat3 {
+            m: [Vec3::new(m[0][0], m[0][1], m[0][2]),
+                Vec3::new(m[1][0], m[1][1], m[1][2]),
+                Vec3::new(m[2][0], m[2][1], m[2][2])],
+        }
+    }
+
+    pub fn empty() -> Mat3 {
+        Mat3 { m: [Vec3::empty(), Vec3::empty(), Vec3::empty()] }
+    }
+
+    pub fn identity() -> Mat3 {
+        Mat3 {
+            m: [Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0)],
+        }
+    }
+
+    pub fn determinant(m: Mat3) -> f32 {
+        Vec3::dot(m[0], Vec3::cross(m[1], m[2]))
+    }
+
+    /// Inverse of the matrix. Returns an empty matrix if m is singular
+    pub fn inverse(m: Mat3) -> Mat3 {
+        let det = Mat3::determinant(m);
+        if det == 0.0 {
+            return Mat3::empty();
+        }
+
+        // rows of the inverse are the cross products of the columns
+        let r0 = Vec3::cross(m[1], m[2]) / det;
+        let r1 = Vec3::cross(m[2], m[0]) / det;
+        let r2 = Vec3::cross(m[0], m[1]) / det;
+        Mat3::transpose(Mat3::from_vec3(r0, r1, r2))
+    }
+
+    pub fn transpose(m : Mat3) -> Mat3 {
+        let mut r = Mat3::empty();
+        for j in 0..3{
+            for i in 0..3{
+                r[i][j] = m[j][i];
+            }
+        }
+        r
+    }
+
+    /// Inverse transpose of the upper 3x3 part of a model(view) matrix,
+    /// used to transform normals under non uniform scaling
+    pub fn normal_matrix(m: &Mat4) -> Mat3 {
+        Mat3::transpose(Mat3::inverse(Mat3::from_mat4(m)))
+    }
+}
@@ -5,8 +5,11 @@ use std::mem;
 
 use math::vec4::*;
 use math::vec3::*;
+use math::mat3::*;
 use math::transform::*;
+use math::simd;
 
+#[repr(C)]
 #[derive(Copy,Clone,Debug,PartialEq)]
 pub struct Mat4 {
     pub m: [Vec4; 4],
@@ -68,21 +71,12 @@ impl SubAssign<Mat4> for Mat4 {
     }
 }
 
-/// TODO maybe refactor this function or the undergoing storage of Mat4 to allow faster multiplication
+/// SIMD accelerated where available, see Mat4::mul_scalar for the reference
 impl Mul<Mat4> for Mat4 {
     type Output = Mat4;
     #[inline(always)]
     fn mul(self, other: Mat4) -> Mat4 {
-        let mut res = Mat4::empty();
-        for c in 0..4 {
-            for r in 0..4 {
-                res[c][r] = 0.0;
-                for k in 0..4 {
-                    res[c][r] += self.m[c][k] * other.m[k][r];
-                }
-            }
-        }
-        res
+        simd::mul(&self, &other)
     }
 }
 
@@ -95,17 +89,13 @@ impl Mul<f32> for Mat4 {
     }
 }
 
-/// Multiply a Vector by a Matrix
+/// Transform a Vector by the Matrix, consistently with the
+/// multiplication order : (a * b) * v applies a first, then b
 impl Mul<Vec4> for Mat4 {
     type Output = Vec4;
     #[inline(always)]
     fn mul(self, other : Vec4) -> Vec4 {
-        Vec4 {
-            x: Vec4::dot(self.m[0], other),
-            y: Vec4::dot(self.m[1], other),
-            z: Vec4::dot(self.m[2], other),
-            w: Vec4::dot(self.m[3], other),
-        }
+        simd::mul_vec4(&self, other)
     }
 }
 
@@ -168,14 +158,29 @@ impl DivAssign<Mat4> for Mat4 {
 
 impl DivAssign<f32> for Mat4 {
     fn div_assign(&mut self, other: f32) {
-        *self *= other;
+        *self = *self / other;
     }
 }
 
 #[inline(always)]
 fn hfov_to_vfof(aspect : f32, hfov_deg : f32) -> f32{
     let rhf = hfov_deg.to_radians();
-    (2.0 * ((rhf * 5.0).tan() / aspect).atan()).to_degrees()
+    (2.0 * ((rhf * 0.5).tan() / aspect).atan()).to_degrees()
+}
+
+/// Perspective matrix with the given depth row terms, z_clip = sz * z + pz
+fn persp_with_depth(fov_x : f32, aspect : f32, sz : f32, pz : f32) -> Mat4 {
+    let fov_rad = hfov_to_vfof(aspect,fov_x).to_radians();
+    let tan_half_fovy = (fov_rad * 0.5).tan();
+
+    let mut result = Mat4::empty();
+    result[0][0] = 1.0 / (aspect * tan_half_fovy);
+    result[1][1] = 1.0 / tan_half_fovy;
+    result[2][2] = sz;
+    result[3][2] = pz;
+    result[2][3] = -1.0;
+
+    result
 }
 
 impl Mat4 {
@@ -214,7 +219,32 @@ impl Mat4 {
         }
     }
 
+    /// General inverse of the matrix. Returns an empty matrix if m is singular
     pub fn inverse(m: Mat4) -> Mat4 {
+        simd::inverse(&m)
+    }
+
+    /// Reference implementation of a * b, used where SIMD is not available
+    pub fn mul_scalar(a: Mat4, b: Mat4) -> Mat4 {
+        let mut res = Mat4::empty();
+        for c in 0..4 {
+            for r in 0..4 {
+                res[c][r] = 0.0;
+                for k in 0..4 {
+                    res[c][r] += a.m[c][k] * b.m[k][r];
+                }
+            }
+        }
+        res
+    }
+
+    /// Reference implementation of m * v, used where SIMD is not available
+    pub fn mul_vec4_scalar(m: Mat4, v: Vec4) -> Vec4 {
+        m.m[0] * v.x + m.m[1] * v.y + m.m[2] * v.z + m.m[3] * v.w
+    }
+
+    /// Reference implementation of inverse, used where SIMD is not available
+    pub fn inverse_scalar(m: Mat4) -> Mat4 {
         let mut r = Mat4::empty();
         r[0][0] = m[1][1] * (m[2][2] * m[3][3] - m[2][3] * m[3][2]) -
                   m[2][1] * (m[1][2] * m[3][3] - m[1][3] * m[3][2]) -
@@ -267,9 +297,16 @@ impl Mat4 {
         r[3][3] = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) -
                   m[1][0] * (m[0][1] * m[2][2] - m[0][2] * m[2][1]) -
                   m[2][0] * (m[0][2] * m[1][1] - m[0][1] * m[1][2]);
-        r
+
+        // r is the adjugate, scale it by the determinant
+        let det = m[0][0] * r[0][0] + m[0][1] * r[1][0] + m[0][2] * r[2][0] + m[0][3] * r[3][0];
+        if det == 0.0 {
+            return Mat4::empty();
+        }
+        r / det
     }
 
+    /// Orthographic projection, mapping depth from [-n, -f] to [0, 1]
     pub fn ortho(l: f32, r: f32, b: f32, t: f32, n: f32, f: f32) -> Mat4 {
         let mut result = Mat4::empty();
         result[0][0] = 2.0 / (r - l);
@@ -295,27 +332,69 @@ impl Mat4 {
         result
     }
 
-    pub fn persp(fov_x : f32, aspect : f32, n : f32, f : f32) -> Mat4{
-        let fov_rad = hfov_to_vfof(aspect,fov_x).to_radians();
-        let tan_half_fovy = (fov_rad * 0.5).tan();
+    /// Orthographic projection, mapping depth from [-n, -f] to [-1, 1] as
+    /// expected by the default OpenGL clip control
+    pub fn ortho_gl(l: f32, r: f32, b: f32, t: f32, n: f32, f: f32) -> Mat4 {
+        let mut result = Mat4::ortho(l, r, b, t, n, f);
+        result[2][2] = -2.0 / (f - n);
+        result[3][2] = -(f + n) / (f - n);
+        result
+    }
 
-        let sx = 1.0 / (aspect * tan_half_fovy);
-        let sy = 1.0 / tan_half_fovy;
-        let sz = (- f + n) / (f - n);
+    /// Perspective projection from an horizontal fov in degrees, mapping
+    /// depth from [-n, -f] to [-1, 1]
+    pub fn persp(fov_x : f32, aspect : f32, n : f32, f : f32) -> Mat4{
+        let sz = -(f + n) / (f - n);
         let pz = -(2.0 * f * n) / (f - n);
+        persp_with_depth(fov_x, aspect, sz, pz)
+    }
+
+    /// Perspective projection with the far plane at infinity, mapping depth
+    /// from [-n, -inf] to [-1, 1]
+    pub fn persp_infinite(fov_x : f32, aspect : f32, n : f32) -> Mat4 {
+        persp_with_depth(fov_x, aspect, -1.0, -2.0 * n)
+    }
+
+    /// Reverse-Z perspective projection, mapping depth from [-n, -f] to
+    /// [1, 0]. Meant for a [0, 1] clip depth range and a GREATER depth test,
+    /// it spreads the float precision evenly over the distance
+    pub fn persp_reverse_z(fov_x : f32, aspect : f32, n : f32, f : f32) -> Mat4 {
+        persp_with_depth(fov_x, aspect, n / (f - n), f * n / (f - n))
+    }
+
+    /// Reverse-Z perspective projection with the far plane at infinity,
+    /// mapping depth from [-n, -inf] to [1, 0]
+    pub fn persp_reverse_z_infinite(fov_x : f32, aspect : f32, n : f32) -> Mat4 {
+        persp_with_depth(fov_x, aspect, 0.0, n)
+    }
 
+    /// Off-axis perspective projection of the [l, r] x [b, t] window on the
+    /// near plane, mapping depth from [-n, -f] to [-1, 1]. Used for
+    /// asymmetric views like split-screen or tiled rendering
+    pub fn frustrum(l: f32, r: f32, b: f32, t: f32, n: f32, f: f32) -> Mat4{
         let mut result = Mat4::empty();
-        result[0][0] = sx;
-        result[1][1] = sy;
-        result[2][2] = sz;
-        result[3][2] = pz;
-        result[2][3] = 0.0;
+        result[0][0] = 2.0 * n / (r - l);
+        result[1][1] = 2.0 * n / (t - b);
+        result[2][0] = (r + l) / (r - l);
+        result[2][1] = (t + b) / (t - b);
+        result[2][2] = -(f + n) / (f - n);
+        result[2][3] = -1.0;
+        result[3][2] = -(2.0 * f * n) / (f - n);
 
         result
     }
 
-    pub fn frustrum(l: f32, r: f32, b: f32, t: f32, n: f32, f: f32) -> Mat4{
-        Mat4::ortho(l,r,b,t,n,f)
+    /// Bring a point from NDC back to the space the projection was applied
+    /// to. inv is the inverse of the projection (or view-projection) matrix
+    pub fn unproject(inv: &Mat4, ndc: Vec3) -> Vec3 {
+        let p = *inv * Vec4::new(ndc.x, ndc.y, ndc.z, 1.0);
+        Vec3::new(p.x, p.y, p.z) / p.w
+    }
+
+    /// View space z of a NDC depth, for any of the projections above.
+    /// The result is negative in front of the camera
+    pub fn view_depth(proj: &Mat4, ndc_z: f32) -> f32 {
+        (proj[3][2] - ndc_z * proj[3][3]) / (ndc_z * proj[2][3] - proj[2][2])
     }
 
     pub fn look_at(cam_pos : Vec3, target : Vec3, up : Vec3) -> Mat4 {
@@ -327,8 +406,37 @@ impl Mat4 {
         R[0][0] = r[0];    R[1][0] = r[1];    R[2][0] = r[2];
 		R[0][1] = u[0];    R[1][1] = u[1];    R[2][1] = u[2];
 		R[0][2] = -f[0];   R[1][2] = -f[1];   R[2][2] = -f[2];
-        R *= Tr;
-        R
+        // translate first, then rotate
+        Tr * R
+    }
+
+    /// Inverse of an affine matrix (no projection part), cheaper than the
+    /// general inverse
+    pub fn affine_inverse(m: &Mat4) -> Mat4 {
+        let inv = Mat3::inverse(Mat3::from_mat4(m));
+        let t = -(inv * Vec3::new(m[3][0], m[3][1], m[3][2]));
+        Mat4::from_vec4(
+            Vec4::new(inv[0].x, inv[0].y, inv[0].z, 0.0),
+            Vec4::new(inv[1].x, inv[1].y, inv[1].z, 0.0),
+            Vec4::new(inv[2].x, inv[2].y, inv[2].z, 0.0),
+            Vec4::new(t.x, t.y, t.z, 1.0)
+        )
+    }
+
+    /// Transform a slice of vectors in place, same as m * v for each of them
+    pub fn transform_vec4s(m: &Mat4, v: &mut [Vec4]) {
+        simd::transform_vec4s(m, v);
+    }
+
+    /// Transform a slice of points in place (w = 1). No perspective divide
+    /// is done, use transform_vec4s for projections
+    pub fn transform_points(m: &Mat4, points: &mut [Vec3]) {
+        simd::transform_vec3s(m, points, 1.0);
+    }
+
+    /// Transform a slice of directions in place (w = 0), ignoring translation
+    pub fn transform_directions(m: &Mat4, dirs: &mut [Vec3]) {
+        simd::transform_vec3s(m, dirs, 0.0);
     }
 
     pub fn transpose(m : Mat4) -> Mat4 {
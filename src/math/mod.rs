@@ -1,6 +1,18 @@
 #![allow(dead_code)]
 
+pub mod vec2;
 pub mod vec3;
 pub mod vec4;
+pub mod mat2;
+pub mod mat3;
 pub mod mat4;
-pub mod transform;
\ No newline at end of file
+mod simd;
+pub mod quat;
+pub mod transform;
+pub mod geometry;
+pub mod polygon;
+pub mod interp;
+pub mod noise;
+pub mod color;
+pub mod approx;
+pub mod serialize;
\ No newline at end of file
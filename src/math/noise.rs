@@ -0,0 +1,381 @@
+use std::ops::Mul;
+
+use math::vec2::*;
+use math::vec3::*;
+use math::vec4::*;
+
+static GRAD3: [[f32; 3]; 12] = [
+    [1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0], [0.0, -1.0, 1.0], [0.0, 1.0, -1.0], [0.0, -1.0, -1.0]
+];
+
+static GRAD4: [[f32; 4]; 32] = [
+    [0.0, 1.0, 1.0, 1.0], [0.0, 1.0, 1.0, -1.0], [0.0, 1.0, -1.0, 1.0], [0.0, 1.0, -1.0, -1.0],
+    [0.0, -1.0, 1.0, 1.0], [0.0, -1.0, 1.0, -1.0], [0.0, -1.0, -1.0, 1.0], [0.0, -1.0, -1.0, -1.0],
+    [1.0, 0.0, 1.0, 1.0], [1.0, 0.0, 1.0, -1.0], [1.0, 0.0, -1.0, 1.0], [1.0, 0.0, -1.0, -1.0],
+    [-1.0, 0.0, 1.0, 1.0], [-1.0, 0.0, 1.0, -1.0], [-1.0, 0.0, -1.0, 1.0], [-1.0, 0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0, 1.0], [1.0, 1.0, 0.0, -1.0], [1.0, -1.0, 0.0, 1.0], [1.0, -1.0, 0.0, -1.0],
+    [-1.0, 1.0, 0.0, 1.0], [-1.0, 1.0, 0.0, -1.0], [-1.0, -1.0, 0.0, 1.0], [-1.0, -1.0, 0.0, -1.0],
+    [1.0, 1.0, 1.0, 0.0], [1.0, 1.0, -1.0, 0.0], [1.0, -1.0, 1.0, 0.0], [1.0, -1.0, -1.0, 0.0],
+    [-1.0, 1.0, 1.0, 0.0], [-1.0, 1.0, -1.0, 0.0], [-1.0, -1.0, 1.0, 0.0], [-1.0, -1.0, -1.0, 0.0]
+];
+
+#[inline(always)]
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+#[inline(always)]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[inline(always)]
+fn floor_i(x: f32) -> i32 {
+    x.floor() as i32
+}
+
+#[inline(always)]
+fn dot2(g: &[f32; 3], x: f32, y: f32) -> f32 {
+    g[0] * x + g[1] * y
+}
+
+#[inline(always)]
+fn dot3(g: &[f32; 3], x: f32, y: f32, z: f32) -> f32 {
+    g[0] * x + g[1] * y + g[2] * z
+}
+
+#[inline(always)]
+fn dot4(g: &[f32; 4], x: f32, y: f32, z: f32, w: f32) -> f32 {
+    g[0] * x + g[1] * y + g[2] * z + g[3] * w
+}
+
+/// Deterministic, seedable gradient and cellular noise generator. The same
+/// seed always gives the same values, on every platform.
+pub struct Noise {
+    seed: u32,
+    perm: [u8; 512],
+}
+
+impl Noise {
+    pub fn new(seed: u32) -> Noise {
+        let mut p = [0u8; 256];
+        for i in 0..256 {
+            p[i] = i as u8;
+        }
+
+        // Fisher-Yates shuffle driven by a xorshift generator, to not
+        // depend on a rand crate implementation
+        let mut state = seed ^ 0x9E37_79B9;
+        if state == 0 {
+            state = 1;
+        }
+        for i in (1..256).rev() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let j = (state % (i as u32 + 1)) as usize;
+            p.swap(i, j);
+        }
+
+        let mut perm = [0u8; 512];
+        for i in 0..512 {
+            perm[i] = p[i & 255];
+        }
+        Noise { seed: seed, perm: perm }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    #[inline(always)]
+    fn hash(&self, i: i32) -> usize {
+        self.perm[(i & 255) as usize] as usize
+    }
+
+    #[inline(always)]
+    fn hash2(&self, i: i32, j: i32) -> usize {
+        self.perm[self.hash(i) + (j & 255) as usize] as usize
+    }
+
+    #[inline(always)]
+    fn hash3(&self, i: i32, j: i32, k: i32) -> usize {
+        self.perm[self.hash2(i, j) + (k & 255) as usize] as usize
+    }
+
+    #[inline(always)]
+    fn hash4(&self, i: i32, j: i32, k: i32, l: i32) -> usize {
+        self.perm[self.hash3(i, j, k) + (l & 255) as usize] as usize
+    }
+
+    /// 2D Perlin noise, roughly in [-1, 1]. Zero on integer coordinates
+    pub fn perlin2(&self, p: Vec2) -> f32 {
+        let (i, j) = (floor_i(p.x), floor_i(p.y));
+        let (x, y) = (p.x - i as f32, p.y - j as f32);
+        let (u, v) = (fade(x), fade(y));
+
+        let g = |a: i32, b: i32, dx: f32, dy: f32| dot2(&GRAD3[self.hash2(i + a, j + b) % 8], dx, dy);
+        let n00 = g(0, 0, x, y);
+        let n10 = g(1, 0, x - 1.0, y);
+        let n01 = g(0, 1, x, y - 1.0);
+        let n11 = g(1, 1, x - 1.0, y - 1.0);
+
+        lerp(lerp(n00, n10, u), lerp(n01, n11, u), v)
+    }
+
+    /// 3D Perlin noise, roughly in [-1, 1]. Zero on integer coordinates
+    pub fn perlin3(&self, p: Vec3) -> f32 {
+        let (i, j, k) = (floor_i(p.x), floor_i(p.y), floor_i(p.z));
+        let (x, y, z) = (p.x - i as f32, p.y - j as f32, p.z - k as f32);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let g = |a: i32, b: i32, c: i32| {
+            dot3(&GRAD3[self.hash3(i + a, j + b, k + c) % 12], x - a as f32, y - b as f32, z - c as f32)
+        };
+
+        let x00 = lerp(g(0, 0, 0), g(1, 0, 0), u);
+        let x10 = lerp(g(0, 1, 0), g(1, 1, 0), u);
+        let x01 = lerp(g(0, 0, 1), g(1, 0, 1), u);
+        let x11 = lerp(g(0, 1, 1), g(1, 1, 1), u);
+        lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+    }
+
+    /// 4D Perlin noise, roughly in [-1, 1]. Zero on integer coordinates
+    pub fn perlin4(&self, p: Vec4) -> f32 {
+        let cell = [floor_i(p.x), floor_i(p.y), floor_i(p.z), floor_i(p.w)];
+        let f = [p.x - cell[0] as f32, p.y - cell[1] as f32, p.z - cell[2] as f32, p.w - cell[3] as f32];
+        let t = [fade(f[0]), fade(f[1]), fade(f[2]), fade(f[3])];
+
+        // sum of the 16 corner contributions, weighted like nested lerps
+        let mut sum = 0.0;
+        for corner in 0..16 {
+            let o = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1, (corner >> 3) & 1];
+            let mut weight = 1.0;
+            for a in 0..4 {
+                weight *= if o[a] == 1 { t[a] } else { 1.0 - t[a] };
+            }
+            let h = self.hash4(cell[0] + o[0], cell[1] + o[1], cell[2] + o[2], cell[3] + o[3]);
+            sum += weight * dot4(&GRAD4[h % 32],
+                                 f[0] - o[0] as f32, f[1] - o[1] as f32,
+                                 f[2] - o[2] as f32, f[3] - o[3] as f32);
+        }
+        sum
+    }
+
+    /// 2D Simplex noise in [-1, 1]
+    pub fn simplex2(&self, p: Vec2) -> f32 {
+        let f2 = 0.5 * (3.0f32.sqrt() - 1.0);
+        let g2 = (3.0 - 3.0f32.sqrt()) / 6.0;
+
+        let s = (p.x + p.y) * f2;
+        let (i, j) = (floor_i(p.x + s), floor_i(p.y + s));
+        let t = (i + j) as f32 * g2;
+        let x0 = p.x - (i as f32 - t);
+        let y0 = p.y - (j as f32 - t);
+
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let x1 = x0 - i1 as f32 + g2;
+        let y1 = y0 - j1 as f32 + g2;
+        let x2 = x0 - 1.0 + 2.0 * g2;
+        let y2 = y0 - 1.0 + 2.0 * g2;
+
+        let corner = |di: i32, dj: i32, x: f32, y: f32| {
+            let t = 0.5 - x * x - y * y;
+            if t < 0.0 {
+                0.0
+            } else {
+                let t2 = t * t;
+                t2 * t2 * dot2(&GRAD3[self.hash2(i + di, j + dj) % 12], x, y)
+            }
+        };
+
+        70.0 * (corner(0, 0, x0, y0) + corner(i1, j1, x1, y1) + corner(1, 1, x2, y2))
+    }
+
+    /// 3D Simplex noise in [-1, 1]
+    pub fn simplex3(&self, p: Vec3) -> f32 {
+        let f3 = 1.0 / 3.0;
+        let g3 = 1.0 / 6.0;
+
+        let s = (p.x + p.y + p.z) * f3;
+        let (i, j, k) = (floor_i(p.x + s), floor_i(p.y + s), floor_i(p.z + s));
+        let t = (i + j + k) as f32 * g3;
+        let x0 = p.x - (i as f32 - t);
+        let y0 = p.y - (j as f32 - t);
+        let z0 = p.z - (k as f32 - t);
+
+        // offsets of the second and third simplex corners
+        let (o1, o2) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else {
+            if y0 < z0 {
+                ((0, 0, 1), (0, 1, 1))
+            } else if x0 < z0 {
+                ((0, 1, 0), (0, 1, 1))
+            } else {
+                ((0, 1, 0), (1, 1, 0))
+            }
+        };
+
+        let corner = |o: (i32, i32, i32), n: f32| {
+            let x = x0 - o.0 as f32 + n * g3;
+            let y = y0 - o.1 as f32 + n * g3;
+            let z = z0 - o.2 as f32 + n * g3;
+            let t = 0.6 - x * x - y * y - z * z;
+            if t < 0.0 {
+                0.0
+            } else {
+                let t2 = t * t;
+                t2 * t2 * dot3(&GRAD3[self.hash3(i + o.0, j + o.1, k + o.2) % 12], x, y, z)
+            }
+        };
+
+        32.0 * (corner((0, 0, 0), 0.0) + corner(o1, 1.0) + corner(o2, 2.0) + corner((1, 1, 1), 3.0))
+    }
+
+    /// 4D Simplex noise in [-1, 1]
+    pub fn simplex4(&self, p: Vec4) -> f32 {
+        let f4 = (5.0f32.sqrt() - 1.0) / 4.0;
+        let g4 = (5.0 - 5.0f32.sqrt()) / 20.0;
+
+        let s = (p.x + p.y + p.z + p.w) * f4;
+        let cell = [floor_i(p.x + s), floor_i(p.y + s), floor_i(p.z + s), floor_i(p.w + s)];
+        let t = (cell[0] + cell[1] + cell[2] + cell[3]) as f32 * g4;
+        let d0 = [p.x - (cell[0] as f32 - t), p.y - (cell[1] as f32 - t),
+                  p.z - (cell[2] as f32 - t), p.w - (cell[3] as f32 - t)];
+
+        // rank the coordinates to find which simplex we are in
+        let mut rank = [0; 4];
+        for a in 0..4 {
+            for b in (a + 1)..4 {
+                if d0[a] > d0[b] { rank[a] += 1; } else { rank[b] += 1; }
+            }
+        }
+
+        let mut sum = 0.0;
+        for n in 0..5 {
+            // corner n is offset by 1 on the axes of rank >= 4 - n
+            let mut o = [0i32; 4];
+            let mut d = [0f32; 4];
+            for a in 0..4 {
+                o[a] = if rank[a] >= 4 - n as i32 { 1 } else { 0 };
+                d[a] = d0[a] - o[a] as f32 + n as f32 * g4;
+            }
+            let t = 0.6 - d[0] * d[0] - d[1] * d[1] - d[2] * d[2] - d[3] * d[3];
+            if t > 0.0 {
+                let h = self.hash4(cell[0] + o[0], cell[1] + o[1], cell[2] + o[2], cell[3] + o[3]);
+                let t2 = t * t;
+                sum += t2 * t2 * dot4(&GRAD4[h % 32], d[0], d[1], d[2], d[3]);
+            }
+        }
+        27.0 * sum
+    }
+
+    /// 2D Worley (cellular) noise. Returns the distances to the closest
+    /// (F1, in x) and second closest (F2, in y) feature points
+    pub fn worley2(&self, p: Vec2) -> Vec2 {
+        let (ci, cj) = (floor_i(p.x), floor_i(p.y));
+        let mut f1 = ::std::f32::MAX;
+        let mut f2 = ::std::f32::MAX;
+
+        for dj in -1..2 {
+            for di in -1..2 {
+                let (i, j) = (ci + di, cj + dj);
+                let h = self.hash2(i, j);
+                let feature = Vec2::new(i as f32 + self.perm[h] as f32 / 255.0,
+                                        j as f32 + self.perm[h + 1] as f32 / 255.0);
+                let d = Vec2::length(feature - p);
+                if d < f1 {
+                    f2 = f1;
+                    f1 = d;
+                } else if d < f2 {
+                    f2 = d;
+                }
+            }
+        }
+        Vec2::new(f1, f2)
+    }
+
+    /// 3D Worley (cellular) noise. Returns the distances to the closest
+    /// (F1, in x) and second closest (F2, in y) feature points
+    pub fn worley3(&self, p: Vec3) -> Vec2 {
+        let (ci, cj, ck) = (floor_i(p.x), floor_i(p.y), floor_i(p.z));
+        let mut f1 = ::std::f32::MAX;
+        let mut f2 = ::std::f32::MAX;
+
+        for dk in -1..2 {
+            for dj in -1..2 {
+                for di in -1..2 {
+                    let (i, j, k) = (ci + di, cj + dj, ck + dk);
+                    let h = self.hash3(i, j, k);
+                    let feature = Vec3::new(i as f32 + self.perm[h] as f32 / 255.0,
+                                            j as f32 + self.perm[h + 1] as f32 / 255.0,
+                                            k as f32 + self.perm[h + 2] as f32 / 255.0);
+                    let d = Vec3::length(feature - p);
+                    if d < f1 {
+                        f2 = f1;
+                        f1 = d;
+                    } else if d < f2 {
+                        f2 = d;
+                    }
+                }
+            }
+        }
+        Vec2::new(f1, f2)
+    }
+}
+
+/// Fractal Brownian motion : sum of octaves of the noise function f, each
+/// at lacunarity times the frequency and gain times the amplitude of the
+/// previous one. Normalized to the range of f.
+pub fn fbm<T, F>(p: T, octaves: u32, lacunarity: f32, gain: f32, f: F) -> f32
+    where T: Copy + Mul<f32, Output = T>,
+          F: Fn(T) -> f32
+{
+    let mut sum = 0.0;
+    let mut norm = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    for _ in 0..octaves {
+        sum += amplitude * f(p * frequency);
+        norm += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+    if norm > 0.0 { sum / norm } else { 0.0 }
+}
+
+/// Ridged multifractal (Musgrave) : sharp crests where f crosses zero,
+/// good for mountain ranges. f should be in [-1, 1], offset is usually 1.
+/// Returns values roughly in [0, 1].
+pub fn ridged<T, F>(p: T, octaves: u32, lacunarity: f32, gain: f32, offset: f32, f: F) -> f32
+    where T: Copy + Mul<f32, Output = T>,
+          F: Fn(T) -> f32
+{
+    let mut sum = 0.0;
+    let mut norm = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut weight = 1.0;
+    for _ in 0..octaves {
+        let mut signal = offset - f(p * frequency).abs();
+        signal *= signal;
+        // crests of previous octaves sharpen the detail of the next ones
+        signal *= weight;
+        weight = (signal * 2.0).max(0.0).min(1.0);
+
+        sum += amplitude * signal;
+        norm += amplitude * offset * offset;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+    if norm > 0.0 { sum / norm } else { 0.0 }
+}
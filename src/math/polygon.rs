@@ -0,0 +1,330 @@
+use std::cmp::Ordering;
+
+use math::vec2::*;
+use math::vec3::*;
+
+/// Orientation of a polygon, for a y-up frame. With y going down, as in
+/// screen space, the two are swapped.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Winding {
+    CounterClockwise,
+    Clockwise,
+}
+
+/// Twice the signed area of abc, positive when c is on the left of ab
+#[inline(always)]
+fn orient(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    Vec2::cross(b - a, c - a)
+}
+
+fn cmp_f32(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// Signed area, positive for counter-clockwise polygons
+pub fn signed_area(points: &[Vec2]) -> f32 {
+    let n = points.len();
+    let mut a = 0.0;
+    for i in 0..n {
+        let p = points[i];
+        let q = points[(i + 1) % n];
+        a += p.x * q.y - q.x * p.y;
+    }
+    a * 0.5
+}
+
+pub fn area(points: &[Vec2]) -> f32 {
+    signed_area(points).abs()
+}
+
+pub fn winding(points: &[Vec2]) -> Winding {
+    if signed_area(points) >= 0.0 {
+        Winding::CounterClockwise
+    } else {
+        Winding::Clockwise
+    }
+}
+
+/// Even-odd test, so self-intersecting polygons are supported. Points
+/// exactly on an edge may be reported either way
+pub fn contains_point(polygon: &[Vec2], p: Vec2) -> bool {
+    let n = polygon.len();
+    if n < 3 {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = n - 1;
+    for i in 0..n {
+        let a = polygon[i];
+        let b = polygon[j];
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Convex hull of a point set (Andrew's monotone chain). Returns the
+/// indices of the hull points in counter-clockwise order, without
+/// collinear points
+pub fn convex_hull(points: &[Vec2]) -> Vec<u32> {
+    let mut idx: Vec<usize> = (0..points.len()).collect();
+    idx.sort_by(|&a, &b| {
+        cmp_f32(points[a].x, points[b].x).then(cmp_f32(points[a].y, points[b].y))
+    });
+    if idx.len() < 3 {
+        return idx.into_iter().map(|i| i as u32).collect();
+    }
+
+    let mut hull: Vec<usize> = Vec::with_capacity(idx.len() * 2);
+    // lower hull, then upper hull
+    for &i in idx.iter() {
+        while hull.len() >= 2 &&
+              orient(points[hull[hull.len() - 2]], points[hull[hull.len() - 1]], points[i]) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(i);
+    }
+    let lower_len = hull.len() + 1;
+    for &i in idx.iter().rev().skip(1) {
+        while hull.len() >= lower_len &&
+              orient(points[hull[hull.len() - 2]], points[hull[hull.len() - 1]], points[i]) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(i);
+    }
+    // the last point is the first one again
+    hull.pop();
+    hull.into_iter().map(|i| i as u32).collect()
+}
+
+/// Triangulate a simple polygon by ear clipping. The indices refer to
+/// points and the triangles keep the winding of the polygon
+pub fn triangulate(polygon: &[Vec2]) -> Vec<u32> {
+    triangulate_with_holes(polygon, &[]).1
+}
+
+/// Triangulate a simple polygon with holes by ear clipping. Each hole is
+/// joined to the outer boundary through a bridge edge before clipping.
+/// Returns the vertices (outer boundary followed by every hole, in order)
+/// and the triangle indices into them, with the winding of the outer
+/// boundary. Holes may be given in either winding.
+pub fn triangulate_with_holes(outer: &[Vec2], holes: &[&[Vec2]]) -> (Vec<Vec2>, Vec<u32>) {
+    let mut vertices: Vec<Vec2> = outer.to_vec();
+    let mut indices = Vec::new();
+    if outer.len() < 3 {
+        return (vertices, indices);
+    }
+
+    // work on a counter-clockwise boundary and clockwise holes
+    let ccw = signed_area(outer) >= 0.0;
+    let mut ring: Vec<usize> = (0..outer.len()).collect();
+    if !ccw {
+        ring.reverse();
+    }
+
+    let mut hole_rings: Vec<Vec<usize>> = Vec::new();
+    for hole in holes.iter() {
+        let start = vertices.len();
+        vertices.extend_from_slice(hole);
+        if hole.len() < 3 {
+            continue;
+        }
+        let mut r: Vec<usize> = (start..vertices.len()).collect();
+        if signed_area(hole) > 0.0 {
+            r.reverse();
+        }
+        hole_rings.push(r);
+    }
+
+    // bridging from the rightmost hole first keeps the bridges from crossing
+    {
+        let max_x = |r: &Vec<usize>| r.iter().fold(::std::f32::MIN, |m, &i| m.max(vertices[i].x));
+        hole_rings.sort_by(|a, b| cmp_f32(max_x(b), max_x(a)));
+    }
+    for k in 0..hole_rings.len() {
+        ring = bridge_hole(&vertices, ring, &hole_rings[k], &hole_rings[k + 1..]);
+    }
+
+    ear_clip(&vertices, ring, ccw, &mut indices);
+    (vertices, indices)
+}
+
+/// Polygon normal with Newell's method, robust to concave and slightly
+/// non planar polygons. Not normalized
+pub fn newell_normal(points: &[Vec3]) -> Vec3 {
+    let n = points.len();
+    let mut normal = Vec3::empty();
+    for i in 0..n {
+        let p = points[i];
+        let q = points[(i + 1) % n];
+        normal.x += (p.y - q.y) * (p.z + q.z);
+        normal.y += (p.z - q.z) * (p.x + q.x);
+        normal.z += (p.x - q.x) * (p.y + q.y);
+    }
+    normal
+}
+
+/// Drop the coordinate the normal is the most aligned with. Polygons
+/// counter-clockwise when seen from the normal stay counter-clockwise
+pub fn project_to_2d(points: &[Vec3], normal: Vec3) -> Vec<Vec2> {
+    let (ax, ay, az) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+    points.iter().map(|p| {
+        if az >= ax && az >= ay {
+            if normal.z >= 0.0 { Vec2::new(p.x, p.y) } else { Vec2::new(p.y, p.x) }
+        } else if ax >= ay {
+            if normal.x >= 0.0 { Vec2::new(p.y, p.z) } else { Vec2::new(p.z, p.y) }
+        } else {
+            if normal.y >= 0.0 { Vec2::new(p.z, p.x) } else { Vec2::new(p.x, p.z) }
+        }
+    }).collect()
+}
+
+/// Triangulate a planar polygon in 3D. The triangles face the same side
+/// as the polygon
+pub fn triangulate_3d(polygon: &[Vec3]) -> Vec<u32> {
+    triangulate(&project_to_2d(polygon, newell_normal(polygon)))
+}
+
+/// Flatten 2D points to the xyz position layout expected by Mesh::new
+pub fn to_positions(points: &[Vec2], z: f32) -> Vec<f32> {
+    let mut out = Vec::with_capacity(points.len() * 3);
+    for p in points.iter() {
+        out.push(p.x);
+        out.push(p.y);
+        out.push(z);
+    }
+    out
+}
+
+fn point_in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    orient(a, b, p) >= 0.0 && orient(b, c, p) >= 0.0 && orient(c, a, p) >= 0.0
+}
+
+fn is_ear(pts: &[Vec2], ring: &[usize], a: Vec2, b: Vec2, c: Vec2) -> bool {
+    if orient(a, b, c) <= 0.0 {
+        return false;
+    }
+    // bridge vertices are duplicated, only test the other positions
+    !ring.iter().any(|&k| {
+        let p = pts[k];
+        p != a && p != b && p != c && point_in_triangle(p, a, b, c)
+    })
+}
+
+/// Clip the ears of a counter-clockwise ring. Triangles are flipped back
+/// when the source polygon was clockwise
+fn ear_clip(pts: &[Vec2], mut ring: Vec<usize>, ccw: bool, indices: &mut Vec<u32>) {
+    let mut emit = |a: usize, b: usize, c: usize| {
+        if ccw {
+            indices.extend_from_slice(&[a as u32, b as u32, c as u32]);
+        } else {
+            indices.extend_from_slice(&[a as u32, c as u32, b as u32]);
+        }
+    };
+
+    let mut start = 0;
+    while ring.len() > 3 {
+        let n = ring.len();
+        let corner = |i: usize| (ring[(i + n - 1) % n], ring[i % n], ring[(i + 1) % n]);
+
+        let ear = (0..n).map(|k| (start + k) % n).find(|&i| {
+            let (a, b, c) = corner(i);
+            is_ear(pts, &ring, pts[a], pts[b], pts[c])
+        });
+
+        let i = match ear {
+            Some(i) => i,
+            None => {
+                // degenerate or self-intersecting input : drop a flat vertex
+                // if there is one, otherwise clip anyway to terminate
+                let flat = (0..n).find(|&i| {
+                    let (a, b, c) = corner(i);
+                    orient(pts[a], pts[b], pts[c]) == 0.0
+                });
+                if let Some(i) = flat {
+                    ring.remove(i);
+                    continue;
+                }
+                start % n
+            }
+        };
+
+        let (a, b, c) = corner(i);
+        emit(a, b, c);
+        ring.remove(i);
+        // continue around the ring instead of fanning from the same place
+        start = i;
+    }
+
+    if ring.len() == 3 && orient(pts[ring[0]], pts[ring[1]], pts[ring[2]]) != 0.0 {
+        emit(ring[0], ring[1], ring[2]);
+    }
+}
+
+/// Whether q lies strictly inside the interior angle of a
+/// counter-clockwise ring at p, between the edges a-p and p-b
+fn in_cone(a: Vec2, p: Vec2, b: Vec2, q: Vec2) -> bool {
+    if orient(a, p, b) >= 0.0 {
+        orient(a, p, q) > 0.0 && orient(p, b, q) > 0.0
+    } else {
+        orient(a, p, q) > 0.0 || orient(p, b, q) > 0.0
+    }
+}
+
+fn on_segment(p: Vec2, q: Vec2, r: Vec2) -> bool {
+    r.x >= p.x.min(q.x) && r.x <= p.x.max(q.x) && r.y >= p.y.min(q.y) && r.y <= p.y.max(q.y)
+}
+
+/// Whether segment pq crosses or touches an edge of the ring. Edges sharing
+/// an endpoint position with pq are ignored
+fn crosses_ring(pts: &[Vec2], p: Vec2, q: Vec2, ring: &[usize]) -> bool {
+    let n = ring.len();
+    (0..n).any(|i| {
+        let u = pts[ring[i]];
+        let v = pts[ring[(i + 1) % n]];
+        if u == p || u == q || v == p || v == q {
+            return false;
+        }
+        let (o1, o2) = (orient(p, q, u), orient(p, q, v));
+        let (o3, o4) = (orient(u, v, p), orient(u, v, q));
+        (o1 * o2 < 0.0 && o3 * o4 < 0.0) ||
+        (o1 == 0.0 && on_segment(p, q, u)) ||
+        (o2 == 0.0 && on_segment(p, q, v)) ||
+        (o3 == 0.0 && on_segment(u, v, p)) ||
+        (o4 == 0.0 && on_segment(u, v, q))
+    })
+}
+
+/// Join a clockwise hole to the counter-clockwise ring with a pair of
+/// edges between the rightmost hole vertex and the closest visible ring
+/// vertex
+fn bridge_hole(pts: &[Vec2], ring: Vec<usize>, hole: &[usize], others: &[Vec<usize>]) -> Vec<usize> {
+    let hi = (0..hole.len()).max_by(|&a, &b| cmp_f32(pts[hole[a]].x, pts[hole[b]].x)).unwrap();
+    let m = pts[hole[hi]];
+
+    let n = ring.len();
+    let mut candidates: Vec<usize> = (0..n).collect();
+    candidates.sort_by(|&a, &b| {
+        cmp_f32(Vec2::length(pts[ring[a]] - m), Vec2::length(pts[ring[b]] - m))
+    });
+
+    let visible = candidates.iter().cloned().find(|&ci| {
+        let p = pts[ring[ci]];
+        in_cone(pts[ring[(ci + n - 1) % n]], p, pts[ring[(ci + 1) % n]], m) &&
+        !crosses_ring(pts, p, m, &ring) &&
+        !crosses_ring(pts, p, m, hole) &&
+        !others.iter().any(|o| crosses_ring(pts, p, m, o))
+    });
+    let ci = visible.unwrap_or(candidates[0]);
+
+    let mut out = Vec::with_capacity(n + hole.len() + 2);
+    out.extend_from_slice(&ring[..ci + 1]);
+    for j in 0..hole.len() + 1 {
+        out.push(hole[(hi + j) % hole.len()]);
+    }
+    out.push(ring[ci]);
+    out.extend_from_slice(&ring[ci + 1..]);
+    out
+}
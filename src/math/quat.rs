@@ -0,0 +1,309 @@
+use std::ops::{Add, Mul, Sub, MulAssign, Neg, Index, IndexMut};
+use std::fmt;
+
+use math::vec3::*;
+use math::vec4::*;
+use math::mat4::*;
+
+/// Unit quaternion representing a rotation. (x, y, z) is the vector part
+/// and w the scalar part.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Index<usize> for Quat {
+    type Output = f32;
+    fn index<'a>(&'a self, _index: usize) -> &'a f32 {
+        match _index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            3 => &self.w,
+            _ => panic!("Invalid index access in Quat {:?}"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Quat {
+    fn index_mut<'a>(&'a mut self, _index: usize) -> &'a mut f32 {
+        match _index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            3 => &mut self.w,
+            _ => panic!("Invalid index access in Quat {:?}"),
+        }
+    }
+}
+
+/// Display function
+impl fmt::Display for Quat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Quat({},{},{},{})", self.x, self.y, self.z, self.w)
+    }
+}
+
+/// Negate all components. -q represents the same rotation as q
+impl Neg for Quat {
+    type Output = Quat;
+    fn neg(self) -> Quat {
+        Quat {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: -self.w,
+        }
+    }
+}
+
+/// Component-wise addition, used for interpolation
+impl Add for Quat {
+    type Output = Quat;
+    #[inline(always)]
+    fn add(self, other: Quat) -> Quat {
+        Quat {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+            w: self.w + other.w,
+        }
+    }
+}
+
+/// Component-wise substraction, used for interpolation
+impl Sub for Quat {
+    type Output = Quat;
+    #[inline(always)]
+    fn sub(self, other: Quat) -> Quat {
+        Quat {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+            w: self.w - other.w,
+        }
+    }
+}
+
+/// Scalar multiplication of all components
+impl Mul<f32> for Quat {
+    type Output = Quat;
+    #[inline(always)]
+    fn mul(self, other: f32) -> Quat {
+        Quat {
+            x: self.x * other,
+            y: self.y * other,
+            z: self.z * other,
+            w: self.w * other,
+        }
+    }
+}
+
+/// Rotation composition. Like Mat4 multiplication, a * b applies the
+/// rotation a first, then the rotation b.
+impl Mul<Quat> for Quat {
+    type Output = Quat;
+    #[inline(always)]
+    fn mul(self, other: Quat) -> Quat {
+        // hamilton product other * self
+        let a = other;
+        let b = self;
+        Quat {
+            x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        }
+    }
+}
+
+impl MulAssign<Quat> for Quat {
+    #[inline(always)]
+    fn mul_assign(&mut self, other: Quat) {
+        *self = *self * other;
+    }
+}
+
+/// Rotate a vector by the quaternion
+impl Mul<Vec3> for Quat {
+    type Output = Vec3;
+    #[inline(always)]
+    fn mul(self, other: Vec3) -> Vec3 {
+        // v' = v + 2w(q x v) + 2(q x (q x v))
+        let q = Vec3::new(self.x, self.y, self.z);
+        let t = Vec3::cross(q, other) * 2.0;
+        other + t * self.w + Vec3::cross(q, t)
+    }
+}
+
+impl Quat {
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Quat {
+        Quat { x: x, y: y, z: z, w: w }
+    }
+
+    /// return the identity rotation
+    pub fn identity() -> Quat {
+        Quat { x: 0.0, y: 0.0, z: 0.0, w: 1.0 }
+    }
+
+    /// Rotation of angle_deg degrees around axis (doesn't need to be unit)
+    pub fn from_axis_angle(axis: Vec3, angle_deg: f32) -> Quat {
+        let axis = Vec3::normalize(axis);
+        let half = angle_deg.to_radians() * 0.5;
+        let s = half.sin();
+        Quat {
+            x: axis.x * s,
+            y: axis.y * s,
+            z: axis.z * s,
+            w: half.cos(),
+        }
+    }
+
+    /// Rotation from euler angles in degrees. pitch is around the x axis,
+    /// yaw around the y axis and roll around the z axis. The roll is
+    /// applied first, then the pitch, then the yaw.
+    pub fn from_euler(pitch_deg: f32, yaw_deg: f32, roll_deg: f32) -> Quat {
+        let (sp, cp) = (pitch_deg.to_radians() * 0.5).sin_cos();
+        let (sy, cy) = (yaw_deg.to_radians() * 0.5).sin_cos();
+        let (sr, cr) = (roll_deg.to_radians() * 0.5).sin_cos();
+        Quat {
+            x: cy * sp * cr + sy * cp * sr,
+            y: sy * cp * cr - cy * sp * sr,
+            z: cy * cp * sr - sy * sp * cr,
+            w: cy * cp * cr + sy * sp * sr,
+        }
+    }
+
+    /// Return the (pitch, yaw, roll) euler angles in degrees, following the
+    /// same convention as from_euler
+    pub fn to_euler(q: Quat) -> Vec3 {
+        let sp = 2.0 * (q.w * q.x - q.y * q.z);
+        let pitch = if sp >= 1.0 {
+            ::std::f32::consts::FRAC_PI_2
+        } else if sp <= -1.0 {
+            -::std::f32::consts::FRAC_PI_2
+        } else {
+            sp.asin()
+        };
+        let yaw = (2.0 * (q.x * q.z + q.w * q.y)).atan2(1.0 - 2.0 * (q.x * q.x + q.y * q.y));
+        let roll = (2.0 * (q.x * q.y + q.w * q.z)).atan2(1.0 - 2.0 * (q.x * q.x + q.z * q.z));
+        Vec3::new(pitch.to_degrees(), yaw.to_degrees(), roll.to_degrees())
+    }
+
+    pub fn dot(a: Quat, b: Quat) -> f32 {
+        a.x * b.x + a.y * b.y + a.z * b.z + a.w * b.w
+    }
+
+    pub fn length(a: Quat) -> f32 {
+        Quat::dot(a, a).sqrt()
+    }
+
+    /// return a unit quaternion, or identity for a null quaternion
+    pub fn normalize(a: Quat) -> Quat {
+        let len = Quat::length(a);
+        if len > 0.0 {
+            a * (1.0 / len)
+        } else {
+            Quat::identity()
+        }
+    }
+
+    pub fn conjugate(a: Quat) -> Quat {
+        Quat { x: -a.x, y: -a.y, z: -a.z, w: a.w }
+    }
+
+    /// Inverse rotation. Equals the conjugate for unit quaternions
+    pub fn inverse(a: Quat) -> Quat {
+        let len2 = Quat::dot(a, a);
+        if len2 > 0.0 {
+            Quat::conjugate(a) * (1.0 / len2)
+        } else {
+            Quat::identity()
+        }
+    }
+
+    /// Normalized linear interpolation, following the shortest path.
+    /// Cheaper than slerp but without constant angular velocity
+    pub fn nlerp(a: Quat, b: Quat, t: f32) -> Quat {
+        let b = if Quat::dot(a, b) < 0.0 { -b } else { b };
+        Quat::normalize(a + (b - a) * t)
+    }
+
+    /// Spherical linear interpolation, following the shortest path
+    pub fn slerp(a: Quat, b: Quat, t: f32) -> Quat {
+        let mut cos_theta = Quat::dot(a, b);
+        let mut b = b;
+        if cos_theta < 0.0 {
+            b = -b;
+            cos_theta = -cos_theta;
+        }
+
+        // too close for a stable division by sin(theta), lerp instead
+        if cos_theta > 0.9995 {
+            return Quat::normalize(a + (b - a) * t);
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        let wa = ((1.0 - t) * theta).sin() / sin_theta;
+        let wb = (t * theta).sin() / sin_theta;
+        a * wa + b * wb
+    }
+
+    /// Return the rotation Matrix (Mat4) equivalent to the quaternion
+    pub fn to_mat4(q: Quat) -> Mat4 {
+        let (x, y, z, w) = (q.x, q.y, q.z, q.w);
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        let (xy, xz, yz) = (x * y, x * z, y * z);
+        let (wx, wy, wz) = (w * x, w * y, w * z);
+        Mat4::from_vec4(
+            Vec4::new(1.0 - 2.0 * (yy + zz), 2.0 * (xy + wz), 2.0 * (xz - wy), 0.0),
+            Vec4::new(2.0 * (xy - wz), 1.0 - 2.0 * (xx + zz), 2.0 * (yz + wx), 0.0),
+            Vec4::new(2.0 * (xz + wy), 2.0 * (yz - wx), 1.0 - 2.0 * (xx + yy), 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Extract the rotation from the upper 3x3 part of a Mat4. The matrix
+    /// must not contain any scaling.
+    pub fn from_mat4(m: Mat4) -> Quat {
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quat {
+                x: (m[1][2] - m[2][1]) / s,
+                y: (m[2][0] - m[0][2]) / s,
+                z: (m[0][1] - m[1][0]) / s,
+                w: 0.25 * s,
+            }
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            Quat {
+                x: 0.25 * s,
+                y: (m[1][0] + m[0][1]) / s,
+                z: (m[2][0] + m[0][2]) / s,
+                w: (m[1][2] - m[2][1]) / s,
+            }
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            Quat {
+                x: (m[1][0] + m[0][1]) / s,
+                y: 0.25 * s,
+                z: (m[2][1] + m[1][2]) / s,
+                w: (m[2][0] - m[0][2]) / s,
+            }
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            Quat {
+                x: (m[2][0] + m[0][2]) / s,
+                y: (m[2][1] + m[1][2]) / s,
+                z: 0.25 * s,
+                w: (m[0][1] - m[1][0]) / s,
+            }
+        };
+        Quat::normalize(q)
+    }
+}
@@ -0,0 +1,168 @@
+//! rustc_serialize support for the math types, so they can be read from
+//! config and scene files. Vectors, quaternions and colors are arrays of
+//! numbers, matrices are arrays of 4 columns (or 16 numbers, column after
+//! column) and transforms are objects.
+
+extern crate rustc_serialize;
+
+use self::rustc_serialize::{Encodable, Decodable, Encoder, Decoder};
+
+use math::vec2::*;
+use math::vec3::*;
+use math::vec4::*;
+use math::mat4::*;
+use math::quat::*;
+use math::color::*;
+use math::transform::Transform;
+
+fn encode_floats<S: Encoder>(s: &mut S, v: &[f32]) -> Result<(), S::Error> {
+    s.emit_seq(v.len(), |s| {
+        for (i, x) in v.iter().enumerate() {
+            s.emit_seq_elt(i, |s| s.emit_f32(*x))?;
+        }
+        Ok(())
+    })
+}
+
+/// Read an array of numbers, of one of the accepted lengths
+fn decode_floats<D: Decoder>(d: &mut D, lengths: &[usize]) -> Result<Vec<f32>, D::Error> {
+    d.read_seq(|d, len| {
+        if !lengths.contains(&len) {
+            return Err(d.error(&format!("expected an array of {:?} numbers, found {} elements",
+                                        lengths, len)));
+        }
+        (0..len).map(|i| d.read_seq_elt(i, |d| d.read_f32())).collect()
+    })
+}
+
+impl Encodable for Vec2 {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        encode_floats(s, &[self.x, self.y])
+    }
+}
+
+impl Decodable for Vec2 {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Vec2, D::Error> {
+        let v = decode_floats(d, &[2])?;
+        Ok(Vec2::new(v[0], v[1]))
+    }
+}
+
+impl Encodable for Vec3 {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        encode_floats(s, &[self.x, self.y, self.z])
+    }
+}
+
+impl Decodable for Vec3 {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Vec3, D::Error> {
+        let v = decode_floats(d, &[3])?;
+        Ok(Vec3::new(v[0], v[1], v[2]))
+    }
+}
+
+impl Encodable for Vec4 {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        encode_floats(s, &[self.x, self.y, self.z, self.w])
+    }
+}
+
+impl Decodable for Vec4 {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Vec4, D::Error> {
+        let v = decode_floats(d, &[4])?;
+        Ok(Vec4::new(v[0], v[1], v[2], v[3]))
+    }
+}
+
+impl Encodable for Mat4 {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_seq(4, |s| {
+            for i in 0..4 {
+                s.emit_seq_elt(i, |s| self.m[i].encode(s))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Decodable for Mat4 {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Mat4, D::Error> {
+        d.read_seq(|d, len| {
+            let mut m = Mat4::empty();
+            match len {
+                4 => {
+                    for i in 0..4 {
+                        m.m[i] = d.read_seq_elt(i, Vec4::decode)?;
+                    }
+                }
+                16 => {
+                    for i in 0..16 {
+                        m.m[i / 4][i % 4] = d.read_seq_elt(i, |d| d.read_f32())?;
+                    }
+                }
+                _ => {
+                    return Err(d.error(&format!("expected 4 columns or 16 numbers for a matrix, found {} elements", len)));
+                }
+            }
+            Ok(m)
+        })
+    }
+}
+
+impl Encodable for Quat {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        encode_floats(s, &[self.x, self.y, self.z, self.w])
+    }
+}
+
+/// Either [x, y, z, w] or euler angles in degrees [pitch, yaw, roll]
+impl Decodable for Quat {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Quat, D::Error> {
+        let v = decode_floats(d, &[3, 4])?;
+        if v.len() == 3 {
+            Ok(Quat::from_euler(v[0], v[1], v[2]))
+        } else {
+            Ok(Quat::new(v[0], v[1], v[2], v[3]))
+        }
+    }
+}
+
+impl Encodable for Color {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        encode_floats(s, &[self.r, self.g, self.b, self.a])
+    }
+}
+
+/// [r, g, b, a], or [r, g, b] for an opaque color
+impl Decodable for Color {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Color, D::Error> {
+        let v = decode_floats(d, &[3, 4])?;
+        Ok(Color::new(v[0], v[1], v[2], if v.len() == 4 { v[3] } else { 1.0 }))
+    }
+}
+
+impl Encodable for Transform {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.emit_struct("Transform", 3, |s| {
+            s.emit_struct_field("translation", 0, |s| self.translation.encode(s))?;
+            s.emit_struct_field("rotation", 1, |s| self.rotation.encode(s))?;
+            s.emit_struct_field("scale", 2, |s| self.scale.encode(s))
+        })
+    }
+}
+
+/// Missing fields default to the identity
+impl Decodable for Transform {
+    fn decode<D: Decoder>(d: &mut D) -> Result<Transform, D::Error> {
+        d.read_struct("Transform", 3, |d| {
+            let translation = d.read_struct_field("translation", 0, Option::<Vec3>::decode)?;
+            let rotation = d.read_struct_field("rotation", 1, Option::<Quat>::decode)?;
+            let scale = d.read_struct_field("scale", 2, Option::<Vec3>::decode)?;
+
+            let id = Transform::identity();
+            Ok(Transform::new(translation.unwrap_or(id.translation),
+                              rotation.unwrap_or(id.rotation),
+                              scale.unwrap_or(id.scale)))
+        })
+    }
+}
@@ -0,0 +1,239 @@
+//! SSE2/AVX kernels for the hot Mat4 operations. SSE2 is part of the
+//! x86_64 baseline and is always used there, AVX is detected at runtime for
+//! the batch transforms. Other architectures use the scalar versions from
+//! mat4.rs.
+//!
+//! Products are evaluated in the same order as the scalar code and never use
+//! FMA, so multiplications give the exact same results on every path.
+
+use math::vec3::*;
+use math::vec4::*;
+use math::mat4::*;
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn load(v: &Vec4) -> __m128 {
+    _mm_loadu_ps(v as *const Vec4 as *const f32)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn store(v: __m128) -> Vec4 {
+    let mut r = Vec4::empty();
+    _mm_storeu_ps(&mut r as *mut Vec4 as *mut f32, v);
+    r
+}
+
+/// c0 * x + c1 * y + c2 * z + c3 * w
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn combine(c: &[__m128; 4], x: __m128, y: __m128, z: __m128, w: __m128) -> __m128 {
+    let r = _mm_add_ps(_mm_mul_ps(c[0], x), _mm_mul_ps(c[1], y));
+    let r = _mm_add_ps(r, _mm_mul_ps(c[2], z));
+    _mm_add_ps(r, _mm_mul_ps(c[3], w))
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn columns(m: &Mat4) -> [__m128; 4] {
+    [load(&m.m[0]), load(&m.m[1]), load(&m.m[2]), load(&m.m[3])]
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+unsafe fn transform(c: &[__m128; 4], v: __m128) -> __m128 {
+    combine(c,
+            _mm_shuffle_ps(v, v, 0x00),
+            _mm_shuffle_ps(v, v, 0x55),
+            _mm_shuffle_ps(v, v, 0xAA),
+            _mm_shuffle_ps(v, v, 0xFF))
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    unsafe {
+        let c = columns(b);
+        Mat4::from_vec4(store(transform(&c, load(&a.m[0]))),
+                        store(transform(&c, load(&a.m[1]))),
+                        store(transform(&c, load(&a.m[2]))),
+                        store(transform(&c, load(&a.m[3]))))
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    Mat4::mul_scalar(*a, *b)
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn mul_vec4(m: &Mat4, v: Vec4) -> Vec4 {
+    unsafe { store(transform(&columns(m), load(&v))) }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn mul_vec4(m: &Mat4, v: Vec4) -> Vec4 {
+    Mat4::mul_vec4_scalar(*m, v)
+}
+
+/// Cofactor inverse from Intel's "Streaming SIMD Extensions - Inverse of
+/// 4x4 Matrix" note, with an exact division instead of the reciprocal
+/// estimate. The inverse of the transpose is the transpose of the inverse,
+/// so the storage order does not matter.
+#[cfg(target_arch = "x86_64")]
+pub fn inverse(m: &Mat4) -> Mat4 {
+    unsafe {
+        let mut row0 = load(&m.m[0]);
+        let mut row1 = load(&m.m[1]);
+        let mut row2 = load(&m.m[2]);
+        let mut row3 = load(&m.m[3]);
+        _MM_TRANSPOSE4_PS(&mut row0, &mut row1, &mut row2, &mut row3);
+        row1 = _mm_shuffle_ps(row1, row1, 0x4E);
+        row3 = _mm_shuffle_ps(row3, row3, 0x4E);
+
+        let mut tmp = _mm_mul_ps(row2, row3);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0xB1);
+        let mut minor0 = _mm_mul_ps(row1, tmp);
+        let mut minor1 = _mm_mul_ps(row0, tmp);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0x4E);
+        minor0 = _mm_sub_ps(_mm_mul_ps(row1, tmp), minor0);
+        minor1 = _mm_sub_ps(_mm_mul_ps(row0, tmp), minor1);
+        minor1 = _mm_shuffle_ps(minor1, minor1, 0x4E);
+
+        tmp = _mm_mul_ps(row1, row2);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0xB1);
+        minor0 = _mm_add_ps(_mm_mul_ps(row3, tmp), minor0);
+        let mut minor3 = _mm_mul_ps(row0, tmp);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0x4E);
+        minor0 = _mm_sub_ps(minor0, _mm_mul_ps(row3, tmp));
+        minor3 = _mm_sub_ps(_mm_mul_ps(row0, tmp), minor3);
+        minor3 = _mm_shuffle_ps(minor3, minor3, 0x4E);
+
+        tmp = _mm_mul_ps(_mm_shuffle_ps(row1, row1, 0x4E), row3);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0xB1);
+        row2 = _mm_shuffle_ps(row2, row2, 0x4E);
+        minor0 = _mm_add_ps(_mm_mul_ps(row2, tmp), minor0);
+        let mut minor2 = _mm_mul_ps(row0, tmp);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0x4E);
+        minor0 = _mm_sub_ps(minor0, _mm_mul_ps(row2, tmp));
+        minor2 = _mm_sub_ps(_mm_mul_ps(row0, tmp), minor2);
+        minor2 = _mm_shuffle_ps(minor2, minor2, 0x4E);
+
+        tmp = _mm_mul_ps(row0, row1);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0xB1);
+        minor2 = _mm_add_ps(_mm_mul_ps(row3, tmp), minor2);
+        minor3 = _mm_sub_ps(_mm_mul_ps(row2, tmp), minor3);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0x4E);
+        minor2 = _mm_sub_ps(_mm_mul_ps(row3, tmp), minor2);
+        minor3 = _mm_sub_ps(minor3, _mm_mul_ps(row2, tmp));
+
+        tmp = _mm_mul_ps(row0, row3);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0xB1);
+        minor1 = _mm_sub_ps(minor1, _mm_mul_ps(row2, tmp));
+        minor2 = _mm_add_ps(_mm_mul_ps(row1, tmp), minor2);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0x4E);
+        minor1 = _mm_add_ps(_mm_mul_ps(row2, tmp), minor1);
+        minor2 = _mm_sub_ps(minor2, _mm_mul_ps(row1, tmp));
+
+        tmp = _mm_mul_ps(row0, row2);
+        tmp = _mm_shuffle_ps(tmp, tmp, 0xB1);
+        minor1 = _mm_add_ps(_mm_mul_ps(row3, tmp), minor1);
+        minor3 = _mm_sub_ps(minor3, _mm_mul_ps(row1, tmp));
+        tmp = _mm_shuffle_ps(tmp, tmp, 0x4E);
+        minor1 = _mm_sub_ps(minor1, _mm_mul_ps(row3, tmp));
+        minor3 = _mm_add_ps(_mm_mul_ps(row1, tmp), minor3);
+
+        let mut det = _mm_mul_ps(row0, minor0);
+        det = _mm_add_ps(_mm_shuffle_ps(det, det, 0x4E), det);
+        det = _mm_add_ss(_mm_shuffle_ps(det, det, 0xB1), det);
+        if _mm_cvtss_f32(det) == 0.0 {
+            return Mat4::empty();
+        }
+        det = _mm_shuffle_ps(det, det, 0x00);
+
+        Mat4::from_vec4(store(_mm_div_ps(minor0, det)),
+                        store(_mm_div_ps(minor1, det)),
+                        store(_mm_div_ps(minor2, det)),
+                        store(_mm_div_ps(minor3, det)))
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn inverse(m: &Mat4) -> Mat4 {
+    Mat4::inverse_scalar(*m)
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn transform_vec4s(m: &Mat4, v: &mut [Vec4]) {
+    if is_x86_feature_detected!("avx") {
+        unsafe { transform_vec4s_avx(m, v) }
+    } else {
+        unsafe { transform_vec4s_sse(m, v) }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn transform_vec4s_sse(m: &Mat4, v: &mut [Vec4]) {
+    let c = columns(m);
+    for p in v.iter_mut() {
+        *p = store(transform(&c, load(p)));
+    }
+}
+
+/// Two vectors per iteration, one in each 128 bit lane
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn transform_vec4s_avx(m: &Mat4, v: &mut [Vec4]) {
+    let c = columns(m);
+    let c0 = _mm256_broadcast_ps(&c[0]);
+    let c1 = _mm256_broadcast_ps(&c[1]);
+    let c2 = _mm256_broadcast_ps(&c[2]);
+    let c3 = _mm256_broadcast_ps(&c[3]);
+
+    let pairs = v.len() / 2;
+    for i in 0..pairs {
+        let ptr = v.as_mut_ptr().add(2 * i) as *mut f32;
+        let p = _mm256_loadu_ps(ptr);
+        let r = _mm256_add_ps(_mm256_mul_ps(c0, _mm256_permute_ps(p, 0x00)),
+                              _mm256_mul_ps(c1, _mm256_permute_ps(p, 0x55)));
+        let r = _mm256_add_ps(r, _mm256_mul_ps(c2, _mm256_permute_ps(p, 0xAA)));
+        let r = _mm256_add_ps(r, _mm256_mul_ps(c3, _mm256_permute_ps(p, 0xFF)));
+        _mm256_storeu_ps(ptr, r);
+    }
+    if v.len() % 2 == 1 {
+        let last = v.len() - 1;
+        v[last] = store(transform(&c, load(&v[last])));
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn transform_vec4s(m: &Mat4, v: &mut [Vec4]) {
+    for p in v.iter_mut() {
+        *p = Mat4::mul_vec4_scalar(*m, *p);
+    }
+}
+
+/// Transform Vec3s with the given w, dropping the resulting w
+#[cfg(target_arch = "x86_64")]
+pub fn transform_vec3s(m: &Mat4, v: &mut [Vec3], w: f32) {
+    unsafe {
+        let c = columns(m);
+        let w = _mm_set1_ps(w);
+        let mut out = [0.0f32; 4];
+        for p in v.iter_mut() {
+            let r = combine(&c, _mm_set1_ps(p.x), _mm_set1_ps(p.y), _mm_set1_ps(p.z), w);
+            _mm_storeu_ps(out.as_mut_ptr(), r);
+            *p = Vec3::new(out[0], out[1], out[2]);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn transform_vec3s(m: &Mat4, v: &mut [Vec3], w: f32) {
+    for p in v.iter_mut() {
+        let r = Mat4::mul_vec4_scalar(*m, Vec4::new(p.x, p.y, p.z, w));
+        *p = Vec3::new(r.x, r.y, r.z);
+    }
+}
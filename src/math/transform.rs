@@ -1,6 +1,9 @@
 use math::vec4::*;
 use math::vec3::*;
+use math::vec2::*;
 use math::mat4::*;
+use math::mat3::*;
+use math::quat::*;
 
 pub fn translation(pos : Vec3) -> Mat4 {
     Mat4::from_vec4(
@@ -15,8 +18,8 @@ pub fn translation(pos : Vec3) -> Mat4 {
 /// angle in degree (angle_deg) around an unit axis.
 pub fn rotate(angle_deg : f32, axis : Vec3) -> Mat4 {
     let axis = Vec3::normalize(axis);
-    let sin = angle_deg.sin();
-    let cos = angle_deg.cos();
+    let sin = angle_deg.to_radians().sin();
+    let cos = angle_deg.to_radians().cos();
     let negcos = 1.0 - cos;
     Mat4::from_vec4(
         Vec4::new(cos + axis.x.powi(2) * negcos,axis.y * axis.x * negcos + axis.z * sin, axis.z * axis.x * negcos - axis.y * sin, 0.0),
@@ -35,3 +38,129 @@ pub fn scale(size : Vec3) -> Mat4 {
     result
 }
 
+/// Return a 2D translation Matrix (Mat3) for homogeneous 2D coordinates
+pub fn translation_2d(pos : Vec2) -> Mat3 {
+    Mat3::from_vec3(
+        Vec3::new(1.0,0.0,0.0),
+        Vec3::new(0.0,1.0,0.0),
+        Vec3::new(pos.x,pos.y,1.0)
+    )
+}
+
+/// Return a 2D rotation Matrix (Mat3) for a counter-clockwise rotation
+/// of a particular angle in degree (angle_deg)
+pub fn rotate_2d(angle_deg : f32) -> Mat3 {
+    let sin = angle_deg.to_radians().sin();
+    let cos = angle_deg.to_radians().cos();
+    Mat3::from_vec3(
+        Vec3::new(cos,sin,0.0),
+        Vec3::new(-sin,cos,0.0),
+        Vec3::new(0.0,0.0,1.0)
+    )
+}
+
+/// Return a possibly non uniform 2D scaling matrix
+pub fn scale_2d(size : Vec2) -> Mat3 {
+    let mut result = Mat3::identity();
+    result[0][0] *= size.x;
+    result[1][1] *= size.y;
+    result
+}
+
+/// Translation, rotation and scale of an object. The scale is applied
+/// first, then the rotation, then the translation.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub fn new(translation: Vec3, rotation: Quat, scale: Vec3) -> Transform {
+        Transform {
+            translation: translation,
+            rotation: rotation,
+            scale: scale,
+        }
+    }
+
+    pub fn identity() -> Transform {
+        Transform::new(Vec3::empty(), Quat::identity(), Vec3::new(1.0, 1.0, 1.0))
+    }
+
+    /// Return the model matrix of the transform
+    pub fn to_mat4(&self) -> Mat4 {
+        let r = Quat::to_mat4(self.rotation);
+        Mat4::from_vec4(
+            r[0] * self.scale.x,
+            r[1] * self.scale.y,
+            r[2] * self.scale.z,
+            Vec4::new(self.translation.x, self.translation.y, self.translation.z, 1.0)
+        )
+    }
+
+    /// Decompose an affine matrix (no shear nor projection) into its
+    /// translation, rotation and scale. A reflection ends up as a
+    /// negative x scale.
+    pub fn from_mat4(m: &Mat4) -> Transform {
+        let translation = Vec3::new(m[3][0], m[3][1], m[3][2]);
+        let basis = Mat3::from_mat4(m);
+
+        let mut scale = Vec3::new(Vec3::length(basis[0]), Vec3::length(basis[1]), Vec3::length(basis[2]));
+        if Mat3::determinant(basis) < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        let mut r = Mat4::identity();
+        for i in 0..3 {
+            if scale[i] != 0.0 {
+                let col = basis[i] / scale[i];
+                r[i] = Vec4::new(col.x, col.y, col.z, 0.0);
+            }
+        }
+
+        Transform::new(translation, Quat::from_mat4(r), scale)
+    }
+
+    /// Inverse transform. Only exact for uniform scales, use inverse_mat4
+    /// otherwise.
+    pub fn inverse(&self) -> Transform {
+        let rotation = Quat::inverse(self.rotation);
+        let scale = Vec3::new(1.0, 1.0, 1.0) / self.scale;
+        let translation = -(scale * (rotation * self.translation));
+        Transform::new(translation, rotation, scale)
+    }
+
+    /// Exact inverse of the model matrix
+    pub fn inverse_mat4(&self) -> Mat4 {
+        Mat4::affine_inverse(&self.to_mat4())
+    }
+
+    /// Inverse transpose of the model matrix, for normals
+    pub fn normal_matrix(&self) -> Mat3 {
+        Mat3::normal_matrix(&self.to_mat4())
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        self.rotation * (p * self.scale) + self.translation
+    }
+
+    /// Transform a direction, ignoring the translation
+    pub fn transform_direction(&self, d: Vec3) -> Vec3 {
+        self.rotation * (d * self.scale)
+    }
+
+    /// Transform a normal by the inverse transpose, keeping it orthogonal
+    /// to transformed surfaces. The result is normalized
+    pub fn transform_normal(&self, n: Vec3) -> Vec3 {
+        Vec3::normalize(self.rotation * (n / self.scale))
+    }
+
+    /// Interpolate between two transforms, slerping the rotation
+    pub fn interpolate(a: &Transform, b: &Transform, t: f32) -> Transform {
+        Transform::new(a.translation + (b.translation - a.translation) * t,
+                       Quat::slerp(a.rotation, b.rotation, t),
+                       a.scale + (b.scale - a.scale) * t)
+    }
+}
@@ -0,0 +1,237 @@
+use std::ops::{Add, Mul, Div, Sub, DivAssign, MulAssign, SubAssign, AddAssign, Neg, Index, IndexMut};
+use std::fmt;
+
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Index<usize> for Vec2 {
+    type Output = f32;
+    fn index<'a>(&'a self, _index: usize) -> &'a f32 {
+        match _index {
+            0 => &self.x,
+            1 => &self.y,
+            _ => panic!("Invalid index access in Vec2 {:?}"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Vec2 {
+    fn index_mut<'a>(&'a mut self, _index: usize) -> &'a mut f32 {
+        match _index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            _ => panic!("Invalid index access in Vec2 {:?}"),
+        }
+    }
+}
+/// Display function
+impl fmt::Display for Vec2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({},{})", self.x, self.y)
+    }
+}
+
+/// Inverse a vector
+impl Neg for Vec2 {
+    type Output = Vec2;
+    fn neg(self) -> Vec2 {
+        Vec2 {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+/// Simple addition between two vectors (component-wise)
+impl Add for Vec2 {
+    type Output = Vec2;
+    #[inline(always)]
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+/// Simple self-addition
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
+/// Simple substraction between two vectors (component-wise)
+impl Sub for Vec2 {
+    type Output = Vec2;
+    #[inline(always)]
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+/// Simple self substraction
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Vec2) {
+        self.x -= other.x;
+        self.y -= other.y;
+    }
+}
+
+/// Simple component-wise multiplication between two vectors
+impl Mul<Vec2> for Vec2 {
+    type Output = Vec2;
+    #[inline(always)]
+    fn mul(self, other: Vec2) -> Vec2 {
+        Vec2 {
+            x: self.x * other.x,
+            y: self.y * other.y,
+        }
+    }
+}
+
+/// Simple component-wise self assign multiplication between two vectors
+impl MulAssign<Vec2> for Vec2 {
+    fn mul_assign(&mut self, other: Vec2) {
+        self.x *= other.x;
+        self.y *= other.y;
+    }
+}
+
+/// Simple component-wise multiplication between two vectors
+impl Mul<f32> for Vec2 {
+    type Output = Vec2;
+    #[inline(always)]
+    fn mul(self, other: f32) -> Vec2 {
+        Vec2 {
+            x: self.x * other,
+            y: self.y * other,
+        }
+    }
+}
+
+/// Simple component-wise self assign multiplication between two vectors
+impl MulAssign<f32> for Vec2 {
+    fn mul_assign(&mut self, other: f32) {
+        self.x *= other;
+        self.y *= other;
+    }
+}
+
+/// Component wise division a / b
+impl Div<Vec2> for Vec2 {
+    type Output = Vec2;
+    #[inline(always)]
+    fn div(self, other: Vec2) -> Vec2 {
+        Vec2 {
+            x: self.x / other.x,
+            y: self.y / other.y,
+        }
+    }
+}
+
+/// Component wise division a / b
+impl DivAssign<Vec2> for Vec2 {
+    fn div_assign(&mut self, other: Vec2) {
+        self.x /= other.x;
+        self.y /= other.y;
+    }
+}
+
+/// Scalar division
+impl Div<f32> for Vec2 {
+    type Output = Vec2;
+    #[inline(always)]
+    fn div(self, other: f32) -> Vec2 {
+        Vec2 {
+            x: self.x / other,
+            y: self.y / other,
+        }
+    }
+}
+
+/// Component wise division a / b
+impl DivAssign<f32> for Vec2 {
+    fn div_assign(&mut self, other: f32) {
+        self.x /= other;
+        self.y /= other;
+    }
+}
+
+impl Vec2 {
+    /// create a new vector with the specified coordinates
+    pub fn new(x: f32, y: f32) -> Vec2 {
+        Vec2 { x: x, y: y }
+    }
+
+    /// return an empty vector
+    pub fn empty() -> Vec2 {
+        Vec2 {
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+
+    /// return an unit vector on x axis
+    pub fn x() -> Vec2 {
+        Vec2 {
+            x: 1.0,
+            y: 0.0,
+        }
+    }
+
+    /// return an unit vector on y axis
+    pub fn y() -> Vec2 {
+        Vec2 {
+            x: 0.0,
+            y: 1.0,
+        }
+    }
+
+    pub fn normalize(a: Vec2) -> Vec2 {
+        let length = (a.x * a.x + a.y * a.y).sqrt();
+        // a / length
+        Vec2 {
+            x: a.x / length,
+            y: a.y / length,
+        }
+    }
+
+    pub fn dot(a: Vec2, b: Vec2) -> f32 {
+        a.x * b.x + a.y * b.y
+    }
+
+    /// z component of the 3D cross product of (a, 0) and (b, 0).
+    /// Positive if b is counter-clockwise from a
+    pub fn cross(a: Vec2, b: Vec2) -> f32 {
+        a.x * b.y - a.y * b.x
+    }
+
+    /// return a vector rotated 90 degrees counter-clockwise
+    pub fn perp(a: Vec2) -> Vec2 {
+        Vec2 {
+            x: -a.y,
+            y: a.x,
+        }
+    }
+
+    pub fn length(a: Vec2) -> f32 {
+        let dot_product = Vec2::dot(a, a);
+        if dot_product > 0.0 {
+            dot_product.sqrt()
+        } else {
+            0.0
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const f32 {
+        &(self[0]) as *const f32
+    }
+}
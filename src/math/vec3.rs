@@ -241,7 +241,7 @@ impl Vec3 {
         Vec3 {
             x: a.y * b.z - a.z * b.y,
             y: a.z * b.x - a.x * b.z,
-            z: a.x * b.y - a.y * b.z,
+            z: a.x * b.y - a.y * b.x,
         }
     }
 
@@ -2,6 +2,7 @@ use std::ops::{Add, Mul, Div, Sub, DivAssign, MulAssign, SubAssign, AddAssign, N
                IndexMut};
 use std::fmt;
 
+#[repr(C)]
 #[derive(Copy,Clone,Debug,PartialEq)]
 pub struct Vec4 {
     pub x: f32,
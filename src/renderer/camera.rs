@@ -0,0 +1,205 @@
+use math::vec2::*;
+use math::vec3::*;
+use math::vec4::*;
+use math::mat4::*;
+use math::quat::*;
+use math::transform;
+use math::geometry::{Ray, Frustum, DepthRange};
+
+/// Projection model of a camera. Sizes come from the camera viewport.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub enum Projection {
+    /// horizontal fov in degrees
+    Perspective { fov_x: f32, near: f32, far: f32 },
+    /// screen aligned projection, with world units being pixels at zoom 1.
+    /// The camera position is the top-left corner of the view, y going down
+    Orthographic { zoom: f32, near: f32, far: f32 },
+}
+
+impl Projection {
+    pub fn depth_range(&self) -> DepthRange {
+        match *self {
+            Projection::Perspective { .. } => DepthRange::MinusOneToOne,
+            Projection::Orthographic { .. } => DepthRange::ZeroToOne,
+        }
+    }
+}
+
+/// A camera owning its view and projection matrices. The camera looks
+/// down its local -z axis, with +y up.
+pub struct Camera {
+    position: Vec3,
+    rotation: Quat,
+    projection: Projection,
+    viewport: Vec2,
+
+    view_matrix: Mat4,
+    proj_matrix: Mat4,
+    view_proj_matrix: Mat4,
+    inv_view_proj_matrix: Mat4,
+}
+
+impl Camera {
+    pub fn new(projection: Projection, viewport: Vec2) -> Camera {
+        let mut cam = Camera {
+            position: Vec3::empty(),
+            rotation: Quat::identity(),
+            projection: projection,
+            viewport: viewport,
+
+            view_matrix: Mat4::identity(),
+            proj_matrix: Mat4::identity(),
+            view_proj_matrix: Mat4::identity(),
+            inv_view_proj_matrix: Mat4::identity(),
+        };
+        cam.update_proj_matrix();
+        cam
+    }
+
+    /// 3D perspective camera, fov_x in degrees
+    pub fn perspective(fov_x: f32, viewport: Vec2, near: f32, far: f32) -> Camera {
+        Camera::new(Projection::Perspective { fov_x: fov_x, near: near, far: far }, viewport)
+    }
+
+    /// 2D camera mapping world units to pixels, origin at the top-left
+    pub fn orthographic(viewport: Vec2, near: f32, far: f32) -> Camera {
+        Camera::new(Projection::Orthographic { zoom: 1.0, near: near, far: far }, viewport)
+    }
+
+    fn update_view_matrix(&mut self) {
+        // move the world by -position, then rotate it by the inverse camera rotation
+        self.view_matrix = transform::translation(-self.position) *
+                           Quat::to_mat4(Quat::inverse(self.rotation));
+        self.update_view_proj_matrix();
+    }
+
+    fn update_proj_matrix(&mut self) {
+        let (w, h) = (self.viewport.x.max(1.0), self.viewport.y.max(1.0));
+        self.proj_matrix = match self.projection {
+            Projection::Perspective { fov_x, near, far } => {
+                Mat4::persp(fov_x, w / h, near, far)
+            }
+            Projection::Orthographic { zoom, near, far } => {
+                Mat4::ortho(0.0, w / zoom, h / zoom, 0.0, near, far)
+            }
+        };
+        self.update_view_proj_matrix();
+    }
+
+    fn update_view_proj_matrix(&mut self) {
+        self.view_proj_matrix = self.view_matrix * self.proj_matrix;
+        self.inv_view_proj_matrix = Mat4::inverse(self.view_proj_matrix);
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, pos: Vec3) {
+        self.position = pos;
+        self.update_view_matrix();
+    }
+
+    pub fn rotation(&self) -> Quat {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, rot: Quat) {
+        self.rotation = Quat::normalize(rot);
+        self.update_view_matrix();
+    }
+
+    /// Orient the camera towards target
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        let f = Vec3::normalize(target - self.position);
+        let r = Vec3::normalize(Vec3::cross(f, up));
+        let u = Vec3::cross(r, f);
+        let basis = Mat4::from_vec4(Vec4::new(r.x, r.y, r.z, 0.0),
+                                    Vec4::new(u.x, u.y, u.z, 0.0),
+                                    Vec4::new(-f.x, -f.y, -f.z, 0.0),
+                                    Vec4::new(0.0, 0.0, 0.0, 1.0));
+        self.set_rotation(Quat::from_mat4(basis));
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.update_proj_matrix();
+    }
+
+    pub fn viewport(&self) -> Vec2 {
+        self.viewport
+    }
+
+    /// To be called when the window is resized
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.viewport = Vec2::new(width as f32, height as f32);
+        self.update_proj_matrix();
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * -Vec3::z()
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.rotation * Vec3::x()
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.rotation * Vec3::y()
+    }
+
+    pub fn view_matrix(&self) -> &Mat4 {
+        &self.view_matrix
+    }
+
+    pub fn proj_matrix(&self) -> &Mat4 {
+        &self.proj_matrix
+    }
+
+    pub fn view_proj_matrix(&self) -> &Mat4 {
+        &self.view_proj_matrix
+    }
+
+    /// World space frustum of the camera, for culling
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.view_proj_matrix, self.projection.depth_range())
+    }
+
+    /// Project a world position to the screen. Returns the position in
+    /// pixels (origin at the top-left) and the NDC depth in z, or None if
+    /// the point is behind the camera
+    pub fn world_to_screen(&self, p: Vec3) -> Option<Vec3> {
+        let clip = self.view_proj_matrix * Vec4::new(p.x, p.y, p.z, 1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let ndc = Vec3::new(clip.x, clip.y, clip.z) / clip.w;
+        Some(Vec3::new((ndc.x + 1.0) * 0.5 * self.viewport.x,
+                       (1.0 - ndc.y) * 0.5 * self.viewport.y,
+                       ndc.z))
+    }
+
+    /// Inverse of world_to_screen : screen position in pixels and NDC
+    /// depth back to world space
+    pub fn unproject(&self, p: Vec3) -> Vec3 {
+        let ndc = Vec3::new(2.0 * p.x / self.viewport.x - 1.0,
+                            1.0 - 2.0 * p.y / self.viewport.y,
+                            p.z);
+        Mat4::unproject(&self.inv_view_proj_matrix, ndc)
+    }
+
+    /// Ray from the near plane through the given pixel, for picking
+    pub fn screen_to_world_ray(&self, p: Vec2) -> Ray {
+        let near_z = match self.projection.depth_range() {
+            DepthRange::MinusOneToOne => -1.0,
+            DepthRange::ZeroToOne => 0.0,
+        };
+        let near = self.unproject(Vec3::new(p.x, p.y, near_z));
+        let far = self.unproject(Vec3::new(p.x, p.y, 1.0));
+        Ray::new(near, far - near)
+    }
+}
@@ -0,0 +1,178 @@
+extern crate glfw;
+extern crate gl;
+
+use renderer::mesh::Mesh;
+use renderer::shader::{Program, Shader, ShaderType};
+use renderer::text::{Font, TextBatch};
+use system::config::Config;
+use system::console::Console;
+use math::vec2::Vec2;
+use math::mat4::Mat4;
+use math::color::Color;
+
+/// Part of the window height covered by the open console
+const HEIGHT_RATIO: f32 = 0.4;
+const MARGIN: f32 = 4.0;
+
+/// Drop-down console drawn over the scene, toggled with the ` key
+pub struct ConsoleOverlay {
+    pub open: bool,
+    input: String,
+    /// Position in the console history while browsing it with up/down
+    history_pos: Option<usize>,
+
+    font: Font,
+    program: Program,
+    batch: TextBatch,
+    mesh: Option<Mesh>,
+
+    /// What the mesh was built for : window size, console lines printed
+    built_for: Option<(u32, u32, usize)>,
+    dirty: bool,
+}
+
+impl ConsoleOverlay {
+    pub fn new(font_path: &str) -> ConsoleOverlay {
+        let mut program = Program::new();
+        let vs = Shader::new(ShaderType::VERTEX, "data/shaders/text.vs".to_string());
+        let fs = Shader::new(ShaderType::FRAGMENT, "data/shaders/text.frag".to_string());
+        program.attach(&vs);
+        program.attach(&fs);
+        program.link();
+        program.register_uniform("ProjMatrix");
+        program.register_uniform("fontTexture");
+        program.set_uniform_1i("fontTexture", 0);
+
+        ConsoleOverlay {
+            open: false,
+            input: String::new(),
+            history_pos: None,
+            font: Font::from_image(font_path, 8, 16),
+            program: program,
+            batch: TextBatch::new(),
+            mesh: None,
+            built_for: None,
+            dirty: true,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.dirty = true;
+    }
+
+    /// Typed text. The toggle key is not part of the input
+    pub fn handle_char(&mut self, c: char) {
+        if c == '`' || c.is_control() {
+            return;
+        }
+        self.input.push(c);
+        self.dirty = true;
+    }
+
+    /// Editing keys, on press and repeat
+    pub fn handle_key(&mut self, key: glfw::Key, console: &mut Console, conf: &mut Config) {
+        match key {
+            glfw::Key::Enter | glfw::Key::KpEnter => {
+                let line = self.input.clone();
+                self.input.clear();
+                self.history_pos = None;
+                // errors are in the console log already
+                let _ = console.execute(conf, &line);
+            }
+            glfw::Key::Backspace => {
+                self.input.pop();
+            }
+            glfw::Key::Tab => {
+                let (completed, candidates) = console.complete(&self.input);
+                if candidates.len() > 1 {
+                    console.print(&format!("  {}", candidates.join("  ")));
+                }
+                self.input = completed;
+            }
+            glfw::Key::Up => {
+                let len = console.history().len();
+                if len > 0 {
+                    let pos = match self.history_pos {
+                        Some(pos) if pos > 0 => pos - 1,
+                        Some(pos) => pos,
+                        None => len - 1,
+                    };
+                    self.history_pos = Some(pos);
+                    self.input = console.history()[pos].clone();
+                }
+            }
+            glfw::Key::Down => {
+                if let Some(pos) = self.history_pos {
+                    if pos + 1 < console.history().len() {
+                        self.history_pos = Some(pos + 1);
+                        self.input = console.history()[pos + 1].clone();
+                    } else {
+                        self.history_pos = None;
+                        self.input.clear();
+                    }
+                }
+            }
+            glfw::Key::Escape => self.open = false,
+            _ => return,
+        }
+        self.dirty = true;
+    }
+
+    fn rebuild(&mut self, console: &Console, width: u32, height: u32) {
+        let glyph = self.font.glyph_size;
+        let panel_h = (height as f32 * HEIGHT_RATIO).floor();
+        let input_y = panel_h - MARGIN - glyph.y;
+
+        self.batch.clear();
+        self.batch.add_rect(&self.font, Vec2::new(0.0, 0.0), Vec2::new(width as f32, panel_h),
+                            &Color::new(0.0, 0.0, 0.0, 0.8));
+        self.batch.add_rect(&self.font, Vec2::new(0.0, panel_h), Vec2::new(width as f32, 2.0),
+                            &Color::new(0.5, 0.5, 0.5, 0.8));
+
+        // log lines from the bottom up, above the input line
+        let max_chars = ((width as f32 - 2.0 * MARGIN) / glyph.x).max(1.0) as usize;
+        let mut y = input_y - glyph.y;
+        for line in console.log().iter().rev() {
+            if y < 0.0 {
+                break;
+            }
+            let color = if line.starts_with("error:") { Color::new(1.0, 0.4, 0.4, 1.0) }
+                        else if line.starts_with("> ") { Color::new(0.6, 0.8, 1.0, 1.0) }
+                        else { Color::new(0.9, 0.9, 0.9, 1.0) };
+            let text: String = line.chars().take(max_chars).collect();
+            self.batch.add_text(&self.font, &text, Vec2::new(MARGIN, y), &color);
+            y -= glyph.y;
+        }
+
+        // keep the end of a long input visible
+        let prompt = format!("> {}_", self.input);
+        let skip = prompt.chars().count().saturating_sub(max_chars);
+        let prompt: String = prompt.chars().skip(skip).collect();
+        self.batch.add_text(&self.font, &prompt, Vec2::new(MARGIN, input_y), &Color::new(1.0, 1.0, 1.0, 1.0));
+
+        self.mesh = Some(self.batch.build());
+    }
+
+    pub fn render(&mut self, console: &Console, width: u32, height: u32) {
+        if !self.open {
+            return;
+        }
+
+        let state = (width, height, console.lines_printed());
+        if self.dirty || self.built_for != Some(state) {
+            self.rebuild(console, width, height);
+            self.built_for = Some(state);
+            self.dirty = false;
+        }
+
+        self.program.bind();
+        self.program.set_uniform_matrix4fv("ProjMatrix", &Mat4::ortho(0.0, width as f32, height as f32, 0.0, -1.0, 1.0));
+        self.font.texture.bind();
+        unsafe { gl::Disable(gl::DEPTH_TEST); }
+        if let Some(ref mesh) = self.mesh {
+            mesh.render();
+        }
+        unsafe { gl::Enable(gl::DEPTH_TEST); }
+    }
+}
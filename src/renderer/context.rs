@@ -1,12 +1,69 @@
 extern crate glfw;
 extern crate gl;
+extern crate rustc_serialize;
 
+use std::ffi::CStr;
 use std::sync::mpsc;
+use std::time::Duration;
 use self::glfw::Context as glfwContext;
 
+use system::asset::{AssetManager, DEFAULT_WORKERS};
 use system::config;
-use math::vec4::Vec4;
-use math::mat4::*;
+use system::console::{self, Console};
+use system::cvar::*;
+use self::rustc_serialize::{Decodable, Decoder};
+use math::vec2::Vec2;
+use math::color::Color;
+use renderer::camera::Camera;
+use renderer::console_overlay::ConsoleOverlay;
+
+/// The "window" section of the config file
+pub struct WindowConfig {
+    pub width: u32,
+    pub height: u32,
+    pub title: String,
+    pub clear_color: Color,
+    pub vsync: bool,
+}
+
+impl Decodable for WindowConfig {
+    fn decode<D: Decoder>(d: &mut D) -> Result<WindowConfig, D::Error> {
+        d.read_struct("WindowConfig", 5, |d| {
+            Ok(WindowConfig {
+                width: d.read_struct_field("width", 0, |d| d.read_u32())?,
+                height: d.read_struct_field("height", 1, |d| d.read_u32())?,
+                title: d.read_struct_field("title", 2, |d| d.read_str())?,
+                clear_color: d.read_struct_field("clear_color", 3, Color::decode)?,
+                vsync: d.read_struct_field("vsync", 4, |d| d.read_bool())?,
+            })
+        })
+    }
+}
+
+/// Console variables for the config values the engine reacts to at runtime
+pub fn register_cvars(console: &mut Console, conf: &mut config::Config) {
+    let archived = vec![
+        CVar::new("window.width", CVarType::Int, "Window width in pixels").with_range(1.0, 16384.0),
+        CVar::new("window.height", CVarType::Int, "Window height in pixels").with_range(1.0, 16384.0),
+        CVar::new("window.title", CVarType::Str, "Window title"),
+        CVar::new("window.clear_color", CVarType::Color, "Background color"),
+        CVar::new("window.vsync", CVarType::Bool, "Wait for the vertical blank to swap buffers"),
+        CVar::new("renderer.texture_filter", CVarType::Str, "Texture sampling : nearest, linear or trilinear"),
+        CVar::new("camera.move_speed", CVarType::Float, "Fly camera speed, in units per second").with_min(0.0),
+        CVar::new("camera.look_sensitivity", CVarType::Float, "Fly camera rotation per pixel of mouse movement").with_min(0.0),
+        CVar::new("camera.orbit_sensitivity", CVarType::Float, "Orbit camera rotation per pixel of mouse movement").with_min(0.0),
+        CVar::new("camera.pan_sensitivity", CVarType::Float, "Orbit camera panning per pixel, times the distance").with_min(0.0),
+        CVar::new("camera.zoom_speed", CVarType::Float, "Zoom per scroll wheel step").with_range(0.0, 1.0),
+        CVar::new("assets.upload_budget_ms", CVarType::Float, "Time per frame for uploading loaded assets").with_min(0.0),
+        CVar::new("assets.hot_reload", CVarType::Bool, "Reload assets when their files change on disk"),
+    ];
+    for cvar in archived.into_iter() {
+        console.cvars.register(conf, cvar.with_flags(CVAR_ARCHIVE));
+    }
+
+    console.cvars.register(conf, CVar::new("renderer.gl_version", CVarType::Str, "OpenGL version of the context")
+                                     .with_flags(CVAR_READ_ONLY));
+}
 
 pub struct Context {
     pub glfw: glfw::Glfw,
@@ -18,14 +75,23 @@ pub struct Context {
     pub window_height: u32,
 
     pub config: config::Config,
+    config_changes: mpsc::Receiver<config::ConfigChange>,
+
+    pub console: Console,
+    console_overlay: ConsoleOverlay,
+
+    pub assets: AssetManager,
 
     key_state: Vec<bool>,
     prev_key_state: Vec<bool>,
     mouse_state: Vec<bool>,
     prev_mouse_state: Vec<bool>,
+    mouse_pos: Vec2,
+    prev_mouse_pos: Vec2,
+    scroll: Vec2,
 
-    clear_color: Vec4,
-    pub proj_matrix_2d: Mat4,
+    clear_color: Color,
+    pub camera_2d: Camera,
 }
 
 impl Context {
@@ -35,25 +101,40 @@ impl Context {
     }
 
     pub fn new(config_file: &str) -> Context {
-        // load config file first
-        let conf = config::Config::new(config_file);
+        // load config first : defaults, config file, user file, environment, command line
+        let mut conf = match config::Config::load_layered(config_file) {
+            Ok(conf) => conf,
+            Err(err) => panic!("Error loading config file {}: {}", config_file, err)
+        };
 
         let mut ctx = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-        let winw = conf.get_u64("iWindowWidth") as u32;
-        let winh = conf.get_u64("iWindowHeight") as u32;
+        let win_conf = conf.get::<WindowConfig>("window");
+        let (winw, winh) = (win_conf.width, win_conf.height);
         println!("{} {}", winw, winh);
 
 		ctx.window_hint(glfw::WindowHint::ContextVersion(3, 2));
 		ctx.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
 		ctx.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
 		
-        let (mut window, events) = ctx.create_window(winw, winh, "radar-rs", glfw::WindowMode::Windowed)
+        let (mut window, events) = ctx.create_window(winw, winh, &win_conf.title, glfw::WindowMode::Windowed)
             .expect("Failed to create GLFW window.");
 
         window.make_current();
         window.set_all_polling(true);
 
         Context::load_gl_procs(&mut window);
+        ctx.set_swap_interval(Context::swap_interval(win_conf.vsync));
+
+        let mut console = Console::new();
+        if let Some(path) = console::history_path() {
+            if let Err(err) = console.load_history(&path) {
+                println!("Console warning: can't read history file {}: {}", path.display(), err);
+            }
+        }
+        register_cvars(&mut console, &mut conf);
+        let gl_version = unsafe { CStr::from_ptr(gl::GetString(gl::VERSION) as *const _) };
+        console.cvars.force(&mut conf, "renderer.gl_version", &gl_version.to_string_lossy().into_owned()).unwrap();
+        let console_overlay = ConsoleOverlay::new(conf.get_str_or("console.font", "data/fonts/console.png"));
 
         unsafe{
             gl::Enable(gl::DEPTH_TEST);
@@ -65,9 +146,15 @@ impl Context {
             gl::Viewport(0, 0, winw as i32, winh as i32);
         }
 
-        let default_clear_color = Vec4::new(0.2, 0.2, 0.2, 1.0);
+        let mut camera_2d = Camera::orthographic(Vec2::new(winw as f32, winh as f32),
+                                                 conf.get_f64("camera.2d.near") as f32,
+                                                 conf.get_f64("camera.2d.far") as f32);
+        camera_2d.set_position(conf.get_vec3("camera.2d.position"));
+
+        let config_changes = conf.subscribe("window");
 
-        let proj_matrix = Mat4::ortho(0.0, winw as f32, winh as f32, 0.0, 0.01, 100.0);
+        let mut assets = AssetManager::new();
+        assets.set_worker_count(conf.get_or("assets.workers", DEFAULT_WORKERS as u64) as usize);
 
         Context {
             glfw: ctx,
@@ -79,19 +166,73 @@ impl Context {
             window_height: winh,
 
             config: conf,
+            config_changes: config_changes,
+            console: console,
+            console_overlay: console_overlay,
+            assets: assets,
             key_state: vec![false; 1024],
             prev_key_state: vec![false; 1024],
             mouse_state: vec![false; 16],
             prev_mouse_state: vec![false; 16],
+            mouse_pos: Vec2::empty(),
+            prev_mouse_pos: Vec2::empty(),
+            scroll: Vec2::empty(),
 
-            clear_color: default_clear_color,
-            proj_matrix_2d: proj_matrix
+            clear_color: win_conf.clear_color,
+            camera_2d: camera_2d
+        }
+    }
+
+    fn swap_interval(vsync: bool) -> glfw::SwapInterval {
+        if vsync { glfw::SwapInterval::Sync(1) } else { glfw::SwapInterval::None }
+    }
+
+    /// Apply the window settings changed since last frame, from an options
+    /// screen, the console or an edit of the config file
+    fn handle_config_changes(&mut self) {
+        let changes: Vec<config::ConfigChange> = self.config_changes.try_iter().collect();
+        for change in changes {
+            match change.key.as_str() {
+                "window.width" | "window.height" => {
+                    if let (Ok(w), Ok(h)) = (self.config.try_get_u64("window.width"),
+                                             self.config.try_get_u64("window.height")) {
+                        // the Size event updates the camera
+                        self.window.set_size(w as i32, h as i32);
+                    }
+                }
+                "window.title" => {
+                    if let Ok(title) = self.config.try_get_str("window.title") {
+                        self.window.set_title(title);
+                    }
+                }
+                "window.clear_color" => {
+                    if let Ok(color) = self.config.try_get_color("window.clear_color") {
+                        self.clear_color = color;
+                    }
+                }
+                "window.vsync" => {
+                    if let Ok(vsync) = self.config.try_get_bool("window.vsync") {
+                        self.glfw.set_swap_interval(Context::swap_interval(vsync));
+                    }
+                }
+                _ => {}
+            }
         }
     }
 
     fn handle_window_events(&mut self, events: &[glfw::WindowEvent]) {
         for event in events {
             match *event {
+                glfw::WindowEvent::Key(glfw::Key::GraveAccent, _, glfw::Action::Press, _) => {
+                    self.console_overlay.toggle();
+                }
+                // the open console takes the keyboard
+                glfw::WindowEvent::Char(c) if self.console_overlay.open => {
+                    self.console_overlay.handle_char(c);
+                }
+                glfw::WindowEvent::Key(k, _, action, _) if self.console_overlay.open && action != glfw::Action::Release => {
+                    self.console_overlay.handle_key(k, &mut self.console, &mut self.config);
+                }
                 glfw::WindowEvent::Key(glfw::Key::Escape, _, glfw::Action::Press, _) => {
                     self.window.set_should_close(true)
                 }
@@ -107,9 +248,16 @@ impl Context {
                 glfw::WindowEvent::MouseButton(mb, glfw::Action::Release, _) => {
                     self.mouse_state[mb as usize] = false;
                 }
+                glfw::WindowEvent::CursorPos(x, y) => {
+                    self.mouse_pos = Vec2::new(x as f32, y as f32);
+                }
+                glfw::WindowEvent::Scroll(x, y) => {
+                    self.scroll += Vec2::new(x as f32, y as f32);
+                }
                 glfw::WindowEvent::Size(width,height) => {
                     self.window_width = width as u32;
                     self.window_height = height as u32;
+                    self.camera_2d.resize(self.window_width, self.window_height);
                 }
                 glfw::WindowEvent::FramebufferSize(fb_width,fb_height) => {
                     unsafe{
@@ -125,6 +273,8 @@ impl Context {
         // cpy last frame events as previous state
         self.prev_key_state.clone_from(&self.key_state);
         self.prev_mouse_state.clone_from(&self.mouse_state);
+        self.prev_mouse_pos = self.mouse_pos;
+        self.scroll = Vec2::empty();
 
         self.glfw.poll_events();
 
@@ -159,41 +309,53 @@ impl Context {
         self.mouse_state[mb as usize] == true
     }
 
+    /// Cursor position in pixels, from the top-left of the window
+    pub fn mouse_position(&self) -> Vec2 {
+        self.mouse_pos
+    }
+
+    /// Cursor movement since last frame
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_pos - self.prev_mouse_pos
+    }
+
+    /// Scroll wheel offset accumulated during this frame
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.scroll
+    }
+
     fn clear_buffers(&self) {
         unsafe {
-            gl::ClearColor(self.clear_color.x, self.clear_color.y, self.clear_color.z, self.clear_color.w);
+            gl::ClearColor(self.clear_color.r, self.clear_color.g, self.clear_color.b, self.clear_color.a);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         }
     }
 
     pub fn is_running(&self) -> bool {
-        !self.window.should_close()
+        !self.window.should_close() && !self.console.quit_requested()
     }
     
     pub fn start_frame(&mut self) {
         self.handle_events();
-
-        if self.is_key_down(glfw::Key::A) {
-            let newr = self.clear_color.x - 0.01;
-            if newr > 0.0 {
-                self.clear_color.x = newr;
-            } else {
-                self.clear_color.x = 0.0;
-            }
-        }
-        if self.is_key_down(glfw::Key::D) {
-            let newr = self.clear_color.x + 0.01;
-            if newr < 1.0 {
-                self.clear_color.x = newr;
-            } else {
-                self.clear_color.x = 1.0;
+        self.config.poll_files();
+        self.handle_config_changes();
+
+        // background loads finish here, where the context is current
+        let budget_ms = self.config.get_or("assets.upload_budget_ms", 4.0f64).max(0.0);
+        self.assets.update(Duration::from_micros((budget_ms * 1000.0) as u64));
+        if self.config.get_or("assets.hot_reload", true) {
+            for (path, result) in self.assets.poll_files() {
+                match result {
+                    Ok(()) => self.console.print(&format!("reloaded {}", path)),
+                    Err(err) => self.console.print(&format!("error: can't reload {}: {}, keeping the previous version", path, err)),
+                }
             }
         }
-
         self.clear_buffers();
     }
 
     pub fn end_frame(&mut self) {
+        self.console_overlay.render(&self.console, self.window_width, self.window_height);
         self.window.swap_buffers();
     }
 }
@@ -0,0 +1,373 @@
+extern crate glfw;
+
+use system::config::{Config, ConfigError};
+use renderer::context::Context;
+use renderer::camera::{Camera, Projection};
+use math::vec2::*;
+use math::vec3::*;
+use math::quat::*;
+
+/// Built-in values of the camera settings, used when they are missing from
+/// the config
+pub const DEFAULT_MOVE_SPEED: f64 = 5.0;
+pub const DEFAULT_LOOK_SENSITIVITY: f64 = 0.15;
+pub const DEFAULT_ORBIT_SENSITIVITY: f64 = 0.3;
+pub const DEFAULT_PAN_SENSITIVITY: f64 = 0.002;
+pub const DEFAULT_ZOOM_SPEED: f64 = 0.1;
+
+/// Parse a key name as written in config files ("W", "Space", "LeftShift"...)
+pub fn key_from_name(name: &str) -> Option<glfw::Key> {
+    use self::glfw::Key::*;
+
+    let key = match name {
+        "A" => A, "B" => B, "C" => C, "D" => D, "E" => E, "F" => F, "G" => G,
+        "H" => H, "I" => I, "J" => J, "K" => K, "L" => L, "M" => M, "N" => N,
+        "O" => O, "P" => P, "Q" => Q, "R" => R, "S" => S, "T" => T, "U" => U,
+        "V" => V, "W" => W, "X" => X, "Y" => Y, "Z" => Z,
+        "0" => Num0, "1" => Num1, "2" => Num2, "3" => Num3, "4" => Num4,
+        "5" => Num5, "6" => Num6, "7" => Num7, "8" => Num8, "9" => Num9,
+        "Space" => Space,
+        "Enter" => Enter,
+        "Tab" => Tab,
+        "Up" => Up,
+        "Down" => Down,
+        "Left" => Left,
+        "Right" => Right,
+        "PageUp" => PageUp,
+        "PageDown" => PageDown,
+        "LeftShift" => LeftShift,
+        "RightShift" => RightShift,
+        "LeftControl" => LeftControl,
+        "RightControl" => RightControl,
+        "LeftAlt" => LeftAlt,
+        "RightAlt" => RightAlt,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Key bound to field, or default if it is missing or not a key name
+fn config_key(conf: &Config, field: &str, default: glfw::Key) -> glfw::Key {
+    let name = match conf.try_get_str(field) {
+        Ok(name) => name,
+        Err(ConfigError::MissingKey(_)) => return default,
+        Err(err) => {
+            println!("Config warning: {}, using the default binding.", err);
+            return default;
+        }
+    };
+    match key_from_name(name) {
+        Some(k) => k,
+        None => {
+            println!("Config warning: unknown key name {} for {}, using the default binding.", name, field);
+            default
+        }
+    }
+}
+
+/// Config fields of the bindings, in the order of BINDING_DEFAULTS
+const BINDING_FIELDS: [&'static str; 6] = [
+    "input.bindings.forward", "input.bindings.back", "input.bindings.left",
+    "input.bindings.right", "input.bindings.up", "input.bindings.down",
+];
+
+/// WASD, E up and Q down
+const BINDING_DEFAULTS: [glfw::Key; 6] = [
+    glfw::Key::W, glfw::Key::S, glfw::Key::A, glfw::Key::D, glfw::Key::E, glfw::Key::Q,
+];
+
+/// Movement keys shared by the camera controllers
+pub struct CameraBindings {
+    pub forward: glfw::Key,
+    pub back: glfw::Key,
+    pub left: glfw::Key,
+    pub right: glfw::Key,
+    pub up: glfw::Key,
+    pub down: glfw::Key,
+    /// Config values the keys were read from, so that only the ones that
+    /// change are parsed again, and reported once if invalid
+    values: Vec<Option<String>>,
+}
+
+impl Default for CameraBindings {
+    fn default() -> CameraBindings {
+        CameraBindings {
+            forward: BINDING_DEFAULTS[0],
+            back: BINDING_DEFAULTS[1],
+            left: BINDING_DEFAULTS[2],
+            right: BINDING_DEFAULTS[3],
+            up: BINDING_DEFAULTS[4],
+            down: BINDING_DEFAULTS[5],
+            values: vec![None; BINDING_FIELDS.len()],
+        }
+    }
+}
+
+impl CameraBindings {
+    /// Bindings of the config. Missing or unknown keys keep their default
+    /// binding
+    pub fn from_config(conf: &Config) -> CameraBindings {
+        let mut bindings = CameraBindings::default();
+        bindings.apply_config(conf);
+        bindings
+    }
+
+    /// Read the bindings whose config value changed
+    pub fn apply_config(&mut self, conf: &Config) {
+        for (i, field) in BINDING_FIELDS.iter().enumerate() {
+            let value = conf.try_get_json(field).ok().map(|value| value.to_string());
+            if value != self.values[i] {
+                *self.key_mut(i) = config_key(conf, field, BINDING_DEFAULTS[i]);
+                self.values[i] = value;
+            }
+        }
+    }
+
+    fn key_mut(&mut self, i: usize) -> &mut glfw::Key {
+        match i {
+            0 => &mut self.forward,
+            1 => &mut self.back,
+            2 => &mut self.left,
+            3 => &mut self.right,
+            4 => &mut self.up,
+            _ => &mut self.down,
+        }
+    }
+
+    /// Movement direction in camera space (x right, y up, -z forward), given
+    /// which keys are down
+    pub fn direction<F: Fn(glfw::Key) -> bool>(&self, is_down: F) -> Vec3 {
+        let mut dir = Vec3::empty();
+        if is_down(self.forward) { dir.z -= 1.0; }
+        if is_down(self.back) { dir.z += 1.0; }
+        if is_down(self.right) { dir.x += 1.0; }
+        if is_down(self.left) { dir.x -= 1.0; }
+        if is_down(self.up) { dir.y += 1.0; }
+        if is_down(self.down) { dir.y -= 1.0; }
+        dir
+    }
+}
+
+/// First person fly camera. Looks around while the right mouse button is
+/// held, moves with the movement keys. The speeds and key bindings are read
+/// from the config on each update, changes apply right away.
+pub struct FpsController {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub move_speed: f32,
+    pub look_sensitivity: f32,
+    pub bindings: CameraBindings,
+}
+
+impl FpsController {
+    pub fn new(conf: &Config) -> FpsController {
+        let mut controller = FpsController {
+            yaw: 0.0,
+            pitch: 0.0,
+            move_speed: 0.0,
+            look_sensitivity: 0.0,
+            bindings: CameraBindings::default(),
+        };
+        controller.apply_config(conf);
+        controller
+    }
+
+    /// Read the speeds and the bindings that changed from the config
+    pub fn apply_config(&mut self, conf: &Config) {
+        self.bindings.apply_config(conf);
+        self.move_speed = conf.get_or("camera.move_speed", DEFAULT_MOVE_SPEED) as f32;
+        self.look_sensitivity = conf.get_or("camera.look_sensitivity", DEFAULT_LOOK_SENSITIVITY) as f32;
+    }
+
+    /// Turn by a mouse movement in pixels. The pitch stays short of the poles
+    pub fn look(&mut self, mouse_delta: Vec2) {
+        let delta = mouse_delta * self.look_sensitivity;
+        self.yaw -= delta.x;
+        self.pitch = (self.pitch - delta.y).max(-89.0).min(89.0);
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(self.pitch, self.yaw, 0.0)
+    }
+
+    /// Position after moving in dir, as given by CameraBindings::direction,
+    /// for dt seconds
+    pub fn step(&self, pos: Vec3, dir: Vec3, dt: f64) -> Vec3 {
+        if dir == Vec3::empty() {
+            return pos;
+        }
+        // horizontal movement follows the view, vertical stays world aligned
+        let dist = self.move_speed * dt as f32;
+        let mut pos = pos + self.rotation() * Vec3::new(dir.x, 0.0, dir.z) * dist;
+        pos.y += dir.y * dist;
+        pos
+    }
+
+    pub fn update(&mut self, ctx: &Context, cam: &mut Camera, dt: f64) {
+        self.apply_config(&ctx.config);
+        if ctx.is_mouse_down(glfw::MouseButtonRight) {
+            self.look(ctx.mouse_delta());
+        }
+
+        let dir = self.bindings.direction(|key| ctx.is_key_down(key));
+        let pos = self.step(cam.position(), dir, dt);
+
+        cam.set_position(pos);
+        cam.set_rotation(self.rotation());
+    }
+}
+
+/// Camera turning around a target point. Left mouse drag orbits, middle
+/// mouse drag pans the target and the scroll wheel zooms. The speeds are
+/// read from the config on each update, changes apply right away.
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub orbit_sensitivity: f32,
+    pub pan_sensitivity: f32,
+    pub zoom_speed: f32,
+}
+
+impl OrbitController {
+    pub fn new(conf: &Config, target: Vec3, distance: f32) -> OrbitController {
+        let mut controller = OrbitController {
+            target: target,
+            distance: distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            orbit_sensitivity: 0.0,
+            pan_sensitivity: 0.0,
+            zoom_speed: 0.0,
+        };
+        controller.apply_config(conf);
+        controller
+    }
+
+    /// Read the speeds from the config
+    pub fn apply_config(&mut self, conf: &Config) {
+        self.orbit_sensitivity = conf.get_or("camera.orbit_sensitivity", DEFAULT_ORBIT_SENSITIVITY) as f32;
+        self.pan_sensitivity = conf.get_or("camera.pan_sensitivity", DEFAULT_PAN_SENSITIVITY) as f32;
+        self.zoom_speed = conf.get_or("camera.zoom_speed", DEFAULT_ZOOM_SPEED) as f32;
+    }
+
+    pub fn orbit(&mut self, mouse_delta: Vec2) {
+        self.yaw -= mouse_delta.x * self.orbit_sensitivity;
+        self.pitch = (self.pitch - mouse_delta.y * self.orbit_sensitivity).max(-89.0).min(89.0);
+    }
+
+    pub fn pan(&mut self, mouse_delta: Vec2) {
+        // pan speed scales with the distance to keep the target under the cursor
+        let k = self.pan_sensitivity * self.distance;
+        self.target += self.rotation() * Vec3::new(-mouse_delta.x * k, mouse_delta.y * k, 0.0);
+    }
+
+    /// Move closer for positive scroll steps
+    pub fn zoom(&mut self, scroll: f32) {
+        self.distance = (self.distance * (1.0 - scroll * self.zoom_speed)).max(0.01);
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(self.pitch, self.yaw, 0.0)
+    }
+
+    pub fn eye_position(&self) -> Vec3 {
+        self.target + self.rotation() * Vec3::new(0.0, 0.0, self.distance)
+    }
+
+    pub fn update(&mut self, ctx: &Context, cam: &mut Camera, _dt: f64) {
+        self.apply_config(&ctx.config);
+        let delta = ctx.mouse_delta();
+        if ctx.is_mouse_down(glfw::MouseButtonLeft) {
+            self.orbit(delta);
+        }
+        if ctx.is_mouse_down(glfw::MouseButtonMiddle) {
+            self.pan(delta);
+        }
+
+        let scroll = ctx.scroll_delta().y;
+        if scroll != 0.0 {
+            self.zoom(scroll);
+        }
+
+        cam.set_position(self.eye_position());
+        cam.set_rotation(self.rotation());
+    }
+}
+
+/// 2D camera control for orthographic cameras. Right or middle mouse drag
+/// pans the view, the scroll wheel zooms around the cursor and the
+/// movement keys scroll the view. The speeds and key bindings are read from
+/// the config on each update, changes apply right away.
+pub struct PanZoomController {
+    pub move_speed: f32,
+    pub zoom_speed: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    pub bindings: CameraBindings,
+}
+
+impl PanZoomController {
+    pub fn new(conf: &Config) -> PanZoomController {
+        let mut controller = PanZoomController {
+            move_speed: 0.0,
+            zoom_speed: 0.0,
+            min_zoom: 0.05,
+            max_zoom: 50.0,
+            bindings: CameraBindings::default(),
+        };
+        controller.apply_config(conf);
+        controller
+    }
+
+    /// Read the speeds and the bindings that changed from the config
+    pub fn apply_config(&mut self, conf: &Config) {
+        self.bindings.apply_config(conf);
+        self.move_speed = conf.get_or("camera.move_speed", DEFAULT_MOVE_SPEED) as f32;
+        self.zoom_speed = conf.get_or("camera.zoom_speed", DEFAULT_ZOOM_SPEED) as f32;
+    }
+
+    /// Camera position and zoom after scrolling with the cursor at the given
+    /// screen position. The world point under the cursor stays in place
+    pub fn zoom_at(&self, pos: Vec2, zoom: f32, cursor: Vec2, scroll: f32) -> (Vec2, f32) {
+        let new_zoom = (zoom * (1.0 + scroll * self.zoom_speed)).max(self.min_zoom).min(self.max_zoom);
+        let world = pos + cursor / zoom;
+        (world - cursor / new_zoom, new_zoom)
+    }
+
+    pub fn update(&mut self, ctx: &Context, cam: &mut Camera, dt: f64) {
+        let (zoom, near, far) = match cam.projection() {
+            Projection::Orthographic { zoom, near, far } => (zoom, near, far),
+            Projection::Perspective { .. } => return,
+        };
+        self.apply_config(&ctx.config);
+
+        let mut pos = cam.position();
+        if ctx.is_mouse_down(glfw::MouseButtonRight) || ctx.is_mouse_down(glfw::MouseButtonMiddle) {
+            let delta = ctx.mouse_delta() / zoom;
+            pos.x -= delta.x;
+            pos.y -= delta.y;
+        }
+
+        // screen y goes down, as does the 2D world y
+        let dir = self.bindings.direction(|key| ctx.is_key_down(key));
+        let step = self.move_speed * dt as f32 / zoom;
+        pos.x += dir.x * step;
+        pos.y += dir.z * step;
+
+        let scroll = ctx.scroll_delta().y;
+        let mut new_zoom = zoom;
+        if scroll != 0.0 {
+            let (new_pos, z) = self.zoom_at(Vec2::new(pos.x, pos.y), zoom, ctx.mouse_position(), scroll);
+            pos.x = new_pos.x;
+            pos.y = new_pos.y;
+            new_zoom = z;
+        }
+
+        cam.set_position(pos);
+        if new_zoom != zoom {
+            cam.set_projection(Projection::Orthographic { zoom: new_zoom, near: near, far: far });
+        }
+    }
+}
@@ -1,8 +1,12 @@
 extern crate gl;
+extern crate rustc_serialize;
 
 use std::mem;
 use std::ptr;
 use self::gl::types::*;
+use self::rustc_serialize::json;
+use system::asset::{Asset, AssetError, AsyncAsset};
+use system::filesystem;
 
 pub struct Vao {
     id: GLuint,
@@ -127,6 +131,8 @@ impl MeshAttrib {
 }
 
 pub struct Mesh {
+    /// The mesh file, for meshes loaded from one
+    pub source_file: Option<String>,
     vao: Vao,
     vbos: [Option<Vbo>; 3], // 0: position, 1: texcoord, 2: color
     ibo: Vbo,
@@ -176,6 +182,7 @@ impl Mesh {
         let vbo_col = Mesh::make_attrib_vbo(colors, MeshAttrib::Color);
 
         let mesh = Mesh { 
+            source_file: None,
             vao: vao, 
             vbos: [vbo_pos, vbo_tex, vbo_col], 
             ibo: ibo,
@@ -206,4 +213,85 @@ impl Mesh {
         self.vao.bind();
         self.vao.draw(self.index_count);
     }
-}
\ No newline at end of file
+}
+
+/// Numbers of a mesh file array, checked against the vertex count
+fn mesh_array<T, F>(data: &json::Json, path: &str, key: &str, count: Option<usize>, convert: F)
+                    -> Result<Option<Vec<T>>, AssetError> where F: Fn(&json::Json) -> Option<T> {
+    let array = match data.find(key) {
+        Some(&json::Json::Array(ref array)) => array,
+        Some(_) => return Err(AssetError::invalid(path, &format!("{} should be an array", key))),
+        None => return Ok(None),
+    };
+    let values: Option<Vec<T>> = array.iter().map(convert).collect();
+    let values = values.ok_or_else(|| AssetError::invalid(path, &format!("{} has invalid numbers", key)))?;
+    if let Some(count) = count {
+        if values.len() != count {
+            return Err(AssetError::invalid(path, &format!("{} should have {} values, found {}", key, count, values.len())));
+        }
+    }
+    Ok(Some(values))
+}
+
+/// Content of a mesh file : a JSON object with the "positions" (3 floats per
+/// vertex) and "indices" of the triangles, and optional "texcoords" (2
+/// floats per vertex) and "colors" (4 floats per vertex)
+pub struct MeshData {
+    pub positions: Vec<f32>,
+    pub indices: Vec<u32>,
+    pub texcoords: Option<Vec<f32>>,
+    pub colors: Option<Vec<f32>>,
+    pub source_file: Option<String>,
+}
+
+impl MeshData {
+    pub fn parse(path: &str, src: &str) -> Result<MeshData, AssetError> {
+        let data = json::Json::from_str(src).map_err(|err| AssetError::invalid(path, &err.to_string()))?;
+        let float = |v: &json::Json| v.as_f64().map(|f| f as f32);
+
+        let positions = mesh_array(&data, path, "positions", None, &float)?
+            .ok_or_else(|| AssetError::invalid(path, "missing positions"))?;
+        if positions.len() % 3 != 0 {
+            return Err(AssetError::invalid(path, "positions should have 3 values per vertex"));
+        }
+        let vcount = positions.len() / 3;
+        let indices = mesh_array(&data, path, "indices", None, |v| v.as_u64().map(|i| i as u32))?
+            .ok_or_else(|| AssetError::invalid(path, "missing indices"))?;
+        if indices.iter().any(|&i| i as usize >= vcount) {
+            return Err(AssetError::invalid(path, "index out of range"));
+        }
+
+        Ok(MeshData {
+            positions: positions,
+            indices: indices,
+            texcoords: mesh_array(&data, path, "texcoords", Some(vcount * 2), &float)?,
+            colors: mesh_array(&data, path, "colors", Some(vcount * 4), &float)?,
+            source_file: Some(path.to_string()),
+        })
+    }
+}
+
+impl Asset for Mesh {
+    fn load(path: &str) -> Result<Mesh, AssetError> {
+        Mesh::upload(Mesh::decode(path)?)
+    }
+
+    fn source_files(&self) -> Vec<String> {
+        self.source_file.iter().cloned().collect()
+    }
+}
+
+impl AsyncAsset for Mesh {
+    type Data = MeshData;
+
+    fn decode(path: &str) -> Result<MeshData, AssetError> {
+        MeshData::parse(path, &filesystem::read_to_string(path)?)
+    }
+
+    fn upload(data: MeshData) -> Result<Mesh, AssetError> {
+        let mut mesh = Mesh::new(&data.positions, &data.indices,
+                                 data.texcoords.as_ref().map(|t| &t[..]), data.colors.as_ref().map(|c| &c[..]));
+        mesh.source_file = data.source_file;
+        Ok(mesh)
+    }
+}
@@ -1,4 +1,8 @@
 pub mod shader;
 pub mod context;
 pub mod mesh;
-pub mod texture;
\ No newline at end of file
+pub mod texture;
+pub mod camera;
+pub mod controller;
+pub mod text;
+pub mod console_overlay;
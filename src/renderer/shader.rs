@@ -1,9 +1,15 @@
 extern crate gl;
 
+use system::asset::{Asset, AssetError, AsyncAsset};
 use system::filesystem;
+use system::vfs;
+use math::vec2::*;
 use math::vec3::*;
 use math::vec4::*;
+use math::mat2::*;
+use math::mat3::*;
 use math::mat4::*;
+use math::color::Color;
 
 use self::gl::types::*;
 use std::ffi::CString;
@@ -11,6 +17,7 @@ use std::ptr;
 use std::str;
 use std::collections::HashMap;
 use std::mem;
+use std::path::Path;
 
 pub enum ShaderType{
     VERTEX,
@@ -30,6 +37,18 @@ impl ShaderType {
             &ShaderType::TESSCRTL => gl::TESS_CONTROL_SHADER,
         }
     }
+
+    /// Type of a shader file from its extension : .vs, .frag, .gs, .tcs, .tes
+    pub fn from_path(path: &str) -> Option<ShaderType> {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("vs") | Some("vert") => Some(ShaderType::VERTEX),
+            Some("frag") | Some("fs") => Some(ShaderType::FRAGMENT),
+            Some("gs") | Some("geom") => Some(ShaderType::GEOMETRY),
+            Some("tcs") => Some(ShaderType::TESSCRTL),
+            Some("tes") => Some(ShaderType::TESSEVAL),
+            _ => None
+        }
+    }
 }
 
 /// Representation of one shader (vertex shader, fragment shader, etc.)
@@ -48,13 +67,126 @@ impl Drop for Shader {
 
 impl Shader{
     pub fn new(shader_type : ShaderType, sourceFilePath : String) -> Shader {
-        let src = filesystem::read_file(&sourceFilePath);
-        Shader {
-            shader_id : compile_shader(&src, shader_type.to_gl_type()), 
+        match Shader::load(shader_type, &sourceFilePath) {
+            Ok(shader) => shader,
+            Err(msg) => panic!("Error loading shader : {}.", msg)
+        }
+    }
+
+    pub fn load(shader_type: ShaderType, path: &str) -> Result<Shader, AssetError> {
+        let src = filesystem::read_to_string(path)?;
+        Shader::from_source(shader_type, path, src)
+    }
+
+    /// Compile errors come with the compiler log
+    pub fn from_source(shader_type: ShaderType, path: &str, src: String) -> Result<Shader, AssetError> {
+        let shader_id = compile_shader(&src, shader_type.to_gl_type()).map_err(|log| AssetError::invalid(path, &log))?;
+        Ok(Shader {
+            shader_id : shader_id, 
             shader_type : shader_type,
-            source_file : sourceFilePath,
+            source_file : path.to_string(),
             source : src 
+        })
+    }
+}
+
+/// Source of a shader file, read on a loading thread
+pub struct ShaderSource {
+    pub shader_type: ShaderType,
+    pub path: String,
+    pub source: String,
+}
+
+impl ShaderSource {
+    /// The type comes from the file extension
+    pub fn read(path: &str) -> Result<ShaderSource, AssetError> {
+        let ty = ShaderType::from_path(path).ok_or_else(|| AssetError::invalid(path, "unknown shader extension"))?;
+        Ok(ShaderSource {
+            shader_type: ty,
+            path: path.to_string(),
+            source: filesystem::read_to_string(path)?,
+        })
+    }
+
+    pub fn compile(self) -> Result<Shader, AssetError> {
+        Shader::from_source(self.shader_type, &self.path, self.source)
+    }
+}
+
+impl Asset for Shader {
+    fn load(path: &str) -> Result<Shader, AssetError> {
+        ShaderSource::read(path)?.compile()
+    }
+
+    fn source_files(&self) -> Vec<String> {
+        vec![self.source_file.clone()]
+    }
+}
+
+impl AsyncAsset for Shader {
+    type Data = ShaderSource;
+
+    fn decode(path: &str) -> Result<ShaderSource, AssetError> {
+        ShaderSource::read(path)
+    }
+
+    fn upload(data: ShaderSource) -> Result<Shader, AssetError> {
+        data.compile()
+    }
+}
+
+/// Shader files of a program : "data/shaders/test" is data/shaders/test.vs
+/// and test.frag, plus test.gs if there is one
+static PROGRAM_STAGES: [(&'static str, bool); 3] = [("vs", true), ("gs", false), ("frag", true)];
+
+/// A program from its shader files, with its active uniforms registered
+impl Asset for Program {
+    fn load(path: &str) -> Result<Program, AssetError> {
+        Program::upload(Program::decode(path)?)
+    }
+
+    fn source_files(&self) -> Vec<String> {
+        self.source_files.clone()
+    }
+
+    /// Keep the uniforms registered and set on the previous version
+    fn reloaded(&mut self, previous: &Program) {
+        for name in previous.uniform_loc.keys() {
+            if !self.uniform_loc.contains_key(name) {
+                self.register_uniform(name);
+            }
+        }
+        self.copy_uniforms_from(previous);
+    }
+}
+
+impl AsyncAsset for Program {
+    type Data = Vec<ShaderSource>;
+
+    fn decode(path: &str) -> Result<Vec<ShaderSource>, AssetError> {
+        let mut sources = Vec::new();
+        for &(ext, required) in PROGRAM_STAGES.iter() {
+            let shader_path = format!("{}.{}", path, ext);
+            if required || vfs::exists(&shader_path) {
+                sources.push(ShaderSource::read(&shader_path)?);
+            }
+        }
+        Ok(sources)
+    }
+
+    fn upload(sources: Vec<ShaderSource>) -> Result<Program, AssetError> {
+        let mut shaders = Vec::new();
+        for source in sources.into_iter() {
+            shaders.push(source.compile()?);
         }
+        let mut program = Program::new();
+        for shader in shaders.iter() {
+            program.attach(shader);
+            program.source_files.push(shader.source_file.clone());
+        }
+        program.try_link().map_err(|log| AssetError::invalid(&program.source_files.join(", "), &log))?;
+        program.register_active_uniforms();
+        Ok(program)
     }
 }
 
@@ -62,6 +194,8 @@ impl Shader{
 pub struct Program{
     pub program_id : GLuint,
     uniform_loc : HashMap<String, GLint>,
+    /// Shader files of programs loaded as assets
+    source_files : Vec<String>,
 }
 
 impl Drop for Program {
@@ -77,6 +211,7 @@ impl Program {
             Program {
                 program_id : gl::CreateProgram(),
                 uniform_loc : HashMap::new(),              
+                source_files : Vec::new(),
             }
         }
     }
@@ -94,6 +229,14 @@ impl Program {
         }
     }
 
+    /// Register every uniform the linked program uses. The ones the compiler
+    /// optimized out still need register_uniform before being set
+    pub fn register_active_uniforms(&mut self) {
+        for (name, _) in self.active_uniforms() {
+            self.register_uniform(&name);
+        }
+    }
+
     fn get_uniform(&self, name: &str) -> GLint {
         match self.uniform_loc.get(name) {
             Some(loc) => return *loc,
@@ -108,10 +251,24 @@ impl Program {
         }
     }
 
-    pub fn set_uniform_2fv(&self, name: &str, vec: (f32, f32)) {
+    pub fn set_uniform_matrix3fv(&self, name : &str, mat3 : &Mat3){
+        let loc = self.get_uniform(name);
+        unsafe {
+            gl::UniformMatrix3fv(loc, 1, gl::FALSE, mat3.as_ptr());
+        }
+    }
+
+    pub fn set_uniform_matrix2fv(&self, name : &str, mat2 : &Mat2){
+        let loc = self.get_uniform(name);
+        unsafe {
+            gl::UniformMatrix2fv(loc, 1, gl::FALSE, mat2.as_ptr());
+        }
+    }
+
+    pub fn set_uniform_2fv(&self, name: &str, vec: &Vec2) {
         let loc = self.get_uniform(name);
         unsafe {
-            gl::Uniform2fv(loc, 1, &vec.0 as *const f32);
+            gl::Uniform2fv(loc, 1, vec.as_ptr());
         }
     }
 
@@ -129,6 +286,13 @@ impl Program {
         }
     }
 
+    pub fn set_uniform_color(&self, name: &str, color: &Color) {
+        let loc = self.get_uniform(name);
+        unsafe {
+            gl::Uniform4fv(loc, 1, color.as_ptr());
+        }
+    }
+
     pub fn set_uniform_1i(&self, name : &str, int : GLint){
         let loc = self.get_uniform(name);
         unsafe {
@@ -149,6 +313,13 @@ impl Program {
     }
 
     pub fn link(&self){
+        if let Err(log) = self.try_link() {
+            panic!("{}", log);
+        }
+    }
+
+    /// Link, returning the linker log on errors
+    pub fn try_link(&self) -> Result<(), String> {
         unsafe{
             gl::LinkProgram(self.program_id);
             let mut status = gl::FALSE as GLint;
@@ -156,25 +327,85 @@ impl Program {
             if status != (gl::TRUE as GLint) {
                 let mut len : GLint = 0;
                 gl::GetProgramiv(self.program_id, gl::INFO_LOG_LENGTH, &mut len);
-                let mut buf = Vec::with_capacity(len as usize);
-                buf.set_len((len as usize - 1));
+                let mut buf = vec![0u8; len.max(1) as usize];
                 gl::GetProgramInfoLog(self.program_id, len, ptr::null_mut(), buf.as_mut_ptr() as *mut GLchar);
-                panic!("{}", str::from_utf8(&buf).ok().expect("ProgramInfoLog not valid utf8"));             
+                buf.pop(); // no trailing char
+                return Err(String::from_utf8_lossy(&buf).into_owned());
             }
 
             self.bind();
             gl::BindFragDataLocation(self.program_id, 0, CString::new("out_color").unwrap().as_ptr());
         }
+        Ok(())
+    }
+
+    /// Active uniforms, with their type
+    fn active_uniforms(&self) -> Vec<(String, GLenum)> {
+        let mut uniforms = Vec::new();
+        unsafe {
+            let mut count: GLint = 0;
+            gl::GetProgramiv(self.program_id, gl::ACTIVE_UNIFORMS, &mut count);
+            let mut buf = [0u8; 256];
+            for i in 0..count as GLuint {
+                let (mut len, mut size, mut ty) = (0, 0, 0);
+                gl::GetActiveUniform(self.program_id, i, buf.len() as GLsizei, &mut len, &mut size, &mut ty,
+                                     buf.as_mut_ptr() as *mut GLchar);
+                if let Ok(name) = str::from_utf8(&buf[..len as usize]) {
+                    // arrays are named "lights[0]"
+                    uniforms.push((name.trim_end_matches("[0]").to_string(), ty));
+                }
+            }
+        }
+        uniforms
+    }
+
+    /// Give the uniforms the values they have in other, for the ones with
+    /// the same name and type. Arrays only get their first element
+    pub fn copy_uniforms_from(&self, other: &Program) {
+        let types: HashMap<String, GLenum> = self.active_uniforms().into_iter().collect();
+        self.bind();
+        for (name, ty) in other.active_uniforms() {
+            if types.get(&name) != Some(&ty) {
+                continue;
+            }
+            let c_name = CString::new(name).unwrap();
+            unsafe {
+                let from = gl::GetUniformLocation(other.program_id, c_name.as_ptr());
+                let to = gl::GetUniformLocation(self.program_id, c_name.as_ptr());
+                let mut f = [0f32; 16];
+                let mut i = [0i32; 4];
+                match ty {
+                    gl::FLOAT | gl::FLOAT_VEC2 | gl::FLOAT_VEC3 | gl::FLOAT_VEC4 |
+                    gl::FLOAT_MAT2 | gl::FLOAT_MAT3 | gl::FLOAT_MAT4 => {
+                        gl::GetUniformfv(other.program_id, from, f.as_mut_ptr());
+                    }
+                    _ => gl::GetUniformiv(other.program_id, from, i.as_mut_ptr()),
+                }
+                match ty {
+                    gl::FLOAT => gl::Uniform1fv(to, 1, f.as_ptr()),
+                    gl::FLOAT_VEC2 => gl::Uniform2fv(to, 1, f.as_ptr()),
+                    gl::FLOAT_VEC3 => gl::Uniform3fv(to, 1, f.as_ptr()),
+                    gl::FLOAT_VEC4 => gl::Uniform4fv(to, 1, f.as_ptr()),
+                    gl::FLOAT_MAT2 => gl::UniformMatrix2fv(to, 1, gl::FALSE, f.as_ptr()),
+                    gl::FLOAT_MAT3 => gl::UniformMatrix3fv(to, 1, gl::FALSE, f.as_ptr()),
+                    gl::FLOAT_MAT4 => gl::UniformMatrix4fv(to, 1, gl::FALSE, f.as_ptr()),
+                    gl::INT_VEC2 | gl::BOOL_VEC2 => gl::Uniform2iv(to, 1, i.as_ptr()),
+                    gl::INT_VEC3 | gl::BOOL_VEC3 => gl::Uniform3iv(to, 1, i.as_ptr()),
+                    gl::INT_VEC4 | gl::BOOL_VEC4 => gl::Uniform4iv(to, 1, i.as_ptr()),
+                    // ints, bools and samplers
+                    _ => gl::Uniform1iv(to, 1, i.as_ptr()),
+                }
+            }
+        }
     }
 }
 
 
-fn compile_shader(src: &str, ty: GLenum) -> GLuint {
-    let shader;
+/// The shader id, or the compiler log
+fn compile_shader(src: &str, ty: GLenum) -> Result<GLuint, String> {
+    let c_str = CString::new(src.as_bytes()).map_err(|_| "the source contains a nul character".to_string())?;
     unsafe {
-        shader = gl::CreateShader(ty);
-
-        let c_str = CString::new(src.as_bytes()).unwrap();
+        let shader = gl::CreateShader(ty);
         gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
         gl::CompileShader(shader);
 
@@ -184,11 +415,12 @@ fn compile_shader(src: &str, ty: GLenum) -> GLuint {
         if status != (gl::TRUE as GLint) {
             let mut len = 0;
             gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
-            let mut buf = Vec::with_capacity(len as usize);
-            buf.set_len((len as usize) - 1); // no trailing char
+            let mut buf = vec![0u8; len.max(1) as usize];
             gl::GetShaderInfoLog(shader, len, ptr::null_mut(), buf.as_mut_ptr() as *mut GLchar);
-            panic!("{}", str::from_utf8(&buf).ok().expect("ShaderInfoLog not valid utf8"));
+            buf.pop(); // no trailing char
+            gl::DeleteShader(shader);
+            return Err(String::from_utf8_lossy(&buf).into_owned());
         }
+        Ok(shader)
     }
-    shader
-}
\ No newline at end of file
+}
@@ -0,0 +1,108 @@
+use renderer::mesh::Mesh;
+use renderer::texture::{Texture, TextureFilter};
+use math::vec2::Vec2;
+use math::color::Color;
+
+/// Glyph of the atlas filled with white, for solid rectangles
+pub const SOLID_GLYPH: char = '\x7f';
+
+const FIRST_GLYPH: u32 = 32;
+const LAST_GLYPH: u32 = 127;
+
+/// Monospace bitmap font : an atlas of the ASCII glyphs from 32 to 127,
+/// in rows of 16, white with the coverage in alpha
+pub struct Font {
+    pub texture: Texture,
+    pub glyph_size: Vec2,
+    columns: u32,
+}
+
+impl Font {
+    pub fn from_image(path: &str, glyph_width: u32, glyph_height: u32) -> Font {
+        let texture = Texture::from_image(path);
+        texture.set_filter(TextureFilter::Nearest);
+        let columns = texture.size.0 / glyph_width;
+        Font {
+            texture: texture,
+            glyph_size: Vec2::new(glyph_width as f32, glyph_height as f32),
+            columns: columns,
+        }
+    }
+
+    /// Texcoords of a glyph, (left, top, right, bottom). Characters outside
+    /// of the atlas are drawn as '?'
+    fn glyph_rect(&self, c: char) -> (f32, f32, f32, f32) {
+        let code = c as u32;
+        let index = if code >= FIRST_GLYPH && code <= LAST_GLYPH { code - FIRST_GLYPH } else { '?' as u32 - FIRST_GLYPH };
+        let (tw, th) = (self.texture.size.0 as f32, self.texture.size.1 as f32);
+        let x = (index % self.columns) as f32 * self.glyph_size.x;
+        let y = (index / self.columns) as f32 * self.glyph_size.y;
+        (x / tw, y / th, (x + self.glyph_size.x) / tw, (y + self.glyph_size.y) / th)
+    }
+}
+
+/// Textured quads for text and rectangles, in pixels from the top-left,
+/// turned into a single mesh
+pub struct TextBatch {
+    positions: Vec<f32>,
+    texcoords: Vec<f32>,
+    colors: Vec<f32>,
+    indices: Vec<u32>,
+}
+
+impl TextBatch {
+    pub fn new() -> TextBatch {
+        TextBatch {
+            positions: Vec::new(),
+            texcoords: Vec::new(),
+            colors: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.texcoords.clear();
+        self.colors.clear();
+        self.indices.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn add_quad(&mut self, pos: Vec2, size: Vec2, uv: (f32, f32, f32, f32), color: &Color) {
+        let first = (self.positions.len() / 3) as u32;
+        let (l, t, r, b) = (pos.x, pos.y, pos.x + size.x, pos.y + size.y);
+        self.positions.extend_from_slice(&[l, t, 0.0, l, b, 0.0, r, b, 0.0, r, t, 0.0]);
+        self.texcoords.extend_from_slice(&[uv.0, uv.1, uv.0, uv.3, uv.2, uv.3, uv.2, uv.1]);
+        for _ in 0..4 {
+            self.colors.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+        self.indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    pub fn add_rect(&mut self, font: &Font, pos: Vec2, size: Vec2, color: &Color) {
+        let uv = font.glyph_rect(SOLID_GLYPH);
+        // sample the middle of the glyph, away from its neighbours
+        let (cu, cv) = ((uv.0 + uv.2) * 0.5, (uv.1 + uv.3) * 0.5);
+        self.add_quad(pos, size, (cu, cv, cu, cv), color);
+    }
+
+    /// One line of text, returns the position after its last character
+    pub fn add_text(&mut self, font: &Font, text: &str, pos: Vec2, color: &Color) -> Vec2 {
+        let mut pen = pos;
+        for c in text.chars() {
+            if c != ' ' {
+                let uv = font.glyph_rect(c);
+                self.add_quad(pen, font.glyph_size, uv, color);
+            }
+            pen.x += font.glyph_size.x;
+        }
+        pen
+    }
+
+    pub fn build(&self) -> Mesh {
+        Mesh::new(&self.positions, &self.indices, Some(&self.texcoords), Some(&self.colors))
+    }
+}
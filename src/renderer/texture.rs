@@ -3,7 +3,9 @@ extern crate image;
 
 use self::gl::types::*;
 use self::image::GenericImage;
+use system::asset::{Asset, AssetError, AsyncAsset};
 use system::filesystem;
+use std::cell::Cell;
 use std::path::Path;
 use std::os::raw::c_void;
 
@@ -11,6 +13,7 @@ static VALID_IMG_EXT: [&'static str; 5] = [
     "png", "jpeg", "jpg", "gif", "bmp"
 ];
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum TextureFmt {
     R8U,
     RG8U,
@@ -80,23 +83,40 @@ fn image_to_radar_fmt(ty: image::ColorType) -> TextureFmt {
     }
 }
 
-fn get_pixel_ptr(img: &image::DynamicImage) -> *const u8 {
-    use self::image::ColorType::*;
-    let ty = img.color();
+/// Sampling of a texture, from the "renderer.texture_filter" config value
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureFilter {
+    Nearest,
+    Linear,
+    Trilinear
+}
 
-    match ty {
-        Gray(_) => (*img.as_luma8().unwrap()).as_ptr(),
-        RGB(_) => (*img.as_rgb8().unwrap()).as_ptr(),
-        GrayA(_) => (*img.as_luma_alpha8().unwrap()).as_ptr(),
-        RGBA(_) => (*img.as_rgba8().unwrap()).as_ptr(),
-        _ => {panic!("Unsupported pixel format.");}
+impl TextureFilter {
+    pub fn from_name(name: &str) -> Option<TextureFilter> {
+        match name {
+            "nearest" => Some(TextureFilter::Nearest),
+            "linear" => Some(TextureFilter::Linear),
+            "trilinear" => Some(TextureFilter::Trilinear),
+            _ => None
+        }
+    }
+
+    fn gl_filters(&self) -> (GLenum, GLenum) {
+        match self {
+            &TextureFilter::Nearest => (gl::NEAREST, gl::NEAREST),
+            &TextureFilter::Linear => (gl::LINEAR, gl::LINEAR),
+            &TextureFilter::Trilinear => (gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR),
+        }
     }
 }
 
 pub struct Texture {
     pub id: GLuint,
     pub size: (u32, u32),
-    pub fmt: TextureFmt
+    pub fmt: TextureFmt,
+    /// The image file, for textures loaded from one
+    pub source_file: Option<String>,
+    filter: Cell<TextureFilter>
 }
 
 impl Drop for Texture {
@@ -105,29 +125,94 @@ impl Drop for Texture {
     }
 }
 
-impl Texture {
-    pub fn from_image(path_str: &str) -> Texture {
-        let mut id = 0u32;
+/// Pixels of an image file, decoded and ready to upload
+pub struct TextureData {
+    pub size: (u32, u32),
+    pub fmt: TextureFmt,
+    pub pixels: Vec<u8>,
+    pub source_file: Option<String>,
+}
 
+impl TextureData {
+    pub fn decode(path_str: &str) -> Result<TextureData, AssetError> {
         let path = Path::new(path_str);
 
         //1. check extension
         if !filesystem::check_extension(path, &VALID_IMG_EXT) {
-            panic!("Invalid image file {}.", path.display());
+            return Err(AssetError::invalid(path_str, "not an image file"));
         }
 
-        //2. load image
-        let img = image::open(&path).unwrap();
+        //2. load image, through the vfs so archives and mods are searched too
+        let data = filesystem::read_bytes(path_str)?;
+        let img = image::load_from_memory(&data).map_err(|err| AssetError::invalid(path_str, &err.to_string()))?;
 
-        let fmt = image_to_gl_fmt(img.color());
-        if fmt == gl::NONE {
-            panic!("Unsupported texture format for {}.", path.display());
+        if image_to_gl_fmt(img.color()) == gl::NONE {
+            return Err(AssetError::invalid(path_str, "unsupported texture format"));
         }
 
-        let dims = img.dimensions();
-        let w = dims.0 as i32;
-        let h = dims.1 as i32;
-        let bytes = get_pixel_ptr(&img);
+        Ok(TextureData {
+            size: img.dimensions(),
+            fmt: image_to_radar_fmt(img.color()),
+            pixels: img.raw_pixels(),
+            source_file: Some(path_str.to_string()),
+        })
+    }
+}
+
+impl Asset for Texture {
+    fn load(path: &str) -> Result<Texture, AssetError> {
+        Texture::load(path)
+    }
+
+    fn source_files(&self) -> Vec<String> {
+        self.source_file.iter().cloned().collect()
+    }
+
+    fn reloaded(&mut self, previous: &Texture) {
+        self.set_filter(previous.filter.get());
+    }
+}
+
+impl AsyncAsset for Texture {
+    type Data = TextureData;
+
+    fn decode(path: &str) -> Result<TextureData, AssetError> {
+        TextureData::decode(path)
+    }
+
+    fn upload(data: TextureData) -> Result<Texture, AssetError> {
+        Ok(Texture::from_data(&data))
+    }
+
+    /// Plain grey until the image is uploaded
+    fn placeholder() -> Option<Texture> {
+        let grey = TextureData { size: (1, 1), fmt: TextureFmt::RGBA8U, pixels: vec![128, 128, 128, 255],
+                                  source_file: None };
+        Some(Texture::from_data(&grey))
+    }
+}
+
+impl Texture {
+    pub fn from_image(path_str: &str) -> Texture {
+        match Texture::load(path_str) {
+            Ok(texture) => texture,
+            Err(msg) => panic!("Error loading texture : {}.", msg)
+        }
+    }
+
+    pub fn load(path_str: &str) -> Result<Texture, AssetError> {
+        Ok(Texture::from_data(&TextureData::decode(path_str)?))
+    }
+
+    /// Upload decoded pixels, with mipmaps. Panics if there are fewer
+    /// pixels than the size and format need, GL would read past them
+    pub fn from_data(data: &TextureData) -> Texture {
+        let mut id = 0u32;
+        let fmt = data.fmt.gl_format();
+        let (w, h) = (data.size.0 as i32, data.size.1 as i32);
+        let needed = data.size.0 as usize * data.size.1 as usize * data.fmt.gl_bpp();
+        assert!(data.pixels.len() >= needed, "texture data of {}x{} needs {} bytes, got {}",
+                w, h, needed, data.pixels.len());
 
         //3. convert to gl texture
         unsafe {
@@ -144,7 +229,7 @@ impl Texture {
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
             // gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_ANISOTROPY_EXT, gl::REPEAT);
             // ADRIEN TODO - anisotropic level from config file : needs global access to config file somehow
-            gl::TexImage2D(gl::TEXTURE_2D, 0, fmt as i32, w, h, 0, fmt, gl::UNSIGNED_BYTE, bytes as *const c_void);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, fmt as i32, w, h, 0, fmt, data.fmt.gl_type(), data.pixels.as_ptr() as *const c_void);
             gl::GenerateMipmap(gl::TEXTURE_2D);
 
             gl::PixelStorei(gl::UNPACK_ALIGNMENT, palign);
@@ -152,8 +237,10 @@ impl Texture {
 
         Texture { 
             id: id,
-            size: (dims.0, dims.1),
-            fmt: image_to_radar_fmt(img.color())
+            size: data.size,
+            fmt: data.fmt,
+            source_file: data.source_file.clone(),
+            filter: Cell::new(TextureFilter::Trilinear)
         }
     }
 
@@ -188,11 +275,25 @@ impl Texture {
         Texture { 
             id: id,
             size: size,
-            fmt: fmt
+            fmt: fmt,
+            source_file: None,
+            filter: Cell::new(TextureFilter::Linear)
         }
     }
 
     pub fn bind(&self) {
         unsafe { gl::BindTexture(gl::TEXTURE_2D, self.id); }
     }
+
+    /// Change the sampling filter. Trilinear needs mipmaps, textures created
+    /// from_image have them
+    pub fn set_filter(&self, filter: TextureFilter) {
+        let (min, mag) = filter.gl_filters();
+        self.filter.set(filter);
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, min as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, mag as i32);
+        }
+    }
 }
\ No newline at end of file
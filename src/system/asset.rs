@@ -0,0 +1,548 @@
+//! Assets loaded by path through the vfs, shared with handles. Loading the
+//! same path twice gives the same asset, which is freed when its last
+//! handle is dropped.
+//!
+//! load_async reads and decodes files on worker threads. What needs the GL
+//! context is done on the main thread by update, within a time budget.
+//!
+//! The source files of loaded assets are watched : when one changes on disk
+//! the asset is loaded again and swapped in, for every handle.
+
+use system::config::{Config, Layer, RELOAD_INTERVAL_MS};
+use system::filesystem::{self, file_stamp, FileStamp, FsError};
+use system::vfs;
+use system::worker::WorkerPool;
+use std::any::{Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::{Rc, Weak};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Worker threads of an AssetManager, unless set with set_worker_count
+pub const DEFAULT_WORKERS: usize = 2;
+
+#[derive(Debug)]
+pub enum AssetError {
+    Fs(FsError),
+    /// The file was read but can't be used
+    Invalid { path: String, msg: String },
+}
+
+impl AssetError {
+    pub fn invalid(path: &str, msg: &str) -> AssetError {
+        AssetError::Invalid { path: path.to_string(), msg: msg.to_string() }
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AssetError::Fs(ref err) => write!(f, "{}", err),
+            AssetError::Invalid { ref path, ref msg } => write!(f, "invalid asset {}: {}", path, msg),
+        }
+    }
+}
+
+impl Error for AssetError {}
+
+impl From<FsError> for AssetError {
+    fn from(err: FsError) -> AssetError {
+        AssetError::Fs(err)
+    }
+}
+
+/// Something the AssetManager can load
+pub trait Asset: Sized + 'static {
+    fn load(path: &str) -> Result<Self, AssetError>;
+
+    /// Files the asset was loaded from, watched for hot-reload
+    fn source_files(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Called on the new version of a reloaded asset, to keep what was set
+    /// on the previous one
+    fn reloaded(&mut self, _previous: &Self) {}
+}
+
+/// An asset loaded in two steps : decode on a worker thread, then upload on
+/// the main thread, where the GL context is
+pub trait AsyncAsset: Asset {
+    type Data: Send + 'static;
+
+    fn decode(path: &str) -> Result<Self::Data, AssetError>;
+    fn upload(data: Self::Data) -> Result<Self, AssetError>;
+
+    /// What the handle holds until the asset is ready, if anything
+    fn placeholder() -> Option<Self> {
+        None
+    }
+}
+
+/// A config file without the layering of Config::load_layered
+impl Asset for Config {
+    fn load(path: &str) -> Result<Config, AssetError> {
+        Config::upload(Config::decode(path)?)
+    }
+}
+
+impl AsyncAsset for Config {
+    /// Path and content of the file
+    type Data = (String, String);
+
+    fn decode(path: &str) -> Result<(String, String), AssetError> {
+        Ok((path.to_string(), filesystem::read_to_string(path)?))
+    }
+
+    fn upload(data: (String, String)) -> Result<Config, AssetError> {
+        let mut conf = Config::empty();
+        conf.add_str(Layer::File, &data.1).map_err(|err| AssetError::invalid(&data.0, &err.to_string()))?;
+        Ok(conf)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AssetStatus {
+    Loading,
+    Ready,
+    /// The error message. The handle keeps its placeholder
+    Failed(String),
+}
+
+struct Entry<T> {
+    path: String,
+    asset: RefCell<Option<T>>,
+    status: RefCell<AssetStatus>,
+}
+
+/// Shared reference to an asset, cheap to clone
+pub struct Handle<T> {
+    entry: Rc<Entry<T>>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Handle<T> {
+        Handle { entry: self.entry.clone() }
+    }
+}
+
+impl<T> Handle<T> {
+    /// Handle on an asset that didn't come from the AssetManager
+    pub fn new(path: &str, asset: T) -> Handle<T> {
+        Handle::with_status(path, Some(asset), AssetStatus::Ready)
+    }
+
+    fn with_status(path: &str, asset: Option<T>, status: AssetStatus) -> Handle<T> {
+        Handle {
+            entry: Rc::new(Entry {
+                path: path.to_string(),
+                asset: RefCell::new(asset),
+                status: RefCell::new(status),
+            })
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.entry.path
+    }
+
+    pub fn status(&self) -> AssetStatus {
+        self.entry.status.borrow().clone()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        *self.entry.status.borrow() == AssetStatus::Ready
+    }
+
+    /// The asset, or its placeholder while loading. Panics if there is
+    /// neither, see try_get
+    pub fn get<'a>(&'a self) -> Ref<'a, T> {
+        match self.try_get() {
+            Some(asset) => asset,
+            None => panic!("Asset {} is not loaded : {:?}.", self.entry.path, self.status())
+        }
+    }
+
+    pub fn try_get<'a>(&'a self) -> Option<Ref<'a, T>> {
+        Ref::filter_map(self.entry.asset.borrow(), |asset| asset.as_ref()).ok()
+    }
+
+    pub fn get_mut<'a>(&'a self) -> RefMut<'a, T> {
+        let path = &self.entry.path;
+        RefMut::map(self.entry.asset.borrow_mut(), |asset| match *asset {
+            Some(ref mut asset) => asset,
+            None => panic!("Asset {} is not loaded.", path)
+        })
+    }
+
+    /// Swap in a new version of the asset, for every handle. Returns the old
+    /// one
+    pub fn replace(&self, asset: T) -> Option<T> {
+        *self.entry.status.borrow_mut() = AssetStatus::Ready;
+        self.entry.asset.replace(Some(asset))
+    }
+
+    fn fail(&self, msg: String) {
+        *self.entry.status.borrow_mut() = AssetStatus::Failed(msg);
+    }
+
+    /// Number of handles on the asset
+    pub fn ref_count(&self) -> usize {
+        Rc::strong_count(&self.entry)
+    }
+
+    pub fn same_asset(&self, other: &Handle<T>) -> bool {
+        Rc::ptr_eq(&self.entry, &other.entry)
+    }
+}
+
+/// A weak reference to an asset of any type
+trait Slot {
+    fn is_alive(&self) -> bool;
+    fn as_any(&self) -> &dyn Any;
+    fn source_files(&self) -> Vec<String>;
+    /// Load the asset again. The previous version is kept on errors
+    fn reload(&self) -> Result<(), AssetError>;
+}
+
+impl<T: Asset> Slot for Weak<Entry<T>> {
+    fn is_alive(&self) -> bool {
+        self.strong_count() > 0
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn source_files(&self) -> Vec<String> {
+        match self.upgrade() {
+            Some(entry) => entry.asset.borrow().as_ref().map_or(Vec::new(), |asset| asset.source_files()),
+            None => Vec::new(),
+        }
+    }
+
+    fn reload(&self) -> Result<(), AssetError> {
+        let handle = match self.upgrade() {
+            Some(entry) => Handle { entry: entry },
+            None => return Ok(()),
+        };
+        let mut asset = T::load(handle.path())?;
+        if let Some(previous) = handle.try_get() {
+            asset.reloaded(&previous);
+        }
+        handle.replace(asset);
+        Ok(())
+    }
+}
+
+/// A loaded asset and the stamps of its source files
+struct Watched {
+    path: String,
+    slot: Box<dyn Slot>,
+    files: Vec<(String, Option<FileStamp>)>,
+}
+
+impl Watched {
+    fn new(path: &str, slot: Box<dyn Slot>) -> Watched {
+        Watched { path: path.to_string(), slot: slot, files: Vec::new() }
+    }
+
+    /// Stamp of a virtual file, None if it isn't a loose file
+    fn stamp(path: &str) -> Option<FileStamp> {
+        vfs::real_path(path).and_then(file_stamp)
+    }
+
+    /// Start watching the source files of the asset as they are now. An
+    /// asset with none, like the placeholder of a failed load, is watched
+    /// by its path
+    fn update_stamps(&mut self) {
+        let mut files = self.slot.source_files();
+        if files.is_empty() {
+            files.push(self.path.clone());
+        }
+        self.files = files.into_iter()
+            .map(|file| { let stamp = Watched::stamp(&file); (file, stamp) })
+            .collect();
+    }
+
+    fn changed(&self) -> bool {
+        self.files.iter().any(|&(ref file, stamp)| Watched::stamp(file) != stamp)
+    }
+}
+
+/// An asset waiting for its decoded data
+trait PendingUpload {
+    /// Whether the upload was done, false if every handle was dropped
+    fn upload(&self, data: Box<dyn Any + Send>) -> bool;
+    fn fail(&self, msg: &str);
+}
+
+struct Pending<T: AsyncAsset> {
+    entry: Weak<Entry<T>>,
+}
+
+impl<T: AsyncAsset> PendingUpload for Pending<T> {
+    fn upload(&self, data: Box<dyn Any + Send>) -> bool {
+        let handle = match self.entry.upgrade() {
+            Some(entry) => Handle { entry: entry },
+            None => return false,
+        };
+        let data = *data.downcast::<Result<T::Data, AssetError>>().unwrap();
+        match data.and_then(T::upload) {
+            Ok(asset) => { handle.replace(asset); }
+            Err(err) => {
+                println!("Asset warning: error loading {}: {}", handle.path(), err);
+                handle.fail(err.to_string());
+            }
+        }
+        true
+    }
+
+    fn fail(&self, msg: &str) {
+        if let Some(entry) = self.entry.upgrade() {
+            Handle { entry: entry }.fail(msg.to_string());
+        }
+    }
+}
+
+/// Decoded data sent back by the workers, with the id of its upload
+type Decoded = (u64, Box<dyn Any + Send>);
+
+/// Counts a job as in flight until it is dropped, even by a panic
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn new(count: &Arc<AtomicUsize>) -> InFlight {
+        count.fetch_add(1, Ordering::SeqCst);
+        InFlight(count.clone())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Loads assets once per path. It only keeps weak references : the assets
+/// live as long as their handles
+pub struct AssetManager {
+    slots: HashMap<(TypeId, String), Watched>,
+    last_poll: Option<Instant>,
+
+    /// Started with the first load_async
+    workers: Option<WorkerPool>,
+    worker_count: usize,
+    decoded_sender: mpsc::Sender<Decoded>,
+    decoded: mpsc::Receiver<Decoded>,
+    /// Decoded, waiting for update
+    ready: VecDeque<Decoded>,
+    pending: HashMap<u64, ((TypeId, String), Box<dyn PendingUpload>)>,
+    next_id: u64,
+    /// Jobs that haven't sent their data yet
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl AssetManager {
+    pub fn new() -> AssetManager {
+        let (sender, receiver) = mpsc::channel();
+        AssetManager {
+            slots: HashMap::new(),
+            last_poll: None,
+            workers: None,
+            worker_count: DEFAULT_WORKERS,
+            decoded_sender: sender,
+            decoded: receiver,
+            ready: VecDeque::new(),
+            pending: HashMap::new(),
+            next_id: 0,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Number of loading threads, to set before the first load_async
+    pub fn set_worker_count(&mut self, count: usize) {
+        self.worker_count = count.max(1);
+    }
+
+    fn key<T: Asset>(path: &str) -> Result<(TypeId, String), AssetError> {
+        let path = vfs::normalize(path).ok_or_else(|| AssetError::Fs(FsError::NotFound(path.to_string())))?;
+        Ok((TypeId::of::<T>(), path))
+    }
+
+    /// The asset at path, loaded if no handle on it is alive. It can still
+    /// be loading if load_async was called first. Dropped assets are
+    /// forgotten when a new one is loaded
+    pub fn load<T: Asset>(&mut self, path: &str) -> Result<Handle<T>, AssetError> {
+        if let Some(handle) = self.get(path) {
+            return Ok(handle);
+        }
+        let key = AssetManager::key::<T>(path)?;
+        let handle = Handle::new(&key.1, T::load(&key.1)?);
+        self.collect_garbage();
+        let mut watched = Watched::new(&key.1, Box::new(Rc::downgrade(&handle.entry)));
+        watched.update_stamps();
+        self.slots.insert(key, watched);
+        Ok(handle)
+    }
+
+    /// Start loading the asset in the background. The handle holds the
+    /// placeholder until an update uploads it. Errors are reported by the
+    /// handle status
+    pub fn load_async<T: AsyncAsset>(&mut self, path: &str) -> Handle<T> {
+        if let Some(handle) = self.get(path) {
+            return handle;
+        }
+        let key = match AssetManager::key::<T>(path) {
+            Ok(key) => key,
+            Err(err) => {
+                let handle = Handle::with_status(path, T::placeholder(), AssetStatus::Loading);
+                handle.fail(err.to_string());
+                return handle;
+            }
+        };
+        let handle = Handle::with_status(&key.1, T::placeholder(), AssetStatus::Loading);
+        self.collect_garbage();
+        self.slots.insert(key.clone(), Watched::new(&key.1, Box::new(Rc::downgrade(&handle.entry))));
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.insert(id, (key, Box::new(Pending { entry: Rc::downgrade(&handle.entry) })));
+
+        if self.workers.is_none() {
+            self.workers = Some(WorkerPool::new("asset loader", self.worker_count));
+        }
+        let path = handle.path().to_string();
+        let sender = self.decoded_sender.clone();
+        let in_flight = InFlight::new(&self.in_flight);
+        self.workers.as_ref().unwrap().execute(move || {
+            // a decoder panicking on a malformed file fails this load only
+            let data: Result<T::Data, AssetError> = panic::catch_unwind(AssertUnwindSafe(|| T::decode(&path)))
+                .unwrap_or_else(|_| Err(AssetError::invalid(&path, "decoder panicked")));
+            // the manager may be gone already
+            let _ = sender.send((id, Box::new(data)));
+            drop(in_flight);
+        });
+        handle
+    }
+
+    /// Upload the assets decoded in the background, until the time budget
+    /// is spent. At least one is uploaded per call. Returns how many were
+    pub fn update(&mut self, budget: Duration) -> usize {
+        self.ready.extend(self.decoded.try_iter());
+
+        let start = Instant::now();
+        let mut uploaded = 0;
+        while let Some((id, data)) = self.ready.pop_front() {
+            if let Some((key, pending)) = self.pending.remove(&id) {
+                if pending.upload(data) {
+                    uploaded += 1;
+                    if let Some(watched) = self.slots.get_mut(&key) {
+                        watched.update_stamps();
+                    }
+                }
+            }
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+        uploaded
+    }
+
+    /// Whether background loads are waiting to be decoded or uploaded
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Wait for every background load and upload it, for loading screens.
+    /// Loads that no worker can finish anymore are failed
+    pub fn finish_loading(&mut self) {
+        loop {
+            self.update(Duration::from_secs(3600));
+            if self.pending.is_empty() {
+                break;
+            }
+            if self.in_flight.load(Ordering::SeqCst) == 0 {
+                // the jobs send their data before leaving, it is all queued
+                self.ready.extend(self.decoded.try_iter());
+                if self.ready.is_empty() {
+                    for (_, (_, pending)) in self.pending.drain() {
+                        pending.fail("loading stopped");
+                    }
+                    break;
+                }
+                continue;
+            }
+            if let Ok(decoded) = self.decoded.recv_timeout(Duration::from_millis(10)) {
+                self.ready.push_back(decoded);
+            }
+        }
+    }
+
+    /// The asset at path if it is loaded or loading
+    pub fn get<T: Asset>(&self, path: &str) -> Option<Handle<T>> {
+        let key = AssetManager::key::<T>(path).ok()?;
+        let watched = self.slots.get(&key)?;
+        let weak = watched.slot.as_any().downcast_ref::<Weak<Entry<T>>>()?;
+        weak.upgrade().map(|entry| Handle { entry: entry })
+    }
+
+    /// Paths of the loaded assets of a type, sorted
+    pub fn loaded<T: Asset>(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.slots.iter()
+            .filter(|&(key, watched)| key.0 == TypeId::of::<T>() && watched.slot.is_alive())
+            .map(|(key, _)| key.1.clone())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Forget the assets whose handles are all dropped
+    pub fn collect_garbage(&mut self) {
+        self.slots.retain(|_, watched| watched.slot.is_alive());
+    }
+
+    /// Number of assets alive
+    pub fn len(&self) -> usize {
+        self.slots.values().filter(|watched| watched.slot.is_alive()).count()
+    }
+
+    /// Load again the assets whose source files changed on disk, sorted by
+    /// path. An asset that fails to load keeps its previous version, the
+    /// error is reported once per change of its files. Dropped assets are
+    /// forgotten first
+    pub fn reload_changed(&mut self) -> Vec<(String, Result<(), AssetError>)> {
+        self.collect_garbage();
+        let mut results = Vec::new();
+        for (key, watched) in self.slots.iter_mut() {
+            if !watched.changed() {
+                continue;
+            }
+            let result = watched.slot.reload();
+            watched.update_stamps();
+            results.push((key.1.clone(), result));
+        }
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        results
+    }
+
+    /// reload_changed at most every RELOAD_INTERVAL_MS. Meant to be called
+    /// every frame, the caller reports the results
+    pub fn poll_files(&mut self) -> Vec<(String, Result<(), AssetError>)> {
+        let now = Instant::now();
+        if let Some(last) = self.last_poll {
+            if now.duration_since(last) < Duration::from_millis(RELOAD_INTERVAL_MS) {
+                return Vec::new();
+            }
+        }
+        self.last_poll = Some(now);
+
+        self.reload_changed()
+    }
+}
@@ -1,42 +1,964 @@
 use system::rustc_serialize::json;
-use system::filesystem;
-use std::path::Path;
+use system::rustc_serialize::{Decodable, Encodable};
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use system::filesystem::{file_stamp, FileStamp};
+use system::vfs;
+use math::vec3::Vec3;
+use math::vec4::Vec4;
+use math::mat4::Mat4;
+use math::color::Color;
 
+/// Everything that can go wrong when loading or reading a config file
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read
+    Io { path: String, err: io::Error },
+    /// Invalid JSON, with the 1-based position of the error
+    Parse { line: usize, column: usize, msg: String },
+    /// The document is valid JSON but not an object
+    NotAnObject,
+    MissingKey(String),
+    /// The value doesn't have the expected JSON type
+    WrongType { key: String, expected: String, found: String },
+    /// The value has the right type but can't be used, like a bad hex color
+    InvalidValue { key: String, msg: String },
+    /// A malformed --set argument
+    InvalidArgument(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io { ref path, ref err } =>
+                write!(f, "couldn't read config file {}: {}", path, err),
+            ConfigError::Parse { line, column, ref msg } =>
+                write!(f, "invalid JSON at line {}, column {}: {}", line, column, msg),
+            ConfigError::NotAnObject =>
+                write!(f, "the config root must be a JSON object"),
+            ConfigError::MissingKey(ref key) =>
+                write!(f, "missing key {}", key),
+            ConfigError::WrongType { ref key, ref expected, ref found } =>
+                write!(f, "key {} should be {}, found {}", key, expected, found),
+            ConfigError::InvalidValue { ref key, ref msg } =>
+                write!(f, "invalid value for key {}: {}", key, msg),
+            ConfigError::InvalidArgument(ref arg) =>
+                write!(f, "invalid setting {}, expected --set key=value", arg),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// JSON type name of a value, for error messages
+fn json_type(value: &json::Json) -> &'static str {
+    match *value {
+        json::Json::I64(_) | json::Json::U64(_) => "an integer",
+        json::Json::F64(_) => "a number",
+        json::Json::String(_) => "a string",
+        json::Json::Boolean(_) => "a boolean",
+        json::Json::Array(_) => "an array",
+        json::Json::Object(_) => "an object",
+        json::Json::Null => "null",
+    }
+}
+
+/// Configuration sources, from the lowest to the highest precedence
+#[derive(Copy,Clone,Debug,PartialEq,Eq,PartialOrd,Ord)]
+pub enum Layer {
+    /// Built-in values, see DEFAULTS
+    Defaults,
+    /// The shipped config file, data/config.json
+    File,
+    /// Per-user overrides, see user_config_path
+    User,
+    /// RADAR_* environment variables
+    Environment,
+    /// --set key=value arguments
+    CommandLine,
+    /// Values set from the console, for this session only
+    Runtime,
+}
+
+/// Built-in values, so that a partial config file is enough to run
+pub const DEFAULTS: &'static str = r#"{
+    "window" : {
+        "width"       : 1280,
+        "height"      : 720,
+        "title"       : "radar-rs",
+        "clear_color" : [0.2, 0.2, 0.2, 1.0],
+        "vsync"       : true
+    },
+    "renderer" : {
+        "texture_filter" : "trilinear"
+    },
+    "assets" : {
+        "workers"          : 2,
+        "upload_budget_ms" : 4.0,
+        "hot_reload"       : true
+    },
+    "camera" : {
+        "move_speed"        : 5.0,
+        "look_sensitivity"  : 0.15,
+        "orbit_sensitivity" : 0.3,
+        "pan_sensitivity"   : 0.002,
+        "zoom_speed"        : 0.1,
+        "2d" : { "position" : [0.0, 0.0, 0.0], "near" : 0.01, "far" : 100.0 }
+    },
+    "input" : {
+        "bindings" : {
+            "forward" : "W", "back" : "S", "left" : "A",
+            "right" : "D", "up" : "E", "down" : "Q"
+        }
+    }
+}"#;
+
+/// Environment variables starting with this prefix override config values.
+/// Double underscores separate sections : RADAR_WINDOW__CLEAR_COLOR sets
+/// window.clear_color
+pub const ENV_PREFIX: &'static str = "RADAR_";
+
+/// Per-user override file : radar/config.json in the user config directory
+/// ($XDG_CONFIG_HOME or ~/.config, %APPDATA% on Windows)
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+    };
+    base.map(|dir| dir.join("radar").join("config.json"))
+}
+
+fn parse_json(s: &str) -> Result<json::Json, ConfigError> {
+    match json::Json::from_str(s) {
+        Ok(o) => Ok(o),
+        Err(json::ParserError::SyntaxError(code, line, column)) => {
+            Err(ConfigError::Parse {
+                line: line,
+                column: column,
+                msg: json::error_str(code).to_string(),
+            })
+        }
+        Err(json::ParserError::IoError(err)) => Err(ConfigError::Io { path: String::new(), err: err }),
+    }
+}
+
+fn read_file(path: &str) -> Result<String, ConfigError> {
+    let mut conf_str = String::new();
+    let read = File::open(path).and_then(|mut f| f.read_to_string(&mut conf_str));
+    if let Err(err) = read {
+        return Err(ConfigError::Io { path: path.to_string(), err: err });
+    }
+    Ok(conf_str)
+}
+
+fn join_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() { key.to_string() } else { format!("{}.{}", parent, key) }
+}
+
+/// Record the keys of every object in document order, json::Object being
+/// sorted. Objects are identified by their dotted path, "" for the root
+fn record_key_order(s: &str, order: &mut HashMap<String, Vec<String>>) {
+    let mut parser = json::Parser::new(s.chars());
+    while let Some(event) = parser.next() {
+        match event {
+            json::JsonEvent::ObjectEnd | json::JsonEvent::ArrayEnd => continue,
+            json::JsonEvent::Error(_) => return,
+            _ => {}
+        }
+
+        let stack = parser.stack();
+        let key = match stack.top() {
+            Some(json::StackElement::Key(key)) => key.to_string(),
+            _ => continue,
+        };
+        let mut parent = String::new();
+        for i in 0..stack.len() - 1 {
+            let elt = match stack.get(i) {
+                json::StackElement::Key(k) => k.to_string(),
+                json::StackElement::Index(i) => i.to_string(),
+            };
+            parent = join_path(&parent, &elt);
+        }
+
+        let keys = order.entry(parent).or_insert_with(Vec::new);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+}
+
+/// Pretty print in the style of data/config.json, keys of an object in
+/// their recorded order and the new ones after them
+fn write_json(out: &mut String, value: &json::Json, path: &str, indent: usize,
+              order: &HashMap<String, Vec<String>>) {
+    let pad = "    ";
+    match *value {
+        json::Json::Object(ref obj) if !obj.is_empty() => {
+            let mut keys: Vec<&String> = Vec::new();
+            if let Some(ordered) = order.get(path) {
+                keys.extend(ordered.iter().filter(|k| obj.contains_key(*k)));
+            }
+            for key in obj.keys() {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+
+            let width = keys.iter().map(|k| json::Json::String(k.to_string()).to_string().len()).max().unwrap();
+            out.push_str("{\n");
+            for (i, key) in keys.iter().enumerate() {
+                let quoted = json::Json::String(key.to_string()).to_string();
+                out.push_str(&pad.repeat(indent + 1));
+                out.push_str(&format!("{:width$} : ", quoted, width = width));
+                write_json(out, &obj[*key], &join_path(path, key), indent + 1, order);
+                out.push_str(if i + 1 < keys.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&pad.repeat(indent));
+            out.push('}');
+        }
+        json::Json::Array(ref arr) if arr.iter().any(|v| v.is_object() || v.is_array()) => {
+            out.push_str("[\n");
+            for (i, v) in arr.iter().enumerate() {
+                out.push_str(&pad.repeat(indent + 1));
+                write_json(out, v, &join_path(path, &i.to_string()), indent + 1, order);
+                out.push_str(if i + 1 < arr.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&pad.repeat(indent));
+            out.push(']');
+        }
+        json::Json::Array(ref arr) => {
+            let items: Vec<String> = arr.iter().map(|v| v.to_string()).collect();
+            out.push_str(&format!("[{}]", items.join(", ")));
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+/// Changed leaf values between two versions of the config. Objects are
+/// compared key by key, anything else as a whole. Removed keys are reported
+/// with a null value. A section added, removed or replaced by a plain value
+/// reports each of its keys
+fn diff_json(path: &str, old: Option<&json::Json>, new: Option<&json::Json>,
+             changes: &mut Vec<ConfigChange>) {
+    let old_obj = old.and_then(|v| v.as_object());
+    let new_obj = new.and_then(|v| v.as_object());
+    if let Some(old_obj) = old_obj {
+        for (key, old_value) in old_obj.iter() {
+            diff_json(&join_path(path, key), Some(old_value), new_obj.and_then(|obj| obj.get(key)), changes);
+        }
+    }
+    if let Some(new_obj) = new_obj {
+        for (key, new_value) in new_obj.iter() {
+            if !old_obj.map_or(false, |obj| obj.contains_key(key)) {
+                diff_json(&join_path(path, key), None, Some(new_value), changes);
+            }
+        }
+    }
+    let old_leaf = old.filter(|_| old_obj.is_none());
+    let new_leaf = new.filter(|_| new_obj.is_none());
+    if (old_leaf.is_some() || new_leaf.is_some()) && old_leaf != new_leaf {
+        changes.push(ConfigChange {
+            key: path.to_string(),
+            value: new.cloned().unwrap_or(json::Json::Null),
+        });
+    }
+}
+
+/// Values given on the command line or in the environment are JSON when
+/// they parse as such, plain strings otherwise
+fn parse_value(s: &str) -> json::Json {
+    json::Json::from_str(s).unwrap_or_else(|_| json::Json::String(s.to_string()))
+}
+
+/// Resolve a key, dotted paths like "window.width" going through nested
+/// objects
+fn lookup<'a>(root: &'a json::Json, field: &str) -> Result<&'a json::Json, ConfigError> {
+    let mut value = root;
+    let mut path_len = 0;
+    for key in field.split('.') {
+        let obj = match value.as_object() {
+            Some(obj) => obj,
+            None => {
+                return Err(ConfigError::WrongType {
+                    key: field[..path_len - 1].to_string(),
+                    expected: "an object".to_string(),
+                    found: json_type(value).to_string(),
+                });
+            }
+        };
+        value = match obj.get(key) {
+            Some(v) => v,
+            None => return Err(ConfigError::MissingKey(field.to_string()))
+        };
+        path_len += key.len() + 1;
+    }
+    Ok(value)
+}
+
+/// Set a value at a dotted path, creating or replacing the intermediate
+/// objects as needed
+fn set_path(root: &mut json::Json, field: &str, value: json::Json) {
+    let mut cur = root;
+    for key in field.split('.') {
+        if !cur.is_object() {
+            *cur = json::Json::Object(json::Object::new());
+        }
+        cur = match *cur {
+            json::Json::Object(ref mut obj) => obj.entry(key.to_string()).or_insert(json::Json::Null),
+            _ => unreachable!(),
+        };
+    }
+    *cur = value;
+}
+
+/// Remove the value at a dotted path, returns false if there was none
+fn remove_path(root: &mut json::Json, field: &str) -> bool {
+    let (parent, key) = match field.rfind('.') {
+        Some(i) => (&field[..i], &field[i + 1..]),
+        None => ("", field),
+    };
+    let mut cur = root;
+    if !parent.is_empty() {
+        for k in parent.split('.') {
+            cur = match *cur {
+                json::Json::Object(ref mut obj) => match obj.get_mut(k) {
+                    Some(v) => v,
+                    None => return false,
+                },
+                _ => return false,
+            };
+        }
+    }
+    match *cur {
+        json::Json::Object(ref mut obj) => obj.remove(key).is_some(),
+        _ => false,
+    }
+}
+
+/// Deep merge of over into base, objects being merged key by key
+fn merge_json(base: &mut json::Json, over: &json::Json) {
+    if let json::Json::Object(ref over_obj) = *over {
+        if let json::Json::Object(ref mut base_obj) = *base {
+            for (key, value) in over_obj.iter() {
+                if let Some(base_value) = base_obj.get_mut(key) {
+                    merge_json(base_value, value);
+                    continue;
+                }
+                base_obj.insert(key.clone(), value.clone());
+            }
+            return;
+        }
+    }
+    *base = over.clone();
+}
+
+/// A value changed by a setter or a new layer
+#[derive(Clone, Debug)]
+pub struct ConfigChange {
+    pub key: String,
+    /// The new value, null if the key was removed
+    pub value: json::Json,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SubscriptionId(usize);
+
+struct Subscriber {
+    id: SubscriptionId,
+    prefix: String,
+    callback: Box<dyn FnMut(&ConfigChange)>,
+}
+
+impl Subscriber {
+    fn wants(&self, key: &str) -> bool {
+        self.prefix.is_empty() || key == self.prefix ||
+            (key.starts_with(&self.prefix) && key[self.prefix.len()..].starts_with('.'))
+    }
+}
+
+fn write_file(path: &str, contents: &str) -> Result<(), ConfigError> {
+    let res = File::create(path).and_then(|mut f| f.write_all(contents.as_bytes()));
+    res.map_err(|err| ConfigError::Io { path: path.to_string(), err: err })
+}
+
+/// Minimum time between two checks of the config files in poll_files
+pub const RELOAD_INTERVAL_MS: u64 = 500;
+
+
+struct LayerValues {
+    layer: Layer,
+    /// File the values were read from, and its stamp when it was read
+    file: Option<(String, Option<FileStamp>)>,
+    values: json::Json,
+}
+
+/// Configuration values merged from several layers. Getters see the value
+/// of the highest precedence layer defining a key
 pub struct Config {
-    json_conf: json::Json
+    layers: Vec<LayerValues>,
+    json_conf: json::Json,
+    key_order: HashMap<String, Vec<String>>,
+    subscribers: Vec<Subscriber>,
+    next_subscription: usize,
+    last_poll: Option<Instant>,
+}
+
+impl FromStr for Config {
+    type Err = ConfigError;
+
+    /// Single layer config from a JSON document
+    fn from_str(s: &str) -> Result<Config, ConfigError> {
+        let mut conf = Config::empty();
+        conf.add_str(Layer::File, s)?;
+        Ok(conf)
+    }
 }
 
 impl Config {
+    pub fn empty() -> Config {
+        Config {
+            layers: Vec::new(),
+            json_conf: json::Json::Object(json::Object::new()),
+            key_order: HashMap::new(),
+            subscribers: Vec::new(),
+            next_subscription: 0,
+            last_poll: None,
+        }
+    }
+
+    /// Load a config file, panicking on errors. See load for a non
+    /// panicking version
     pub fn new(path: &str) -> Config {
-        let conf_str = filesystem::read_file(path);
+        match Config::load(path) {
+            Ok(conf) => conf,
+            Err(err) => panic!("Error loading config file {}: {}", path, err)
+        }
+    }
 
-        // decode
-        let json = match json::Json::from_str(&conf_str) {
-            Ok(o) => o,
-            Err(msg) => panic!("Error parsing Json config file: {}", msg)
-        };
+    pub fn load(path: &str) -> Result<Config, ConfigError> {
+        let mut conf = Config::empty();
+        conf.add_file(Layer::File, path)?;
+        Ok(conf)
+    }
 
-        Config {
-            json_conf: json
+    /// Load every layer : the built-in defaults, the config file at the vfs
+    /// path, the user file if there is one, the environment and the process
+    /// arguments
+    pub fn load_layered(path: &str) -> Result<Config, ConfigError> {
+        let mut conf = Config::empty();
+        conf.add_str(Layer::Defaults, DEFAULTS)?;
+        conf.add_vfs_file(Layer::File, path)?;
+        if let Some(user_path) = user_config_path() {
+            if user_path.exists() {
+                conf.add_file(Layer::User, &user_path.to_string_lossy())?;
+            }
+        }
+        conf.add_env(env::vars());
+        conf.add_args(env::args().skip(1))?;
+        Ok(conf)
+    }
+
+    /// Add a layer of values. Layers of the same kind added later take
+    /// precedence over the earlier ones
+    pub fn add_layer(&mut self, layer: Layer, values: json::Json) -> Result<(), ConfigError> {
+        self.insert_layer(layer, None, values)
+    }
+
+    fn insert_layer(&mut self, layer: Layer, file: Option<(String, Option<FileStamp>)>,
+                    values: json::Json) -> Result<(), ConfigError> {
+        if !values.is_object() {
+            return Err(ConfigError::NotAnObject);
+        }
+        let pos = self.layers.iter().position(|l| l.layer > layer).unwrap_or(self.layers.len());
+        self.layers.insert(pos, LayerValues { layer: layer, file: file, values: values });
+        self.merge_layers();
+        Ok(())
+    }
+
+    /// Add a layer from a JSON document, remembering its key order for save
+    pub fn add_str(&mut self, layer: Layer, s: &str) -> Result<(), ConfigError> {
+        let values = parse_json(s)?;
+        self.add_layer(layer, values)?;
+        record_key_order(s, &mut self.key_order);
+        Ok(())
+    }
+
+    /// Add a layer from a file, which poll_files then watches for changes
+    pub fn add_file(&mut self, layer: Layer, path: &str) -> Result<(), ConfigError> {
+        let stamp = file_stamp(path);
+        let conf_str = read_file(path)?;
+        let values = parse_json(&conf_str)?;
+        self.insert_layer(layer, Some((path.to_string(), stamp)), values)?;
+        record_key_order(&conf_str, &mut self.key_order);
+        Ok(())
+    }
+
+    /// Add a layer from a file of the vfs, so that an archive can patch it.
+    /// A loose file is watched by poll_files like the ones of add_file
+    pub fn add_vfs_file(&mut self, layer: Layer, path: &str) -> Result<(), ConfigError> {
+        let real_path = vfs::real_path(path).map(|p| p.to_string_lossy().into_owned());
+        let stamp = real_path.as_ref().and_then(|p| file_stamp(p));
+        let conf_str = vfs::read_to_string(path).map_err(|err| ConfigError::Io { path: path.to_string(), err: err })?;
+        let values = parse_json(&conf_str)?;
+        self.insert_layer(layer, real_path.map(|p| (p, stamp)), values)?;
+        record_key_order(&conf_str, &mut self.key_order);
+        Ok(())
+    }
+
+    /// Reload the files modified since they were read, subscribers getting
+    /// the values that changed. A file that fails to load keeps its previous
+    /// values, it is tried again on its next modification. Values set at
+    /// runtime in a layer backed by a file are replaced by the file contents.
+    /// Returns the files that failed, with their error
+    pub fn reload_changed(&mut self) -> Vec<(String, ConfigError)> {
+        let mut errors = Vec::new();
+        let mut reloaded = false;
+        for entry in self.layers.iter_mut() {
+            let (path, stamp) = match entry.file {
+                Some((ref path, ref mut stamp)) => (path, stamp),
+                None => continue,
+            };
+            // missing while an editor replaces it, or deleted : keep the values
+            let new_stamp = match file_stamp(path) {
+                Some(new_stamp) => new_stamp,
+                None => continue,
+            };
+            if *stamp == Some(new_stamp) {
+                continue;
+            }
+            *stamp = Some(new_stamp);
+
+            let res = read_file(path).and_then(|s| parse_json(&s).map(|v| (s, v)));
+            match res {
+                Ok((_, ref values)) if !values.is_object() => {
+                    errors.push((path.clone(), ConfigError::NotAnObject));
+                }
+                Ok((conf_str, values)) => {
+                    record_key_order(&conf_str, &mut self.key_order);
+                    entry.values = values;
+                    reloaded = true;
+                }
+                Err(err) => errors.push((path.clone(), err)),
+            }
+        }
+        if reloaded {
+            self.merge_layers();
         }
+        errors
     }
 
-    fn get_obj<T>(&self, field: &str) -> &json::Json {
-        match self.json_conf.as_object().unwrap().get(field) {
-            Some(f) => f,
-            None => panic!("Couldn't retrieve field {} from config file.", field)
+    /// reload_changed at most every RELOAD_INTERVAL_MS, logging the errors.
+    /// Meant to be called every frame
+    pub fn poll_files(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last_poll {
+            if now.duration_since(last) < Duration::from_millis(RELOAD_INTERVAL_MS) {
+                return;
+            }
+        }
+        self.last_poll = Some(now);
+
+        for (path, err) in self.reload_changed() {
+            println!("Config warning: error reloading {}: {}, keeping the previous values.", path, err);
+        }
+    }
+
+    /// Rebuild the merged values and notify the subscribers of the changes
+    fn merge_layers(&mut self) {
+        let mut merged = json::Json::Object(json::Object::new());
+        for entry in self.layers.iter() {
+            merge_json(&mut merged, &entry.values);
+        }
+
+        let mut changes = Vec::new();
+        diff_json("", Some(&self.json_conf), Some(&merged), &mut changes);
+        self.json_conf = merged;
+
+        for change in changes.iter() {
+            for sub in self.subscribers.iter_mut().filter(|sub| sub.wants(&change.key)) {
+                (sub.callback)(change);
+            }
+        }
+    }
+
+    /// Environment layer from the variables starting with ENV_PREFIX, except
+    /// vfs::BASE_DIR_VAR which is read by the vfs before the config exists
+    pub fn add_env<I: IntoIterator<Item = (String, String)>>(&mut self, vars: I) {
+        let mut values = json::Json::Object(json::Object::new());
+        for (name, value) in vars {
+            if !name.starts_with(ENV_PREFIX) || name.len() == ENV_PREFIX.len() || name == vfs::BASE_DIR_VAR {
+                continue;
+            }
+            let field = name[ENV_PREFIX.len()..].to_lowercase().replace("__", ".");
+            set_path(&mut values, &field, parse_value(&value));
+        }
+        self.add_layer(Layer::Environment, values).unwrap();
+    }
+
+    /// Command line layer from "--set key=value" or "--set=key=value"
+    /// arguments, the other arguments are ignored
+    pub fn add_args<I: IntoIterator<Item = String>>(&mut self, args: I) -> Result<(), ConfigError> {
+        let mut values = json::Json::Object(json::Object::new());
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let setting = if arg == "--set" {
+                match args.next() {
+                    Some(s) => s,
+                    None => return Err(ConfigError::InvalidArgument(arg)),
+                }
+            } else if arg.starts_with("--set=") {
+                arg["--set=".len()..].to_string()
+            } else {
+                continue;
+            };
+
+            match setting.find('=') {
+                Some(i) if i > 0 => set_path(&mut values, &setting[..i], parse_value(&setting[i + 1..])),
+                _ => return Err(ConfigError::InvalidArgument(setting)),
+            }
+        }
+        self.add_layer(Layer::CommandLine, values)
+    }
+
+    /// The layer the value of a key comes from, None if no layer defines it
+    pub fn source(&self, field: &str) -> Option<Layer> {
+        self.layers.iter().rev()
+            .find(|entry| lookup(&entry.values, field).is_ok())
+            .map(|entry| entry.layer)
+    }
+
+    fn try_get_obj(&self, field: &str) -> Result<&json::Json, ConfigError> {
+        lookup(&self.json_conf, field)
+    }
+
+    /// The raw value of a key or section
+    pub fn try_get_json(&self, field: &str) -> Result<&json::Json, ConfigError> {
+        self.try_get_obj(field)
+    }
+
+    fn wrong_type(&self, field: &str, expected: &str) -> ConfigError {
+        ConfigError::WrongType {
+            key: field.to_string(),
+            expected: expected.to_string(),
+            found: self.try_get_obj(field).map(json_type).unwrap_or("nothing").to_string(),
+        }
+    }
+
+    pub fn try_get_f64(&self, field: &str) -> Result<f64, ConfigError> {
+        self.try_get_obj(field)?.as_f64().ok_or_else(|| self.wrong_type(field, "a number"))
+    }
+
+    pub fn try_get_u64(&self, field: &str) -> Result<u64, ConfigError> {
+        self.try_get_obj(field)?.as_u64().ok_or_else(|| self.wrong_type(field, "a positive integer"))
+    }
+
+    pub fn try_get_i64(&self, field: &str) -> Result<i64, ConfigError> {
+        self.try_get_obj(field)?.as_i64().ok_or_else(|| self.wrong_type(field, "an integer"))
+    }
+
+    pub fn try_get_str(&self, field: &str) -> Result<&str, ConfigError> {
+        self.try_get_obj(field)?.as_string().ok_or_else(|| self.wrong_type(field, "a string"))
+    }
+
+    pub fn try_get_bool(&self, field: &str) -> Result<bool, ConfigError> {
+        self.try_get_obj(field)?.as_boolean().ok_or_else(|| self.wrong_type(field, "a boolean"))
+    }
+
+    pub fn try_get_array(&self, field: &str) -> Result<&json::Array, ConfigError> {
+        self.try_get_obj(field)?.as_array().ok_or_else(|| self.wrong_type(field, "an array"))
+    }
+
+    pub fn try_get_object(&self, field: &str) -> Result<&json::Object, ConfigError> {
+        self.try_get_obj(field)?.as_object().ok_or_else(|| self.wrong_type(field, "an object"))
+    }
+
+    /// Decode any type from the field : the math types from arrays, or a
+    /// whole section into a struct implementing Decodable
+    pub fn try_get<T: Decodable>(&self, field: &str) -> Result<T, ConfigError> {
+        let mut decoder = json::Decoder::new(self.try_get_obj(field)?.clone());
+        T::decode(&mut decoder).map_err(|err| match err {
+            json::DecoderError::ExpectedError(expected, found) => ConfigError::WrongType {
+                key: field.to_string(),
+                expected: expected,
+                found: found,
+            },
+            err => ConfigError::InvalidValue { key: field.to_string(), msg: err.to_string() },
+        })
+    }
+
+    pub fn try_get_vec3(&self, field: &str) -> Result<Vec3, ConfigError> {
+        self.try_get::<Vec3>(field)
+    }
+
+    pub fn try_get_vec4(&self, field: &str) -> Result<Vec4, ConfigError> {
+        self.try_get::<Vec4>(field)
+    }
+
+    pub fn try_get_mat4(&self, field: &str) -> Result<Mat4, ConfigError> {
+        self.try_get::<Mat4>(field)
+    }
+
+    /// Color written as a hex string ("#rrggbb" or "#rrggbbaa") or as an
+    /// array of 3 or 4 numbers
+    pub fn try_get_color(&self, field: &str) -> Result<Color, ConfigError> {
+        if let Some(hex) = self.try_get_obj(field)?.as_string() {
+            return Color::from_hex(hex).ok_or_else(|| ConfigError::InvalidValue {
+                key: field.to_string(),
+                msg: format!("{} is not a hex color", hex),
+            });
+        }
+        self.try_get::<Color>(field)
+    }
+
+    /// Value of the field, or default if it is missing. A value of the wrong
+    /// type is reported and replaced by default as well
+    pub fn get_or<T: Decodable>(&self, field: &str, default: T) -> T {
+        match self.try_get::<T>(field) {
+            Ok(v) => v,
+            Err(ConfigError::MissingKey(_)) => default,
+            Err(err) => {
+                println!("Config warning: {}, using the default value.", err);
+                default
+            }
+        }
+    }
+
+    pub fn get_str_or<'a>(&'a self, field: &str, default: &'a str) -> &'a str {
+        match self.try_get_str(field) {
+            Ok(v) => v,
+            Err(ConfigError::MissingKey(_)) => default,
+            Err(err) => {
+                println!("Config warning: {}, using the default value.", err);
+                default
+            }
         }
     }
 
     pub fn get_f64(&self, field: &str) -> f64 {
-        self.get_obj::<f64>(field).as_f64().unwrap()
+        unwrap_field(self.try_get_f64(field))
     }
 
     pub fn get_u64(&self, field: &str) -> u64 {
-        self.get_obj::<u64>(field).as_u64().unwrap()
+        unwrap_field(self.try_get_u64(field))
     }
 
     pub fn get_i64(&self, field: &str) -> i64 {
-        self.get_obj::<i64>(field).as_i64().unwrap()
+        unwrap_field(self.try_get_i64(field))
+    }
+
+    pub fn get_str(&self, field: &str) -> &str {
+        unwrap_field(self.try_get_str(field))
+    }
+
+    pub fn get_bool(&self, field: &str) -> bool {
+        unwrap_field(self.try_get_bool(field))
+    }
+
+    pub fn get_array(&self, field: &str) -> &json::Array {
+        unwrap_field(self.try_get_array(field))
+    }
+
+    pub fn get_object(&self, field: &str) -> &json::Object {
+        unwrap_field(self.try_get_object(field))
+    }
+
+    pub fn get<T: Decodable>(&self, field: &str) -> T {
+        unwrap_field(self.try_get::<T>(field))
+    }
+
+    pub fn get_vec3(&self, field: &str) -> Vec3 {
+        unwrap_field(self.try_get_vec3(field))
     }
-}
\ No newline at end of file
+
+    pub fn get_vec4(&self, field: &str) -> Vec4 {
+        unwrap_field(self.try_get_vec4(field))
+    }
+
+    pub fn get_mat4(&self, field: &str) -> Mat4 {
+        unwrap_field(self.try_get_mat4(field))
+    }
+
+    pub fn get_color(&self, field: &str) -> Color {
+        unwrap_field(self.try_get_color(field))
+    }
+
+    /// Set a value in the user layer, the one save writes. Layers above it
+    /// (environment, command line, runtime) still take precedence
+    pub fn set_json(&mut self, field: &str, value: json::Json) {
+        self.set_json_in(Layer::User, field, value);
+    }
+
+    /// Set a value in a given layer, the last one of that kind
+    pub fn set_json_in(&mut self, layer: Layer, field: &str, value: json::Json) {
+        if !self.layers.iter().any(|entry| entry.layer == layer) {
+            self.add_layer(layer, json::Json::Object(json::Object::new())).unwrap();
+        }
+        {
+            let entry = self.layers.iter_mut().rev().find(|entry| entry.layer == layer).unwrap();
+            set_path(&mut entry.values, field, value);
+        }
+
+        let mut parent = String::new();
+        for key in field.split('.') {
+            {
+                let keys = self.key_order.entry(parent.clone()).or_insert_with(Vec::new);
+                if !keys.iter().any(|k| k == key) {
+                    keys.push(key.to_string());
+                }
+            }
+            parent = join_path(&parent, key);
+        }
+        self.merge_layers();
+    }
+
+    /// Remove a value from every layer of the given kind, the value of the
+    /// layers below becoming visible again
+    pub fn remove_in(&mut self, layer: Layer, field: &str) {
+        let mut removed = false;
+        for entry in self.layers.iter_mut().filter(|entry| entry.layer == layer) {
+            removed |= remove_path(&mut entry.values, field);
+        }
+        if removed {
+            self.merge_layers();
+        }
+    }
+
+    pub fn set<T: Encodable>(&mut self, field: &str, value: &T) -> Result<(), ConfigError> {
+        let encoded = json::encode(value).map_err(|err| ConfigError::InvalidValue {
+            key: field.to_string(),
+            msg: err.to_string(),
+        })?;
+        self.set_json(field, parse_json(&encoded)?);
+        Ok(())
+    }
+
+    pub fn set_f64(&mut self, field: &str, value: f64) {
+        self.set_json(field, json::Json::F64(value));
+    }
+
+    pub fn set_u64(&mut self, field: &str, value: u64) {
+        self.set_json(field, json::Json::U64(value));
+    }
+
+    pub fn set_i64(&mut self, field: &str, value: i64) {
+        self.set_json(field, json::Json::I64(value));
+    }
+
+    pub fn set_str(&mut self, field: &str, value: &str) {
+        self.set_json(field, json::Json::String(value.to_string()));
+    }
+
+    pub fn set_bool(&mut self, field: &str, value: bool) {
+        self.set_json(field, json::Json::Boolean(value));
+    }
+
+    pub fn set_vec3(&mut self, field: &str, value: Vec3) {
+        self.set(field, &value).unwrap();
+    }
+
+    pub fn set_vec4(&mut self, field: &str, value: Vec4) {
+        self.set(field, &value).unwrap();
+    }
+
+    pub fn set_color(&mut self, field: &str, value: Color) {
+        self.set(field, &value).unwrap();
+    }
+
+    /// Every value as pretty JSON, merged from all the layers, keys in the
+    /// order they were read in
+    pub fn to_pretty_string(&self) -> String {
+        self.json_to_pretty_string(&self.json_conf)
+    }
+
+    /// The values of one layer as pretty JSON. For Layer::User, the user
+    /// file contents and the values set at runtime
+    pub fn layer_to_pretty_string(&self, layer: Layer) -> String {
+        let empty = json::Json::Object(json::Object::new());
+        let values = self.layers.iter().rev().find(|entry| entry.layer == layer)
+            .map(|entry| &entry.values).unwrap_or(&empty);
+        self.json_to_pretty_string(values)
+    }
+
+    fn json_to_pretty_string(&self, values: &json::Json) -> String {
+        let mut out = String::new();
+        write_json(&mut out, values, "", 0, &self.key_order);
+        out.push('\n');
+        out
+    }
+
+    /// Write every value to a file, see to_pretty_string. A config file that
+    /// is loaded, changed and saved back keeps the values it didn't change
+    pub fn save(&self, path: &str) -> Result<(), ConfigError> {
+        write_file(path, &self.to_pretty_string())
+    }
+
+    /// Write the values of one layer to a file
+    pub fn save_layer(&self, layer: Layer, path: &str) -> Result<(), ConfigError> {
+        write_file(path, &self.layer_to_pretty_string(layer))
+    }
+
+    /// Save to the per-user file load_layered reads, creating its directory
+    pub fn save_user(&self) -> Result<(), ConfigError> {
+        let path = match user_config_path() {
+            Some(path) => path,
+            None => {
+                return Err(ConfigError::Io {
+                    path: String::new(),
+                    err: io::Error::new(io::ErrorKind::NotFound, "no user config directory"),
+                });
+            }
+        };
+        let path_str = path.to_string_lossy().into_owned();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| ConfigError::Io { path: path_str.clone(), err: err })?;
+        }
+        self.save_layer(Layer::User, &path_str)
+    }
+
+    /// Call f with every change of the field, or of the fields under it for
+    /// a section. An empty prefix gets every change
+    pub fn on_change<F: FnMut(&ConfigChange) + 'static>(&mut self, prefix: &str, f: F) -> SubscriptionId {
+        let id = SubscriptionId(self.next_subscription);
+        self.next_subscription += 1;
+        self.subscribers.push(Subscriber {
+            id: id,
+            prefix: prefix.to_string(),
+            callback: Box::new(f),
+        });
+        id
+    }
+
+    /// Changes of the field as a channel, for systems that poll them once
+    /// per frame
+    pub fn subscribe(&mut self, prefix: &str) -> mpsc::Receiver<ConfigChange> {
+        let (tx, rx) = mpsc::channel();
+        self.on_change(prefix, move |change| {
+            let _ = tx.send(change.clone());
+        });
+        rx
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.subscribers.retain(|sub| sub.id != id);
+    }
+}
+
+/// The get_* methods panic, for values the program can't run without
+fn unwrap_field<T>(res: Result<T, ConfigError>) -> T {
+    match res {
+        Ok(v) => v,
+        Err(err) => panic!("Config error: {}.", err)
+    }
+}
@@ -0,0 +1,446 @@
+use system::config::{self, Config};
+use system::cvar::*;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Lines kept in the history file
+pub const HISTORY_SIZE: usize = 200;
+/// Output lines kept for the overlay
+pub const LOG_SIZE: usize = 256;
+
+#[derive(Debug)]
+pub enum ConsoleError {
+    /// Unterminated quote, bad escape
+    Syntax(String),
+    UnknownCommand(String),
+    /// Wrong arguments, with the usage of the command
+    Usage(String),
+    CVar(CVarError),
+    /// A command failed, with its message
+    Failed(String),
+}
+
+impl fmt::Display for ConsoleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConsoleError::Syntax(ref msg) => write!(f, "syntax error: {}", msg),
+            ConsoleError::UnknownCommand(ref name) => write!(f, "unknown command or variable {}", name),
+            ConsoleError::Usage(ref usage) => write!(f, "usage: {}", usage),
+            ConsoleError::CVar(ref err) => write!(f, "{}", err),
+            ConsoleError::Failed(ref msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl Error for ConsoleError {}
+
+impl From<CVarError> for ConsoleError {
+    fn from(err: CVarError) -> ConsoleError {
+        ConsoleError::CVar(err)
+    }
+}
+
+/// Command handlers get the arguments without the command name, and return
+/// the text to print
+pub type CommandFn = Rc<dyn Fn(&mut Console, &mut Config, &[String]) -> Result<String, ConsoleError>>;
+
+pub struct Command {
+    /// Arguments, as in "set <name> <value>"
+    pub usage: String,
+    pub description: String,
+    handler: CommandFn,
+}
+
+/// Split a console line in commands and their arguments. Commands are
+/// separated by ';', arguments by spaces unless in double quotes, where
+/// \" and \\ are escapes
+pub fn parse_line(line: &str) -> Result<Vec<Vec<String>>, ConsoleError> {
+    let mut commands = Vec::new();
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            match chars.next() {
+                                Some(e) if e == '"' || e == '\\' => arg.push(e),
+                                Some(e) => return Err(ConsoleError::Syntax(format!("unknown escape \\{}", e))),
+                                None => return Err(ConsoleError::Syntax("unterminated quote".to_string())),
+                            }
+                        }
+                        Some(other) => arg.push(other),
+                        None => return Err(ConsoleError::Syntax("unterminated quote".to_string())),
+                    }
+                }
+            }
+            ';' | ' ' | '\t' => {
+                if in_arg {
+                    args.push(arg.clone());
+                    arg.clear();
+                    in_arg = false;
+                }
+                if c == ';' && !args.is_empty() {
+                    commands.push(args);
+                    args = Vec::new();
+                }
+            }
+            _ => {
+                in_arg = true;
+                arg.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(arg);
+    }
+    if !args.is_empty() {
+        commands.push(args);
+    }
+    Ok(commands)
+}
+
+/// Default history file, next to the user config file
+pub fn history_path() -> Option<PathBuf> {
+    config::user_config_path().and_then(|path| path.parent().map(|dir| dir.join("console_history")))
+}
+
+fn longest_common_prefix(words: &[String]) -> String {
+    let mut prefix = words[0].clone();
+    for word in words[1..].iter() {
+        let len = prefix.chars().zip(word.chars()).take_while(|&(a, b)| a == b).count();
+        prefix = prefix.chars().take(len).collect();
+    }
+    prefix
+}
+
+/// Commands and console variables, run from the in-app overlay or from
+/// stdin in headless mode
+pub struct Console {
+    pub cvars: CVarRegistry,
+    commands: BTreeMap<String, Command>,
+    history: Vec<String>,
+    history_file: Option<PathBuf>,
+    log: Vec<String>,
+    printed: usize,
+    quit: bool,
+}
+
+impl Console {
+    pub fn new() -> Console {
+        let mut console = Console {
+            cvars: CVarRegistry::new(),
+            commands: BTreeMap::new(),
+            history: Vec::new(),
+            history_file: None,
+            log: Vec::new(),
+            printed: 0,
+            quit: false,
+        };
+        console.register_builtins();
+        console
+    }
+
+    pub fn register_command<F>(&mut self, name: &str, usage: &str, description: &str, f: F)
+        where F: Fn(&mut Console, &mut Config, &[String]) -> Result<String, ConsoleError> + 'static {
+        self.commands.insert(name.to_string(), Command {
+            usage: usage.to_string(),
+            description: description.to_string(),
+            handler: Rc::new(f),
+        });
+    }
+
+    pub fn command(&self, name: &str) -> Option<&Command> {
+        self.commands.get(name)
+    }
+
+    /// Run a line of one or more commands, stopping at the first error. The
+    /// line and its output are added to the history and the log
+    pub fn execute(&mut self, conf: &mut Config, line: &str) -> Result<String, ConsoleError> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(String::new());
+        }
+        self.add_history(line);
+        self.print(&format!("> {}", line));
+
+        let res = parse_line(line).and_then(|commands| {
+            let mut output = Vec::new();
+            for args in commands.iter() {
+                let out = self.run(conf, args)?;
+                if !out.is_empty() {
+                    output.push(out);
+                }
+            }
+            Ok(output.join("\n"))
+        });
+        match res {
+            Ok(ref out) if !out.is_empty() => self.print(out),
+            Err(ref err) => self.print(&format!("error: {}", err)),
+            _ => {}
+        }
+        res
+    }
+
+    /// Run a command, or show or set a variable
+    pub fn run(&mut self, conf: &mut Config, args: &[String]) -> Result<String, ConsoleError> {
+        let name = &args[0];
+        let handler = self.commands.get(name).map(|cmd| cmd.handler.clone());
+        if let Some(handler) = handler {
+            return handler(self, conf, &args[1..]);
+        }
+
+        if self.cvars.get(name).is_none() {
+            return Err(ConsoleError::UnknownCommand(name.clone()));
+        }
+        if args.len() > 1 {
+            self.cvars.set(conf, name, &args[1..])?;
+        }
+        Ok(format!("{} = {}", name, self.cvars.value_string(conf, name)?))
+    }
+
+    /// Complete the last word of a line. Returns the completed line and the
+    /// candidates, the line being extended to their common prefix
+    pub fn complete(&self, line: &str) -> (String, Vec<String>) {
+        let start = line.rfind(|c: char| c == ' ' || c == ';').map(|i| i + 1).unwrap_or(0);
+        let (head, word) = line.split_at(start);
+        let cmd_start = head.rfind(';').map(|i| i + 1).unwrap_or(0);
+        let previous: Vec<&str> = head[cmd_start..].split_whitespace().collect();
+
+        let mut candidates: Vec<String> = Vec::new();
+        if previous.is_empty() {
+            candidates.extend(self.commands.keys().filter(|name| name.starts_with(word)).cloned());
+            candidates.extend(self.cvars.names_with_prefix(word).iter().map(|name| name.to_string()));
+        } else if previous.len() == 1 {
+            match previous[0] {
+                "set" | "reset" | "toggle" | "cvars" => {
+                    candidates.extend(self.cvars.names_with_prefix(word).iter().map(|name| name.to_string()));
+                }
+                "help" => {
+                    candidates.extend(self.commands.keys().filter(|name| name.starts_with(word)).cloned());
+                    candidates.extend(self.cvars.names_with_prefix(word).iter().map(|name| name.to_string()));
+                }
+                name => {
+                    if self.cvars.get(name).map_or(false, |cvar| cvar.ty == CVarType::Bool) {
+                        candidates.extend(["false", "true"].iter().filter(|v| v.starts_with(word)).map(|v| v.to_string()));
+                    }
+                }
+            }
+        }
+        candidates.sort();
+        candidates.dedup();
+
+        let completed = match candidates.len() {
+            0 => line.to_string(),
+            1 => format!("{}{} ", head, candidates[0]),
+            _ => format!("{}{}", head, longest_common_prefix(&candidates)),
+        };
+        (completed, candidates)
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Read the previous history, lines run from now on being appended to
+    /// the file. A missing file is fine
+    pub fn load_history(&mut self, path: &Path) -> io::Result<()> {
+        self.history_file = Some(path.to_path_buf());
+        let mut content = String::new();
+        match File::open(path) {
+            Ok(mut f) => { f.read_to_string(&mut content)?; }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        }
+        self.history = content.lines().filter(|l| !l.trim().is_empty()).map(|l| l.to_string()).collect();
+        let len = self.history.len();
+        if len > HISTORY_SIZE {
+            self.history.drain(..len - HISTORY_SIZE);
+        }
+
+        // keep the file from growing forever
+        if len > 2 * HISTORY_SIZE {
+            let mut f = File::create(path)?;
+            for line in self.history.iter() {
+                writeln!(f, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_history(&mut self, line: &str) {
+        if self.history.last().map_or(false, |last| last == line) {
+            return;
+        }
+        self.history.push(line.to_string());
+        if self.history.len() > HISTORY_SIZE {
+            self.history.remove(0);
+        }
+
+        if let Some(ref path) = self.history_file {
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            let res = OpenOptions::new().create(true).append(true).open(path)
+                .and_then(|mut f| writeln!(f, "{}", line));
+            if let Err(err) = res {
+                println!("Console warning: can't write history file {}: {}", path.display(), err);
+                self.history_file = None;
+            }
+        }
+    }
+
+    /// Output shown by the overlay, oldest first
+    pub fn log(&self) -> &[String] {
+        &self.log
+    }
+
+    pub fn print(&mut self, text: &str) {
+        for line in text.lines() {
+            self.log.push(line.to_string());
+            self.printed += 1;
+        }
+        let len = self.log.len();
+        if len > LOG_SIZE {
+            self.log.drain(..len - LOG_SIZE);
+        }
+    }
+
+    /// Number of lines printed so far, to know when the log changed
+    pub fn lines_printed(&self) -> usize {
+        self.printed
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    /// Read commands from stdin until quit or the end of input, for headless
+    /// runs
+    pub fn run_stdin(&mut self, conf: &mut Config) {
+        let stdin = io::stdin();
+        print!("> ");
+        let _ = io::stdout().flush();
+        for line in stdin.lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            conf.poll_files();
+            match self.execute(conf, &line) {
+                Ok(ref out) if !out.is_empty() => println!("{}", out),
+                Err(err) => println!("error: {}", err),
+                _ => {}
+            }
+            if self.quit {
+                break;
+            }
+            print!("> ");
+            let _ = io::stdout().flush();
+        }
+    }
+
+    fn register_builtins(&mut self) {
+        self.register_command("help", "help [name]", "List the commands, or describe a command or variable",
+                              |console, conf, args| {
+            if args.is_empty() {
+                let lines: Vec<String> = console.commands.iter()
+                    .map(|(name, cmd)| format!("{:8} {}", name, cmd.description)).collect();
+                return Ok(lines.join("\n"));
+            }
+            if let Some(cmd) = console.commands.get(&args[0]) {
+                return Ok(format!("{}\n  {}", cmd.usage, cmd.description));
+            }
+            match console.cvars.get(&args[0]) {
+                Some(cvar) => {
+                    let mut help = format!("{} ({}) = {}\n  {}", cvar.name, cvar.ty.name(),
+                                           console.cvars.value_string(conf, &cvar.name)?, cvar.description);
+                    if cvar.min.is_some() || cvar.max.is_some() {
+                        help.push_str(&format!("\n  range: {} to {}",
+                                               cvar.min.map_or("-".to_string(), |v| v.to_string()),
+                                               cvar.max.map_or("-".to_string(), |v| v.to_string())));
+                    }
+                    if cvar.is_read_only() {
+                        help.push_str("\n  read-only");
+                    }
+                    if cvar.is_archived() {
+                        help.push_str("\n  saved with the user config");
+                    }
+                    Ok(help)
+                }
+                None => Err(ConsoleError::UnknownCommand(args[0].clone())),
+            }
+        });
+
+        self.register_command("cvars", "cvars [prefix]", "List the variables and their values",
+                              |console, conf, args| {
+            let prefix = args.get(0).map_or("", |s| s.as_str());
+            let mut lines = Vec::new();
+            for cvar in console.cvars.iter().filter(|cvar| cvar.name.starts_with(prefix)) {
+                lines.push(format!("{} = {}", cvar.name, console.cvars.value_string(conf, &cvar.name)?));
+            }
+            Ok(lines.join("\n"))
+        });
+
+        self.register_command("set", "set <name> <value>", "Set a variable", |console, conf, args| {
+            if args.len() < 2 {
+                return Err(ConsoleError::Usage("set <name> <value>".to_string()));
+            }
+            console.cvars.set(conf, &args[0], &args[1..])?;
+            Ok(format!("{} = {}", args[0], console.cvars.value_string(conf, &args[0])?))
+        });
+
+        self.register_command("reset", "reset <name>", "Set a variable back to its config file value",
+                              |console, conf, args| {
+            if args.len() != 1 {
+                return Err(ConsoleError::Usage("reset <name>".to_string()));
+            }
+            console.cvars.reset(conf, &args[0])?;
+            Ok(format!("{} = {}", args[0], console.cvars.value_string(conf, &args[0])?))
+        });
+
+        self.register_command("toggle", "toggle <name>", "Flip a boolean variable", |console, conf, args| {
+            if args.len() != 1 {
+                return Err(ConsoleError::Usage("toggle <name>".to_string()));
+            }
+            let value = console.cvars.toggle(conf, &args[0])?;
+            Ok(format!("{} = {}", args[0], value))
+        });
+
+        self.register_command("echo", "echo <text>", "Print text", |_, _, args| {
+            Ok(args.join(" "))
+        });
+
+        self.register_command("history", "history", "List the previous commands", |console, _, _| {
+            let lines: Vec<String> = console.history.iter().enumerate()
+                .map(|(i, line)| format!("{:4} {}", i + 1, line)).collect();
+            Ok(lines.join("\n"))
+        });
+
+        self.register_command("save", "save", "Save the archived variables to the user config file",
+                              |_, conf, _| {
+            match conf.save_user() {
+                Ok(()) => Ok(format!("saved to {}", config::user_config_path().unwrap().display())),
+                Err(err) => Err(ConsoleError::Failed(err.to_string())),
+            }
+        });
+
+        self.register_command("quit", "quit", "Exit the application", |console, _, _| {
+            console.quit = true;
+            Ok(String::new())
+        });
+    }
+}
@@ -0,0 +1,283 @@
+use system::rustc_serialize::json;
+use system::rustc_serialize::Encodable;
+use system::config::{Config, Layer};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use math::color::Color;
+
+/// The value can't be changed from the console
+pub const CVAR_READ_ONLY: u32 = 0x1;
+/// Changes are also written to the user layer, so that Config::save_user
+/// persists them
+pub const CVAR_ARCHIVE: u32 = 0x2;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CVarType {
+    Bool,
+    Int,
+    Float,
+    Str,
+    /// A hex string or 3 to 4 numbers, stored as [r, g, b, a]
+    Color,
+}
+
+impl CVarType {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            CVarType::Bool => "bool",
+            CVarType::Int => "int",
+            CVarType::Float => "float",
+            CVarType::Str => "string",
+            CVarType::Color => "color",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CVarError {
+    Unknown(String),
+    ReadOnly(String),
+    InvalidValue { name: String, msg: String },
+    OutOfRange { name: String, value: f64, min: Option<f64>, max: Option<f64> },
+}
+
+impl fmt::Display for CVarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CVarError::Unknown(ref name) => write!(f, "unknown variable {}", name),
+            CVarError::ReadOnly(ref name) => write!(f, "{} is read-only", name),
+            CVarError::InvalidValue { ref name, ref msg } => write!(f, "invalid value for {}: {}", name, msg),
+            CVarError::OutOfRange { ref name, value, min, max } => {
+                write!(f, "{} is out of range for {}, expected", value, name)?;
+                match (min, max) {
+                    (Some(min), Some(max)) => write!(f, " {} to {}", min, max),
+                    (Some(min), None) => write!(f, " at least {}", min),
+                    (None, Some(max)) => write!(f, " at most {}", max),
+                    (None, None) => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl Error for CVarError {}
+
+/// A console variable. The value lives in the Config, under the same name
+/// as the variable : "window.vsync" is the vsync value of the config file
+pub struct CVar {
+    pub name: String,
+    pub ty: CVarType,
+    pub description: String,
+    pub flags: u32,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Set in the defaults layer when no config file has the value
+    pub default: Option<json::Json>,
+}
+
+impl CVar {
+    pub fn new(name: &str, ty: CVarType, description: &str) -> CVar {
+        CVar {
+            name: name.to_string(),
+            ty: ty,
+            description: description.to_string(),
+            flags: 0,
+            min: None,
+            max: None,
+            default: None,
+        }
+    }
+
+    pub fn with_flags(mut self, flags: u32) -> CVar {
+        self.flags = flags;
+        self
+    }
+
+    /// Bounds of an int or float variable
+    pub fn with_range(mut self, min: f64, max: f64) -> CVar {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    pub fn with_min(mut self, min: f64) -> CVar {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn with_default(mut self, value: json::Json) -> CVar {
+        self.default = Some(value);
+        self
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.flags & CVAR_READ_ONLY != 0
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.flags & CVAR_ARCHIVE != 0
+    }
+
+    fn invalid(&self, msg: &str) -> CVarError {
+        CVarError::InvalidValue { name: self.name.clone(), msg: msg.to_string() }
+    }
+
+    fn check_range(&self, value: f64) -> Result<(), CVarError> {
+        let too_low = self.min.map_or(false, |min| value < min);
+        let too_high = self.max.map_or(false, |max| value > max);
+        if too_low || too_high {
+            return Err(CVarError::OutOfRange {
+                name: self.name.clone(),
+                value: value,
+                min: self.min,
+                max: self.max,
+            });
+        }
+        Ok(())
+    }
+
+    /// Parse console arguments as a value of this variable
+    pub fn parse(&self, args: &[String]) -> Result<json::Json, CVarError> {
+        if args.is_empty() {
+            return Err(self.invalid("missing value"));
+        }
+        if self.ty != CVarType::Str && self.ty != CVarType::Color && args.len() > 1 {
+            return Err(self.invalid(&format!("expected one {} value", self.ty.name())));
+        }
+
+        match self.ty {
+            CVarType::Bool => {
+                match args[0].as_str() {
+                    "1" | "true" | "on" | "yes" => Ok(json::Json::Boolean(true)),
+                    "0" | "false" | "off" | "no" => Ok(json::Json::Boolean(false)),
+                    s => Err(self.invalid(&format!("{} is not a boolean", s))),
+                }
+            }
+            CVarType::Int => {
+                let v = args[0].parse::<i64>().map_err(|_| self.invalid(&format!("{} is not an integer", args[0])))?;
+                self.check_range(v as f64)?;
+                Ok(json::Json::I64(v))
+            }
+            CVarType::Float => {
+                let v = args[0].parse::<f64>().map_err(|_| self.invalid(&format!("{} is not a number", args[0])))?;
+                self.check_range(v)?;
+                Ok(json::Json::F64(v))
+            }
+            CVarType::Str => Ok(json::Json::String(args.join(" "))),
+            CVarType::Color => {
+                let color = if args.len() == 1 {
+                    Color::from_hex(&args[0]).ok_or_else(|| self.invalid(&format!("{} is not a hex color", args[0])))?
+                } else if args.len() == 3 || args.len() == 4 {
+                    let mut c = [0.0, 0.0, 0.0, 1.0];
+                    for (i, arg) in args.iter().enumerate() {
+                        c[i] = arg.parse::<f32>().map_err(|_| self.invalid(&format!("{} is not a number", arg)))?;
+                    }
+                    Color::new(c[0], c[1], c[2], c[3])
+                } else {
+                    return Err(self.invalid("expected a hex color or 3 to 4 numbers"));
+                };
+                let encoded = json::encode(&color).unwrap();
+                Ok(json::Json::from_str(&encoded).unwrap())
+            }
+        }
+    }
+}
+
+/// The variables known to the console, sorted by name
+pub struct CVarRegistry {
+    cvars: BTreeMap<String, CVar>,
+}
+
+impl CVarRegistry {
+    pub fn new() -> CVarRegistry {
+        CVarRegistry { cvars: BTreeMap::new() }
+    }
+
+    /// Add a variable, giving its default value to the config if it has none
+    pub fn register(&mut self, conf: &mut Config, cvar: CVar) {
+        if conf.try_get_json(&cvar.name).is_err() {
+            if let Some(ref default) = cvar.default {
+                conf.set_json_in(Layer::Defaults, &cvar.name, default.clone());
+            }
+        }
+        self.cvars.insert(cvar.name.clone(), cvar);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CVar> {
+        self.cvars.get(name)
+    }
+
+    pub fn iter<'a>(&'a self) -> ::std::collections::btree_map::Values<'a, String, CVar> {
+        self.cvars.values()
+    }
+
+    /// Names starting with prefix, in order
+    pub fn names_with_prefix(&self, prefix: &str) -> Vec<&str> {
+        self.cvars.keys().filter(|name| name.starts_with(prefix)).map(|name| name.as_str()).collect()
+    }
+
+    fn find(&self, name: &str) -> Result<&CVar, CVarError> {
+        self.cvars.get(name).ok_or_else(|| CVarError::Unknown(name.to_string()))
+    }
+
+    /// The value as shown in the console, "unset" if the config has none
+    pub fn value_string(&self, conf: &Config, name: &str) -> Result<String, CVarError> {
+        self.find(name)?;
+        Ok(match conf.try_get_json(name) {
+            Ok(&json::Json::String(ref s)) => s.clone(),
+            Ok(value) => value.to_string(),
+            Err(_) => "unset".to_string(),
+        })
+    }
+
+    /// Set from console arguments. The value goes to the runtime layer so it
+    /// applies right away, and to the user layer for archived variables
+    pub fn set(&self, conf: &mut Config, name: &str, args: &[String]) -> Result<(), CVarError> {
+        let cvar = self.find(name)?;
+        if cvar.is_read_only() {
+            return Err(CVarError::ReadOnly(name.to_string()));
+        }
+        let value = cvar.parse(args)?;
+        if cvar.is_archived() {
+            conf.set_json_in(Layer::User, name, value.clone());
+        }
+        conf.set_json_in(Layer::Runtime, name, value);
+        Ok(())
+    }
+
+    /// Set a value from code, read-only variables included
+    pub fn force<T: Encodable>(&self, conf: &mut Config, name: &str, value: &T) -> Result<(), CVarError> {
+        self.find(name)?;
+        let encoded = json::encode(value).map_err(|err| CVarError::InvalidValue {
+            name: name.to_string(),
+            msg: err.to_string(),
+        })?;
+        conf.set_json_in(Layer::Runtime, name, json::Json::from_str(&encoded).unwrap());
+        Ok(())
+    }
+
+    /// Drop the values set from the console, back to the config files value
+    pub fn reset(&self, conf: &mut Config, name: &str) -> Result<(), CVarError> {
+        let cvar = self.find(name)?;
+        if cvar.is_read_only() {
+            return Err(CVarError::ReadOnly(name.to_string()));
+        }
+        if cvar.is_archived() {
+            conf.remove_in(Layer::User, name);
+        }
+        conf.remove_in(Layer::Runtime, name);
+        Ok(())
+    }
+
+    /// Flip a boolean variable
+    pub fn toggle(&self, conf: &mut Config, name: &str) -> Result<bool, CVarError> {
+        let cvar = self.find(name)?;
+        if cvar.ty != CVarType::Bool {
+            return Err(CVarError::InvalidValue { name: name.to_string(), msg: "not a boolean".to_string() });
+        }
+        let value = !conf.try_get_bool(name).unwrap_or(false);
+        self.set(conf, name, &[value.to_string()])?;
+        Ok(value)
+    }
+}
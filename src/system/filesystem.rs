@@ -1,42 +1,90 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
 use std::path::Path;
-use std::fs::File;
-use std::io::prelude::*;
-use std::env;
+use std::time::SystemTime;
+use system::vfs;
 
+#[derive(Debug)]
+pub enum FsError {
+    NotFound(String),
+    PermissionDenied(String),
+    /// A text file with invalid utf8, at the given byte offset
+    InvalidUtf8 { path: String, offset: usize },
+    /// Any other read error, a corrupted archive for example
+    Io { path: String, err: io::Error },
+}
+
+impl FsError {
+    fn from_io(path: &str, err: io::Error) -> FsError {
+        match err.kind() {
+            io::ErrorKind::NotFound => FsError::NotFound(path.to_string()),
+            io::ErrorKind::PermissionDenied => FsError::PermissionDenied(path.to_string()),
+            _ => FsError::Io { path: path.to_string(), err: err },
+        }
+    }
+}
+
+impl fmt::Display for FsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FsError::NotFound(ref path) =>
+                write!(f, "{} doesn't exist", path),
+            FsError::PermissionDenied(ref path) =>
+                write!(f, "permission denied reading {}", path),
+            FsError::InvalidUtf8 { ref path, offset } =>
+                write!(f, "found non valid utf8 characters in {} at byte {}", path, offset),
+            FsError::Io { ref path, ref err } =>
+                write!(f, "error reading {}: {}", path, err),
+        }
+    }
+}
+
+impl Error for FsError {}
+
+/// Modification time and size of a file on disk, a change of either
+/// triggers a reload
+pub type FileStamp = (SystemTime, u64);
+
+pub fn file_stamp<P: AsRef<Path>>(path: P) -> Option<FileStamp> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// False for files with no extension, or one that isn't valid utf8
 pub fn check_extension(path: &Path, valid_ext: &[&str]) -> bool {
-    let ext = &path.extension().expect("The file has no extension").to_str().expect("Extension is not valid utf8");
+    let ext = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => ext,
+        None => return false,
+    };
 
     for vext in valid_ext.iter() {
-        if vext == ext {
+        if *vext == ext {
             return true;
         }
     }
     false
 }
 
-pub fn read_file(path_str: &str) -> String {
-    let mut path = env::current_dir().unwrap();
-    path.push(Path::new(path_str));
-    
-
-    if !path.exists() {
-        panic!("Error reading file, path {} doesn't exist.", path.display());
-    }
+/// Read a file through the vfs
+pub fn read_bytes(path_str: &str) -> Result<Vec<u8>, FsError> {
+    vfs::read_bytes(path_str).map_err(|err| FsError::from_io(path_str, err))
+}
 
-    let mut f = match File::open(&path) {
-        Ok(f) => f,
-        Err(msg) => panic!("Error reading file {} : {}.", path.display(), msg)
-    };
+pub fn read_to_string(path_str: &str) -> Result<String, FsError> {
+    let bytes = read_bytes(path_str)?;
+    String::from_utf8(bytes).map_err(|err| FsError::InvalidUtf8 {
+        path: path_str.to_string(),
+        offset: err.utf8_error().valid_up_to(),
+    })
+}
 
-    // read bytes and return as str
-    let mut bytes = Vec::new();
-    match f.read_to_end(&mut bytes) {
-        Ok(_) => {
-            match String::from_utf8(bytes) {
-                Ok(s) => s,
-                Err(msg) => panic!("Found non valid utf8 characters in {} : {}.", path.display(), msg)
-            }
-        },
-        Err(msg) => panic!("Error reading file {} : {}.", path.display(), msg)
+/// Read a text file through the vfs, so it doesn't depend on the current
+/// directory. Panics on errors, see read_to_string
+pub fn read_file(path_str: &str) -> String {
+    match read_to_string(path_str) {
+        Ok(s) => s,
+        Err(msg) => panic!("Error reading file : {}.", msg)
     }
-}
\ No newline at end of file
+}
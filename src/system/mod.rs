@@ -3,4 +3,9 @@
 extern crate rustc_serialize;
 
 pub mod filesystem;
-pub mod config;
\ No newline at end of file
+pub mod vfs;
+pub mod asset;
+pub mod worker;
+pub mod config;
+pub mod cvar;
+pub mod console;
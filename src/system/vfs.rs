@@ -0,0 +1,562 @@
+//! Virtual file system : directories and zip archives mounted at virtual
+//! prefixes. A path is looked up in the mounts from the highest priority to
+//! the lowest, so a patch archive can shadow the base files. Virtual paths
+//! use '/' and are relative, "data/shaders/test.vs".
+//!
+//! Archives are zip files, stored or deflated. .pak is just another
+//! extension for them.
+
+extern crate inflate;
+
+use self::inflate::DeflateDecoder;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// An entry of list_dir
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Files mounted in the VFS. Paths are normalized and relative to the mount
+/// point, "" being its root
+pub trait MountSource: Send + Sync {
+    fn is_file(&self, path: &str) -> bool;
+    fn is_dir(&self, path: &str) -> bool;
+    fn open(&self, path: &str) -> io::Result<Box<dyn Read + Send>>;
+    fn list_dir(&self, path: &str) -> io::Result<Vec<DirEntry>>;
+
+    fn read_bytes(&self, path: &str) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.open(path)?.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Path on disk, for loose files
+    fn real_path(&self, _path: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Clean up a virtual path : '/' separators, no empty, "." or ".."
+/// components. None if it goes above the root
+pub fn normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split(|c| c == '/' || c == '\\') {
+        match part {
+            "" | "." => {}
+            ".." => { parts.pop()?; }
+            _ => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+fn not_found(path: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path))
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// A directory on disk
+pub struct Directory {
+    root: PathBuf,
+}
+
+impl Directory {
+    pub fn new<P: AsRef<Path>>(root: P) -> io::Result<Directory> {
+        let root = root.as_ref().to_path_buf();
+        if !root.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} is not a directory", root.display())));
+        }
+        Ok(Directory { root: root })
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        if path.is_empty() { self.root.clone() } else { self.root.join(path) }
+    }
+}
+
+impl MountSource for Directory {
+    fn is_file(&self, path: &str) -> bool {
+        self.path(path).is_file()
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        self.path(path).is_dir()
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(File::open(self.path(path))?))
+    }
+
+    fn list_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(self.path(path))? {
+            let entry = entry?;
+            entries.push(DirEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                is_dir: entry.file_type()?.is_dir(),
+            });
+        }
+        Ok(entries)
+    }
+
+    fn real_path(&self, path: &str) -> Option<PathBuf> {
+        Some(self.path(path))
+    }
+}
+
+/// Table driven crc32 of zip files
+fn crc32_table() -> &'static [u32; 256] {
+    static TABLE: OnceLock<[u32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut c = i as u32;
+            for _ in 0..8 {
+                c = if c & 1 != 0 { 0xEDB88320 ^ (c >> 1) } else { c >> 1 };
+            }
+            *entry = c;
+        }
+        table
+    })
+}
+
+fn crc32_update(crc: u32, bytes: &[u8]) -> u32 {
+    let table = crc32_table();
+    let mut c = !crc;
+    for b in bytes {
+        c = table[((c ^ *b as u32) & 0xFF) as usize] ^ (c >> 8);
+    }
+    !c
+}
+
+/// Checks the size and crc of an entry once it is read to the end
+struct CheckedReader<R> {
+    inner: R,
+    name: String,
+    crc: u32,
+    expected_crc: u32,
+    remaining: u64,
+}
+
+impl<R: Read> Read for CheckedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n as u64 > self.remaining {
+            return Err(invalid_data(format!("{} is larger than its recorded size", self.name)));
+        }
+        self.crc = crc32_update(self.crc, &buf[..n]);
+        self.remaining -= n as u64;
+        if n == 0 && !buf.is_empty() {
+            if self.remaining != 0 {
+                return Err(invalid_data(format!("{} is truncated", self.name)));
+            }
+            if self.crc != self.expected_crc {
+                return Err(invalid_data(format!("{} is corrupted, bad crc", self.name)));
+            }
+        }
+        Ok(n)
+    }
+}
+
+struct ZipEntry {
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    header_offset: u64,
+}
+
+fn u16_at(b: &[u8], i: usize) -> u16 {
+    b[i] as u16 | (b[i + 1] as u16) << 8
+}
+
+fn u32_at(b: &[u8], i: usize) -> u32 {
+    u16_at(b, i) as u32 | (u16_at(b, i + 2) as u32) << 16
+}
+
+/// A zip archive. The central directory is read once, entries are read
+/// from the file when opened
+pub struct ZipArchive {
+    path: PathBuf,
+    files: BTreeMap<String, ZipEntry>,
+    dirs: BTreeSet<String>,
+}
+
+impl ZipArchive {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<ZipArchive> {
+        let path = path.as_ref().to_path_buf();
+        let mut f = File::open(&path)?;
+        let len = f.seek(SeekFrom::End(0))?;
+        let bad = |msg: &str| invalid_data(format!("{} is not a valid zip archive: {}", path.display(), msg));
+
+        // the end of central directory record is in the last 64k, after the comment
+        let tail_len = len.min(0xFFFF + 22);
+        let mut tail = vec![0; tail_len as usize];
+        f.seek(SeekFrom::Start(len - tail_len))?;
+        f.read_exact(&mut tail)?;
+        let eocd = (0..tail.len().saturating_sub(21)).rev()
+            .find(|&i| u32_at(&tail, i) == 0x06054b50)
+            .ok_or_else(|| bad("no end of central directory"))?;
+        let count = u16_at(&tail, eocd + 10) as usize;
+        let cd_size = u32_at(&tail, eocd + 12) as u64;
+        let cd_offset = u32_at(&tail, eocd + 16) as u64;
+        if cd_offset == 0xFFFFFFFF || cd_offset + cd_size > len {
+            return Err(bad("zip64 or bad central directory offset"));
+        }
+
+        let mut cd = vec![0; cd_size as usize];
+        f.seek(SeekFrom::Start(cd_offset))?;
+        f.read_exact(&mut cd)?;
+
+        let mut files = BTreeMap::new();
+        let mut dirs = BTreeSet::new();
+        dirs.insert(String::new());
+        let mut i = 0;
+        for _ in 0..count {
+            if i + 46 > cd.len() || u32_at(&cd, i) != 0x02014b50 {
+                return Err(bad("bad central directory entry"));
+            }
+            let flags = u16_at(&cd, i + 8);
+            let method = u16_at(&cd, i + 10);
+            let name_len = u16_at(&cd, i + 28) as usize;
+            let extra_len = u16_at(&cd, i + 30) as usize;
+            let comment_len = u16_at(&cd, i + 32) as usize;
+            if i + 46 + name_len > cd.len() {
+                return Err(bad("bad central directory entry"));
+            }
+            let raw_name = String::from_utf8_lossy(&cd[i + 46..i + 46 + name_len]).into_owned();
+            let entry = ZipEntry {
+                method: method,
+                crc: u32_at(&cd, i + 16),
+                compressed_size: u32_at(&cd, i + 20) as u64,
+                size: u32_at(&cd, i + 24) as u64,
+                header_offset: u32_at(&cd, i + 42) as u64,
+            };
+            i += 46 + name_len + extra_len + comment_len;
+
+            let name = match normalize(&raw_name) {
+                Some(ref name) if !name.is_empty() => name.clone(),
+                _ => continue,
+            };
+            // parent directories, which zip files don't always list
+            let mut parent = name.as_str();
+            while let Some(pos) = parent.rfind('/') {
+                parent = &parent[..pos];
+                dirs.insert(parent.to_string());
+            }
+            if raw_name.ends_with('/') {
+                dirs.insert(name);
+                continue;
+            }
+            if flags & 1 != 0 {
+                println!("VFS warning: skipping encrypted file {} in {}", name, path.display());
+                continue;
+            }
+            files.insert(name, entry);
+        }
+
+        Ok(ZipArchive { path: path, files: files, dirs: dirs })
+    }
+}
+
+impl MountSource for ZipArchive {
+    fn is_file(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        self.dirs.contains(path)
+    }
+
+    /// Entries are decompressed while being read
+    fn open(&self, path: &str) -> io::Result<Box<dyn Read + Send>> {
+        let entry = self.files.get(path).ok_or_else(|| not_found(path))?;
+        let name = format!("{} in {}", path, self.path.display());
+
+        let mut f = File::open(&self.path)?;
+        let mut header = [0; 30];
+        f.seek(SeekFrom::Start(entry.header_offset))?;
+        f.read_exact(&mut header)?;
+        if u32_at(&header, 0) != 0x04034b50 {
+            return Err(invalid_data(format!("{} has a bad local header", name)));
+        }
+        let skip = u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64;
+        f.seek(SeekFrom::Current(skip))?;
+        let data = f.take(entry.compressed_size);
+
+        let inner: Box<dyn Read + Send> = match entry.method {
+            0 => Box::new(data),
+            8 => Box::new(DeflateDecoder::new(data)),
+            m => return Err(io::Error::new(io::ErrorKind::Other,
+                                           format!("{} uses the unsupported compression method {}", name, m))),
+        };
+        Ok(Box::new(CheckedReader {
+            inner: inner,
+            name: name,
+            crc: 0,
+            expected_crc: entry.crc,
+            remaining: entry.size,
+        }))
+    }
+
+    fn list_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        if !self.dirs.contains(path) {
+            return Err(not_found(path));
+        }
+        let prefix = if path.is_empty() { String::new() } else { format!("{}/", path) };
+        let direct_child = |name: &String| name.starts_with(&prefix) && name.len() > prefix.len() &&
+                                           !name[prefix.len()..].contains('/');
+
+        let mut entries = Vec::new();
+        for name in self.dirs.iter().filter(|name| direct_child(name)) {
+            entries.push(DirEntry { name: name[prefix.len()..].to_string(), is_dir: true });
+        }
+        for name in self.files.keys().filter(|name| direct_child(name)) {
+            entries.push(DirEntry { name: name[prefix.len()..].to_string(), is_dir: false });
+        }
+        Ok(entries)
+    }
+}
+
+struct Mount {
+    prefix: String,
+    priority: i32,
+    source: Box<dyn MountSource>,
+}
+
+impl Mount {
+    /// The path relative to this mount point, if it is under it
+    fn relative<'a>(&self, path: &'a str) -> Option<&'a str> {
+        if self.prefix.is_empty() {
+            Some(path)
+        } else if path == self.prefix {
+            Some("")
+        } else if path.starts_with(&self.prefix) && path[self.prefix.len()..].starts_with('/') {
+            Some(&path[self.prefix.len() + 1..])
+        } else {
+            None
+        }
+    }
+}
+
+pub struct Vfs {
+    /// Highest priority first
+    mounts: Vec<Mount>,
+}
+
+impl Vfs {
+    pub fn new() -> Vfs {
+        Vfs { mounts: Vec::new() }
+    }
+
+    /// The base directory mounted at the root, and the archives of its data
+    /// directory mounted at "data" above it, in name order so that
+    /// patch2.pak overrides patch1.pak
+    pub fn with_defaults() -> Vfs {
+        let mut vfs = Vfs::new();
+        let base = base_dir();
+        if let Err(err) = vfs.mount_dir("", &base, 0) {
+            println!("VFS warning: can't mount {}: {}", base.display(), err);
+        }
+
+        let mut archives: Vec<PathBuf> = match fs::read_dir(base.join("data")) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| is_archive(p)).collect(),
+            Err(_) => Vec::new(),
+        };
+        archives.sort();
+        for archive in archives {
+            if let Err(err) = vfs.mount_archive("data", &archive, 1) {
+                println!("VFS warning: can't mount {}: {}", archive.display(), err);
+            }
+        }
+        vfs
+    }
+
+    /// Mount a source at a virtual prefix. Mounts of higher priority shadow
+    /// the others, the last one mounted wins on equal priority
+    pub fn mount(&mut self, prefix: &str, priority: i32, source: Box<dyn MountSource>) {
+        let prefix = normalize(prefix).unwrap_or_default();
+        let pos = self.mounts.iter().position(|m| m.priority <= priority).unwrap_or(self.mounts.len());
+        self.mounts.insert(pos, Mount { prefix: prefix, priority: priority, source: source });
+    }
+
+    pub fn mount_dir<P: AsRef<Path>>(&mut self, prefix: &str, dir: P, priority: i32) -> io::Result<()> {
+        let dir = Directory::new(dir)?;
+        self.mount(prefix, priority, Box::new(dir));
+        Ok(())
+    }
+
+    pub fn mount_archive<P: AsRef<Path>>(&mut self, prefix: &str, archive: P, priority: i32) -> io::Result<()> {
+        let archive = ZipArchive::open(archive)?;
+        self.mount(prefix, priority, Box::new(archive));
+        Ok(())
+    }
+
+    /// Remove the mounts at a prefix
+    pub fn unmount(&mut self, prefix: &str) {
+        let prefix = normalize(prefix).unwrap_or_default();
+        self.mounts.retain(|m| m.prefix != prefix);
+    }
+
+    /// The mount a file comes from, with its path in the mount
+    fn find<'a>(&'a self, path: &str) -> io::Result<(&'a Mount, String)> {
+        let path = normalize(path).ok_or_else(|| not_found(path))?;
+        for mount in self.mounts.iter() {
+            if let Some(rel) = mount.relative(&path) {
+                if mount.source.is_file(rel) {
+                    return Ok((mount, rel.to_string()));
+                }
+            }
+        }
+        Err(not_found(&path))
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.find(path).is_ok() || self.is_dir(path)
+    }
+
+    pub fn is_dir(&self, path: &str) -> bool {
+        let path = match normalize(path) {
+            Some(path) => path,
+            None => return false,
+        };
+        self.mounts.iter().any(|m| {
+            m.relative(&path).map_or(false, |rel| m.source.is_dir(rel)) ||
+                (path.is_empty() || m.prefix.starts_with(&format!("{}/", path)))
+        })
+    }
+
+    /// Streaming reader on a file
+    pub fn open(&self, path: &str) -> io::Result<Box<dyn Read + Send>> {
+        let (mount, rel) = self.find(path)?;
+        mount.source.open(&rel)
+    }
+
+    pub fn read_bytes(&self, path: &str) -> io::Result<Vec<u8>> {
+        let (mount, rel) = self.find(path)?;
+        mount.source.read_bytes(&rel)
+    }
+
+    pub fn read_to_string(&self, path: &str) -> io::Result<String> {
+        String::from_utf8(self.read_bytes(path)?)
+            .map_err(|err| invalid_data(format!("{} is not valid utf8: {}", path, err)))
+    }
+
+    /// Files and directories of a directory, merged from every mount and
+    /// sorted by name
+    pub fn list_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        let path = normalize(path).ok_or_else(|| not_found(path))?;
+        let mut entries: BTreeMap<String, bool> = BTreeMap::new();
+        let mut found = false;
+        for mount in self.mounts.iter() {
+            if let Some(rel) = mount.relative(&path) {
+                if mount.source.is_dir(rel) {
+                    found = true;
+                    for entry in mount.source.list_dir(rel)? {
+                        *entries.entry(entry.name).or_insert(false) |= entry.is_dir;
+                    }
+                }
+            }
+
+            // mount points appear as directories of their parent
+            let parent = if path.is_empty() { String::new() } else { format!("{}/", path) };
+            if !mount.prefix.is_empty() && mount.prefix.starts_with(&parent) {
+                found = true;
+                let name = mount.prefix[parent.len()..].split('/').next().unwrap();
+                entries.insert(name.to_string(), true);
+            }
+        }
+        if !found {
+            return Err(not_found(&path));
+        }
+        Ok(entries.into_iter().map(|(name, is_dir)| DirEntry { name: name, is_dir: is_dir }).collect())
+    }
+
+    /// Path on disk of a loose file, for the code that needs one (file
+    /// watching)
+    pub fn real_path(&self, path: &str) -> Option<PathBuf> {
+        self.find(path).ok().and_then(|(mount, rel)| mount.source.real_path(&rel))
+    }
+}
+
+fn is_archive(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => ext.eq_ignore_ascii_case("pak") || ext.eq_ignore_ascii_case("zip"),
+        None => false,
+    }
+}
+
+/// Environment variable overriding base_dir. Config::add_env skips it, it
+/// isn't a config value
+pub const BASE_DIR_VAR: &'static str = "RADAR_BASE_DIR";
+
+/// Directory the data directory is in : $RADAR_BASE_DIR, or the current
+/// directory if it has one, or the first parent of the executable with one
+pub fn base_dir() -> PathBuf {
+    if let Some(dir) = env::var_os(BASE_DIR_VAR) {
+        return PathBuf::from(dir);
+    }
+    let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    if cwd.join("data").is_dir() {
+        return cwd;
+    }
+    if let Ok(exe) = env::current_exe() {
+        if let Some(dir) = exe.ancestors().skip(1).find(|dir| dir.join("data").is_dir()) {
+            return dir.to_path_buf();
+        }
+    }
+    cwd
+}
+
+/// The VFS used by the asset loaders, created with the default mounts on
+/// first use
+pub fn global() -> &'static RwLock<Vfs> {
+    static VFS: OnceLock<RwLock<Vfs>> = OnceLock::new();
+    VFS.get_or_init(|| RwLock::new(Vfs::with_defaults()))
+}
+
+pub fn mount_dir<P: AsRef<Path>>(prefix: &str, dir: P, priority: i32) -> io::Result<()> {
+    global().write().unwrap().mount_dir(prefix, dir, priority)
+}
+
+pub fn mount_archive<P: AsRef<Path>>(prefix: &str, archive: P, priority: i32) -> io::Result<()> {
+    global().write().unwrap().mount_archive(prefix, archive, priority)
+}
+
+pub fn exists(path: &str) -> bool {
+    global().read().unwrap().exists(path)
+}
+
+pub fn open(path: &str) -> io::Result<Box<dyn Read + Send>> {
+    global().read().unwrap().open(path)
+}
+
+pub fn read_bytes(path: &str) -> io::Result<Vec<u8>> {
+    global().read().unwrap().read_bytes(path)
+}
+
+pub fn read_to_string(path: &str) -> io::Result<String> {
+    global().read().unwrap().read_to_string(path)
+}
+
+pub fn list_dir(path: &str) -> io::Result<Vec<DirEntry>> {
+    global().read().unwrap().list_dir(path)
+}
+
+pub fn real_path(path: &str) -> Option<PathBuf> {
+    global().read().unwrap().real_path(path)
+}
@@ -0,0 +1,56 @@
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Fixed number of threads running jobs in the order they are queued.
+/// Dropping the pool waits for the queued jobs
+pub struct WorkerPool {
+    jobs: Option<mpsc::Sender<Job>>,
+    threads: Vec<thread::JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub fn new(name: &str, count: usize) -> WorkerPool {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let mut threads = Vec::new();
+        for i in 0..count.max(1) {
+            let receiver = receiver.clone();
+            let thread = thread::Builder::new()
+                .name(format!("{} {}", name, i))
+                .spawn(move || loop {
+                    // the lock is released before running the job
+                    let job = match receiver.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    job();
+                })
+                .expect("Failed to spawn worker thread.");
+            threads.push(thread);
+        }
+
+        WorkerPool { jobs: Some(sender), threads: threads }
+    }
+
+    pub fn size(&self) -> usize {
+        self.threads.len()
+    }
+
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        self.jobs.as_ref().unwrap().send(Box::new(job)).expect("Worker threads stopped.");
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // closing the channel stops the threads once the queue is empty
+        self.jobs.take();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}